@@ -9,13 +9,13 @@ extern crate coinnect;
 
 use coinnect::coinnect::Coinnect;
 use coinnect::exchange::Exchange::*;
-use coinnect::pair::Pair::*;
+use coinnect::pair::Pair;
 
 fn main() {
     // We create a Coinnect Generic API
     // Since Kraken does not need customer_id field, we set it to None
-    let mut my_api = Coinnect::new(Kraken, "api_key", "api_secret", None);
-    let ticker = my_api.ticker(ETC_BTC);
+    let mut my_api = Coinnect::new(Kraken, "api_key", "api_secret", None).unwrap();
+    let ticker = my_api.ticker(Pair::ETC_BTC);
 
     println!("ETC_BTC last trade price is {}.",
              ticker.unwrap().last_trade_price);
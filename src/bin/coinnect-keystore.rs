@@ -0,0 +1,138 @@
+//! Manage the accounts of an encrypted keystore (see `coinnect::keystore`).
+//!
+//! Usage:
+//!
+//! - `coinnect-keystore <keystore> list`
+//! - `coinnect-keystore <keystore> add <name> <exchange>`
+//! - `coinnect-keystore <keystore> remove <name>`
+//!
+//! The passphrase is read from the `COINNECT_KEYSTORE_PASSPHRASE` environment variable, or from
+//! the standard input. `add` reads the API key, secret and the exchange specific fields from the
+//! standard input, so that they never appear in the shell history or in the process list. The
+//! passphrase and the secrets are not echoed when typed in a terminal. The keystore is created by
+//! the first `add` if it does not exist.
+
+extern crate coinnect;
+extern crate rpassword;
+
+use coinnect::credentials::Secret;
+use coinnect::error::{Error, ErrorKind};
+use coinnect::exchange::Exchange;
+use coinnect::keystore::Keystore;
+
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &'static str = "Usage: coinnect-keystore <keystore> list
+       coinnect-keystore <keystore> add <name> <exchange>
+       coinnect-keystore <keystore> remove <name>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match (args.len(), args.get(1).map(|command| command.as_str())) {
+        (2, Some("list")) => list(PathBuf::from(&args[0])),
+        (4, Some("add")) => add(PathBuf::from(&args[0]), &args[2], &args[3]),
+        (3, Some("remove")) => remove(PathBuf::from(&args[0]), &args[2]),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn list(path: PathBuf) -> Result<(), Error> {
+    let keystore = Keystore::open(path, passphrase()?.expose())?;
+    for name in keystore.names() {
+        match keystore.account(name) {
+            Ok(account) => println!("{} ({:?})", name, account.exchange()),
+            Err(e) => println!("{} (invalid: {})", name, e),
+        }
+    }
+    Ok(())
+}
+
+fn add(path: PathBuf, name: &str, exchange: &str) -> Result<(), Error> {
+    let exchange = exchange.parse::<Exchange>()?;
+    let passphrase = passphrase()?;
+    let mut keystore = if path.exists() {
+        Keystore::open(path, passphrase.expose())?
+    } else {
+        Keystore::create(path, passphrase.expose())
+    };
+
+    let mut fields = HashMap::new();
+    fields.insert("exchange".to_string(),
+                  Secret::new(&format!("{:?}", exchange).to_lowercase()));
+    fields.insert("api_key".to_string(), prompt("API key")?);
+    fields.insert("api_secret".to_string(), prompt_secret("API secret")?);
+    match exchange {
+        Exchange::Bitstamp => {
+            fields.insert("customer_id".to_string(), prompt("Customer id")?);
+        }
+        Exchange::Kraken => {
+            let otp_secret = prompt_secret("Two-factor TOTP secret (empty if none)")?;
+            if !otp_secret.is_empty() {
+                fields.insert("otp_secret".to_string(), otp_secret);
+            }
+        }
+        Exchange::Poloniex => (),
+    }
+
+    keystore.add(name, fields)?;
+    keystore.save()?;
+    println!("Account {} saved to {}", name, keystore.path().display());
+    Ok(())
+}
+
+fn remove(path: PathBuf, name: &str) -> Result<(), Error> {
+    let mut keystore = Keystore::open(path, passphrase()?.expose())?;
+    if !keystore.remove(name) {
+        println!("No account {} in {}", name, keystore.path().display());
+        return Ok(());
+    }
+    keystore.save()?;
+    println!("Account {} removed from {}", name, keystore.path().display());
+    Ok(())
+}
+
+fn passphrase() -> Result<Secret, Error> {
+    match env::var("COINNECT_KEYSTORE_PASSPHRASE") {
+        Ok(passphrase) => Ok(Secret::from(passphrase)),
+        Err(_) => prompt_secret("Passphrase"),
+    }
+}
+
+/// Read a line of the standard input, after printing the label specified on the standard error.
+fn prompt(label: &str) -> Result<Secret, Error> {
+    eprint!("{}: ", label);
+    let _ = io::stderr().flush();
+
+    let mut line = String::new();
+    let stdin = io::stdin();
+    if let Err(e) = stdin.lock().read_line(&mut line) {
+        return Err(stdin_error(e));
+    }
+    let line = Secret::from(line);
+    Ok(Secret::new(line.expose().trim_right_matches(|c| c == '\r' || c == '\n')))
+}
+
+/// Same as `prompt`, without echoing the line if the standard input is a terminal.
+fn prompt_secret(label: &str) -> Result<Secret, Error> {
+    eprint!("{}: ", label);
+    let _ = io::stderr().flush();
+
+    rpassword::read_password().map(Secret::from).map_err(stdin_error)
+}
+
+fn stdin_error(source: io::Error) -> Error {
+    let reason = "Cannot read the standard input".to_string();
+    Error::new(ErrorKind::ConfigError(reason)).with_source(source)
+}
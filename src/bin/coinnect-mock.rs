@@ -0,0 +1,76 @@
+//! Mock exchange server emulating the Kraken, Poloniex and Bitstamp REST APIs.
+//!
+//! Usage: `coinnect-mock [address]` (default address: 127.0.0.1:8080)
+//!
+//! The server accepts the credentials given by the `COINNECT_MOCK_API_KEY`,
+//! `COINNECT_MOCK_API_SECRET` and `COINNECT_MOCK_CUSTOMER_ID` environment variables, or the
+//! defaults of `coinnect::mock_server`.
+
+extern crate coinnect;
+extern crate hyper;
+
+use coinnect::mock_server::{MockServer, DEFAULT_API_KEY, DEFAULT_API_SECRET, DEFAULT_CUSTOMER_ID};
+use coinnect::transport::{HttpRequest, Method};
+
+use hyper::server::{Server, Request, Response};
+use hyper::status::StatusCode;
+use hyper::uri::RequestUri;
+
+use std::env;
+use std::io::Read;
+use std::process;
+
+fn main() {
+    let address = env::args().nth(1).unwrap_or("127.0.0.1:8080".to_string());
+    let api_key = env::var("COINNECT_MOCK_API_KEY").unwrap_or(DEFAULT_API_KEY.to_string());
+    let api_secret = env::var("COINNECT_MOCK_API_SECRET")
+        .unwrap_or(DEFAULT_API_SECRET.to_string());
+    let customer_id = env::var("COINNECT_MOCK_CUSTOMER_ID")
+        .unwrap_or(DEFAULT_CUSTOMER_ID.to_string());
+
+    let server = MockServer::new(&api_key, &api_secret, &customer_id);
+
+    let listening = match Server::http(address.as_str()) {
+        Ok(http) => {
+            http.handle(move |mut request: Request, mut response: Response| {
+                    let mut body = String::new();
+                    let _ = request.read_to_string(&mut body);
+                    let path = match request.uri {
+                        RequestUri::AbsolutePath(ref path) => path.clone(),
+                        ref uri => uri.to_string(),
+                    };
+                    let method = match request.method {
+                        hyper::method::Method::Post => Method::Post,
+                        _ => Method::Get,
+                    };
+
+                    let mut mock_request = HttpRequest {
+                        method: method,
+                        url: path,
+                        headers: Vec::new(),
+                        body: body,
+                    };
+                    for header in request.headers.iter() {
+                        mock_request = mock_request.with_header(header.name(),
+                                                                &header.value_string());
+                    }
+
+                    let mock_response = server.handle(&mock_request);
+                    *response.status_mut() = StatusCode::from_u16(mock_response.status);
+                    let _ = response.send(mock_response.body.as_bytes());
+                })
+        }
+        Err(e) => {
+            eprintln!("Could not listen on {}: {}", address, e);
+            process::exit(1);
+        }
+    };
+
+    match listening {
+        Ok(_) => println!("coinnect-mock listening on http://{}", address),
+        Err(e) => {
+            eprintln!("Could not start the server: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -2,53 +2,67 @@
 //! Use this module to interact with Bitstamp exchange.
 //! Please see examples for more informations.
 
-use hyper_native_tls::NativeTlsClient;
-use hyper::Client;
-use hyper::header::ContentType;
-use hyper::net::HttpsConnector;
-
-use serde_json;
 use serde_json::Value;
 use serde_json::value::Map;
 
 use std::collections::HashMap;
-use std::io::Read;
 use std::path::PathBuf;
-use std::fs::File;
 
+use config::ClientConfig;
+use credentials::{Account, Credentials};
 use error;
+use error::ResponseContext;
+use exchange::Exchange;
 use helpers;
+use market::MarketCache;
+use nonce::NonceGenerator;
+use rate_limiter::RateLimiter;
+use retry::RetryPolicy;
+use scope::Scope;
+use transport::{Transport, HttpsTransport, HttpRequest};
+use validator::OrderValidator;
 use bitstamp::utils;
 use pair::Pair;
 
-header! {
-    #[doc(hidden)]
-    (KeyHeader, "Key") => [String]
-}
-
-header! {
-    #[doc(hidden)]
-    (SignHeader, "Sign") => [String]
-}
-
-header! {
-    #[doc(hidden)]
-    (ContentHeader, "Content-Type") => [String]
-}
-
 #[derive(Debug)]
 pub struct BitstampApi {
-    last_request: i64, // unix timestamp in ms, to avoid ban
-    api_key: String,
-    api_secret: String,
+    credentials: Credentials,
     customer_id: String,
-    http_client: Client,
+    base_url: String,
+    rate_limiter: RateLimiter,
+    private_rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
+    nonce_generator: NonceGenerator,
+    scope: Scope,
+    market_cache: MarketCache,
+    order_validator: OrderValidator,
+    last_response: Option<ResponseContext>,
+    transport: Box<Transport>,
 }
 
 
 impl BitstampApi {
     /// Create a new BitstampApi by providing an API key & API secret
-    pub fn new(params: &HashMap<&str, &str>) -> BitstampApi {
+    pub fn new(params: &HashMap<&str, &str>) -> Result<BitstampApi, error::Error> {
+        Ok(BitstampApi::with_transport(params, HttpsTransport::new()?))
+    }
+
+    /// Create a new BitstampApi sending its requests through the Transport specified instead of
+    /// HTTPS. This is mostly useful to test the client with a `MockTransport`.
+    pub fn with_transport<T>(params: &HashMap<&str, &str>, transport: T) -> BitstampApi
+        where T: Transport + 'static
+    {
+        BitstampApi::with_config(params, ClientConfig::default(), transport)
+    }
+
+    /// Create a new BitstampApi using the configuration specified (base URL, ...) and sending
+    /// its requests through the Transport specified.
+    pub fn with_config<T>(params: &HashMap<&str, &str>,
+                          config: ClientConfig,
+                          transport: T)
+                          -> BitstampApi
+        where T: Transport + 'static
+    {
         let mut params = params.clone();
         helpers::strip_empties(&mut params);
 
@@ -58,20 +72,27 @@ impl BitstampApi {
         let api_secret = params.get("api_secret").unwrap_or(&empty_str);
         let customer_id = params.get("customer_id").unwrap_or(&empty_str);
 
-        let ssl = NativeTlsClient::new().unwrap();
-        let connector = HttpsConnector::new(ssl);
+        let rate_limiter = config.rate_limiter_or(utils::rate_limiter());
+        let private_rate_limiter = config.private_rate_limiter_or(rate_limiter.clone());
 
         BitstampApi {
-            last_request: 0,
-            api_key: api_key.to_string(),
-            api_secret: api_secret.to_string(),
+            credentials: Credentials::new(api_key, api_secret),
             customer_id: customer_id.to_string(),
-            http_client: Client::with_connector(connector),
+            base_url: config.base_url_or(utils::BASE_URL),
+            rate_limiter: rate_limiter,
+            private_rate_limiter: private_rate_limiter,
+            retry_policy: config.retry_policy_or(RetryPolicy::new()),
+            nonce_generator: config.nonce_generator_or(NonceGenerator::shared()),
+            scope: config.scope_or(Scope::default()),
+            market_cache: config.market_cache_or(MarketCache::default()),
+            order_validator: config.order_validator_or(OrderValidator::default()),
+            last_response: None,
+            transport: Box::new(transport),
         }
     }
 
-    /// Create a new BitstampApi from a json configuration file. This file must follow this
-    /// structure:
+    /// Create a new BitstampApi from a JSON or TOML configuration file (see the `credentials`
+    /// module). A JSON file must follow this structure:
     ///
     /// ```ignore
     /// {
@@ -90,38 +111,90 @@ impl BitstampApi {
     /// ```
     /// For this example, you could use load your Bitstamp account with
     /// `new_from_file("account_bitstamp", Path::new("/keys.json"))`
-    pub fn new_from_file(config_name: &str, path: PathBuf) -> BitstampApi {
-        let mut f = File::open(&path).unwrap();
-        let mut buffer = String::new();
-        f.read_to_string(&mut buffer).unwrap();
-
-        let data: Value = serde_json::from_str(&buffer).unwrap();
-        let json_obj = data.as_object().unwrap().get(config_name).unwrap();
-        let api_key = json_obj.get("api_key").unwrap().as_str().unwrap();
-        let api_secret = json_obj.get("api_secret").unwrap().as_str().unwrap();
-        let customer_id = json_obj.get("customer_id").unwrap().as_str().unwrap();
+    pub fn new_from_file(config_name: &str, path: PathBuf) -> Result<BitstampApi, error::Error> {
+        let account = Account::from_exchange_file(Exchange::Bitstamp, config_name, &path)?;
+        let credentials = account.credentials();
 
         let mut params = HashMap::new();
-        params.insert("api_key", api_key);
-        params.insert("api_secret", api_secret);
-        params.insert("customer_id", customer_id);
+        params.insert("api_key", credentials.api_key());
+        params.insert("api_secret", credentials.api_secret());
+        params.insert("customer_id", account.customer_id().unwrap_or(""));
         BitstampApi::new(&params)
     }
 
+    /// Return the cache of the markets listed by Bitstamp (see `ExchangeApi::markets`).
+    pub fn market_cache(&self) -> &MarketCache {
+        &self.market_cache
+    }
+
+    /// Return the validator of the orders placed through the generic API.
+    pub fn order_validator(&self) -> &OrderValidator {
+        &self.order_validator
+    }
+
+    /// Return the endpoint called by the last request and the response received, if any. The
+    /// generic API attaches them to the errors raised while reading the response.
+    pub fn last_response(&self) -> Option<&ResponseContext> {
+        self.last_response.as_ref()
+    }
+
+    /// Send a request to the endpoint specified and deserialize the response. Errors carry the
+    /// endpoint, and the status and body of the response when one was received.
+    fn send(&mut self,
+            endpoint: &str,
+            request: &HttpRequest)
+            -> Result<Map<String, Value>, error::Error> {
+        let response = self.transport
+            .send(request)
+            .map_err(|e| e.with_endpoint(Exchange::Bitstamp, endpoint))?;
+        self.last_response = Some(ResponseContext::new(Exchange::Bitstamp,
+                                                       endpoint,
+                                                       response.status,
+                                                       &response.body));
+        utils::deserialize_json(response.body.clone()).map_err(|e| {
+            e.with_endpoint(Exchange::Bitstamp, endpoint)
+                .with_response(response.status, &response.body)
+        })
+    }
+
     fn public_query(&mut self,
                     params: &HashMap<&str, &str>)
                     -> Result<Map<String, Value>, error::Error> {
 
-        let method: &str = params.get("method").unwrap();
-        let pair: &str = params.get("pair").unwrap();
-        let url: String = utils::build_url(method, pair);
+        let method: &str = params.get("method").ok_or(error::ErrorKind::InvalidArguments)?;
+        let pair: &str = params.get("pair").unwrap_or(&"");
+        let mut url = utils::build_url(&self.base_url, method, pair);
+        let mut query = params.clone();
+        query.remove("method");
+        query.remove("pair");
+        helpers::strip_empties(&mut query);
+        if !query.is_empty() {
+            url = url + "?" + &helpers::url_encode_hashmap(&query);
+        }
+        self.public_query_url(method, &url)
+    }
 
-        utils::block_or_continue(self.last_request);
-        let mut response = self.http_client.get(&url).send().unwrap();
-        self.last_request = helpers::get_unix_timestamp_ms();
-        let mut buffer = String::new();
-        response.read_to_string(&mut buffer).unwrap();
-        return utils::deserialize_json(buffer);
+    /// Same as `public_query`, for the methods which only exist in the v1 API.
+    fn public_query_v1(&mut self,
+                       params: &HashMap<&str, &str>)
+                       -> Result<Map<String, Value>, error::Error> {
+
+        let method: &str = params.get("method").ok_or(error::ErrorKind::InvalidArguments)?;
+        let url = utils::build_url_v1(&self.base_url, method);
+        self.public_query_url(method, &url)
+    }
+
+    /// Send the public request of the method specified to the URL specified.
+    fn public_query_url(&mut self,
+                        method: &str,
+                        url: &str)
+                        -> Result<Map<String, Value>, error::Error> {
+        let retry_policy = self.retry_policy.clone();
+        retry_policy.run(|| {
+                             self.rate_limiter.acquire(1.0);
+                             self.send(method, &HttpRequest::get(url))
+                         },
+                         utils::is_retryable)
     }
 
     ///
@@ -139,55 +212,79 @@ impl BitstampApi {
                      params: &HashMap<&str, &str>)
                      -> Result<Map<String, Value>, error::Error> {
 
-        let method: &str = params.get("method").unwrap();
-        let pair: &str = params.get("pair").unwrap();
-        let url: String = utils::build_url(method, pair);
+        let method: &str = params.get("method").ok_or(error::ErrorKind::InvalidArguments)?;
+        let pair: &str = params.get("pair").unwrap_or(&"");
+        let url = utils::build_url(&self.base_url, method, pair);
+        self.private_query_url(method, &url, params)
+    }
+
+    /// Same as `private_query`, for the methods which only exist in the v1 API.
+    fn private_query_v1(&mut self,
+                        params: &HashMap<&str, &str>)
+                        -> Result<Map<String, Value>, error::Error> {
 
-        let nonce = utils::generate_nonce(None);
-        let signature = utils::build_signature(nonce.clone(),
-                                               self.customer_id.clone(),
-                                               self.api_key.clone(),
-                                               self.api_secret.clone());
+        let method: &str = params.get("method").ok_or(error::ErrorKind::InvalidArguments)?;
+        let url = utils::build_url_v1(&self.base_url, method);
+        self.private_query_url(method, &url, params)
+    }
+
+    /// Check the scope of the private method specified and send its request to the URL
+    /// specified.
+    fn private_query_url(&mut self,
+                         method: &str,
+                         url: &str,
+                         params: &HashMap<&str, &str>)
+                         -> Result<Map<String, Value>, error::Error> {
+        self.scope
+            .check(utils::required_scope(method))
+            .map_err(|e| e.with_endpoint(Exchange::Bitstamp, method))?;
+
+        let retry_policy = self.retry_policy.for_request(utils::is_read_only(method));
+        retry_policy.run(|| self.signed_query(method, url, params), utils::is_retryable)
+    }
+
+    /// Sign the private request specified with a new nonce and send it to the URL specified.
+    fn signed_query(&mut self,
+                    method: &str,
+                    url: &str,
+                    params: &HashMap<&str, &str>)
+                    -> Result<Map<String, Value>, error::Error> {
+        self.private_rate_limiter.acquire(1.0);
+        let nonce = self.nonce_generator.next()?.to_string();
+        let signature = utils::build_signature(&nonce,
+                                               &self.customer_id,
+                                               self.credentials.api_key(),
+                                               self.credentials.api_secret());
 
-        let copy_api_key = self.api_key.clone();
         let mut post_params: &mut HashMap<&str, &str> = &mut HashMap::new();
-        post_params.insert("key", &copy_api_key);
+        post_params.insert("key", self.credentials.api_key());
         post_params.insert("signature", &signature);
         post_params.insert("nonce", &nonce);
+        for (name, value) in params.iter() {
+            if *name != "method" && *name != "pair" {
+                post_params.insert(name, value);
+            }
+        }
         helpers::strip_empties(&mut post_params);
         let post_data = helpers::url_encode_hashmap(&post_params);
-        let mut response = self.http_client
-            .post(&url)
-            .header(ContentType::form_url_encoded())
-            .body(&post_data)
-            .send()
-            .unwrap();
+        let request = HttpRequest::post(url, &post_data)
+            .with_header("Content-Type", "application/x-www-form-urlencoded");
 
-        let mut buffer = String::new();
-        response.read_to_string(&mut buffer).unwrap();
-        utils::deserialize_json(buffer)
+        self.send(method, &request)
     }
 
     /// Sample output :
     ///
     /// ```ignore
-    /// {
-    /// "BTC_LTC":{
-    /// "last":"0.0251","lowestAsk":"0.02589999","highestBid":"0.0251",
-    /// "percentChange":"0.02390438","baseVolume":"6.16485315","quoteVolume":"245.82513926"},
-    /// "BTC_NXT":{
-    /// "last":"0.00005730","lowestAsk":"0.00005710","highestBid":"0.00004903",
-    /// "percentChange":"0.16701570","baseVolume":"0.45347489","quoteVolume":"9094"},
-    /// ... }
+    /// {"high": "1186.00", "last": "1177.57", "timestamp": "1491919199", "bid": "1177.57",
+    /// "vwap": "1169.89", "volume": "7127.60468436", "low": "1156.10", "ask": "1179.69",
+    /// "open": "1165.24"}
     /// ```
     pub fn return_ticker(&mut self, pair: Pair) -> Result<Map<String, Value>, error::Error> {
-        let currency_pair = match pair {
-            Pair::BTC_USD => "btcusd",
-            _ => panic!("Unknown pair"),
-        };
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
 
         let mut params = HashMap::new();
-        params.insert("pair", currency_pair);
+        params.insert("pair", currency_pair.as_str());
         params.insert("method", "ticker");
         self.public_query(&params)
     }
@@ -195,61 +292,451 @@ impl BitstampApi {
     /// Sample output :
     ///
     /// ```ignore
-    /// {"asks":[[0.00007600,1164],[0.00007620,1300], ... ], "bids":[[0.00006901,200],
-    /// [0.00006900,408], ... ], "timestamp": "1234567890"}
+    /// {"asks":[["1179.69","1.20000000"],["1180.00","0.56300000"], ... ],
+    /// "bids":[["1177.57","0.10000000"],["1177.00","2.00000000"], ... ], "timestamp": "1234567890"}
     /// ```
     pub fn return_order_book(&mut self, pair: Pair) -> Result<Map<String, Value>, error::Error> {
-
-        let currency_pair = match pair {
-            Pair::BTC_USD => "btcusd",
-            _ => panic!("Unknown pair"),
-        };
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
 
         let mut params = HashMap::new();
         params.insert("method", "order_book");
-        params.insert("pair", currency_pair);
+        params.insert("pair", currency_pair.as_str());
         self.public_query(&params)
     }
 
+    /// Bitstamp returns an array, which is wrapped in an object under the "data" key.
+    ///
     /// Sample output :
     ///
     /// ```ignore
-    /// [{"date":"2014-02-10 04:23:23","type":"buy","rate":"0.00007600","amount":"140",
-    /// "total":"0.01064"},
-    /// {"date":"2014-02-10 01:19:37","type":"buy","rate":"0.00007600","amount":"655",
-    /// "total":"0.04978"}, ... ]
+    /// {"data": [{"date":"1491919199","tid":"13526279","price":"1170.00","type":"0",
+    /// "amount":"0.10000000"},
+    /// {"date":"1491919186","tid":"13526278","price":"1169.99","type":"1",
+    /// "amount":"1.20000000"}, ... ]}
     /// ```
-    pub fn return_trade_history(&mut self, pair: Pair) -> Result<Map<String, Value>, error::Error> {
-
-        let currency_pair = match pair {
-            Pair::BTC_USD => "btcusd",
-            _ => panic!("Unknown pair"),
-        };
+    ///
+    /// `time` is the period of the trades returned: "minute", "hour" or "day" (empty for the
+    /// default, "hour").
+    pub fn return_trade_history(&mut self,
+                                pair: Pair,
+                                time: &str)
+                                -> Result<Map<String, Value>, error::Error> {
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
 
         let mut params = HashMap::new();
-        params.insert("pair", currency_pair);
+        params.insert("pair", currency_pair.as_str());
         params.insert("method", "transactions");
+        params.insert("time", time);
+        self.public_query(&params)
+    }
+
+    /// Returns the markets listed by Bitstamp. Bitstamp returns an array, which is wrapped in an
+    /// object under the "data" key.
+    ///
+    /// Sample output :
+    ///
+    /// ```ignore
+    /// {"data": [{"name": "BTC/USD", "url_symbol": "btcusd", "base_decimals": 8,
+    /// "counter_decimals": 2, "minimum_order": "5.0 USD", "trading": "Enabled",
+    /// "description": "Bitcoin / U.S. dollar"}, ... ]}
+    /// ```
+    pub fn return_trading_pairs_info(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "trading-pairs-info");
         self.public_query(&params)
     }
 
+    /// Returns the EUR/USD conversion rate used for deposits and withdrawals.
+    ///
+    /// Sample output :
+    ///
+    /// ```ignore
+    /// {"sell": "1.0548", "buy": "1.0624"}
+    /// ```
+    pub fn return_eur_usd_conversion_rate(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "eur_usd");
+        self.public_query_v1(&params)
+    }
 
-    /// Returns all of your available balances.
+    /// Returns the balances of the pair currencies and the trading fee of the pair.
     ///
     /// Sample output:
     ///
     /// ```ignore
-    /// {"BTC":"0.59098578","LTC":"3.31117268", ... }
+    /// {"btc_available":"0.50000000","btc_balance":"1.00000000","btc_reserved":"0.50000000",
+    /// "usd_available":"100.00","usd_balance":"100.00","usd_reserved":"0.00","fee":"0.25"}
     /// ```
     pub fn return_balances(&mut self, pair: Pair) -> Result<Map<String, Value>, error::Error> {
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
 
+        let mut params = HashMap::new();
+        params.insert("method", "balance");
+        params.insert("pair", currency_pair.as_str());
+        self.private_query(&params)
+    }
+
+    /// Returns the balances of every currency held on the account.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"btc_available":"0.50000000","btc_balance":"1.00000000","btc_reserved":"0.50000000",
+    /// "usd_available":"100.00","usd_balance":"100.00","usd_reserved":"0.00",
+    /// "btcusd_fee":"0.25", ... }
+    /// ```
+    pub fn return_all_balances(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "balance");
+        self.private_query(&params)
+    }
+
+    /// Returns your transactions (deposits, withdrawals and trades) for the pair specified, or
+    /// for every pair if None is given.
+    /// offset skips that many transactions (optional. default = 0), limit is the number of
+    /// transactions to return (optional. default = 100, maximum = 1000) and sort is the sorting
+    /// by date and time: "asc" or "desc" (optional. default = "desc").
+    /// Bitstamp returns an array, which is wrapped in an object under the "data" key.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"data": [{"id": 1234, "datetime": "2017-04-11 14:21:07", "type": "2", "fee": "0.02",
+    /// "order_id": 2253465, "usd": "-117.00", "btc": "0.10000000", "btc_usd": "1170.00"},
+    /// ... ]}
+    /// ```
+    /// type: 0 = deposit, 1 = withdrawal, 2 = market trade, 14 = sub account transfer.
+    pub fn return_user_transactions(&mut self,
+                                    pair: Option<Pair>,
+                                    offset: &str,
+                                    limit: &str,
+                                    sort: &str)
+                                    -> Result<Map<String, Value>, error::Error> {
         let currency_pair = match pair {
-            Pair::BTC_USD => "btcusd",
-            _ => panic!("Unknown pair"),
+            Some(ref pair) => helpers::get_pair_string(self, pair)?,
+            None => String::new(),
         };
 
         let mut params = HashMap::new();
-        params.insert("method", "balance");
-        params.insert("pair", currency_pair);
+        params.insert("method", "user_transactions");
+        params.insert("pair", currency_pair.as_str());
+        params.insert("offset", offset);
+        params.insert("limit", limit);
+        params.insert("sort", sort);
+        self.private_query(&params)
+    }
+
+    /// Places a limit buy order. Amount is in base currency, price in quote currency.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"id": "2253465", "datetime": "2017-04-11 14:21:07", "type": "0", "price": "1170.00",
+    /// "amount": "0.10000000"}
+    /// ```
+    pub fn buy_limit_order(&mut self,
+                           pair: Pair,
+                           amount: &str,
+                           price: &str)
+                           -> Result<Map<String, Value>, error::Error> {
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
+
+        let mut params = HashMap::new();
+        params.insert("method", "buy");
+        params.insert("pair", currency_pair.as_str());
+        params.insert("amount", amount);
+        params.insert("price", price);
+        self.private_query(&params)
+    }
+
+    /// Places a limit sell order. Parameters and output are the same as for the buy_limit_order
+    /// method.
+    pub fn sell_limit_order(&mut self,
+                            pair: Pair,
+                            amount: &str,
+                            price: &str)
+                            -> Result<Map<String, Value>, error::Error> {
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
+
+        let mut params = HashMap::new();
+        params.insert("method", "sell");
+        params.insert("pair", currency_pair.as_str());
+        params.insert("amount", amount);
+        params.insert("price", price);
+        self.private_query(&params)
+    }
+
+    /// Places a market buy order. Amount is in base currency.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"id": "2253466", "datetime": "2017-04-11 14:21:07", "type": "0", "price": "1170.00",
+    /// "amount": "0.10000000"}
+    /// ```
+    pub fn buy_market_order(&mut self,
+                            pair: Pair,
+                            amount: &str)
+                            -> Result<Map<String, Value>, error::Error> {
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
+
+        let mut params = HashMap::new();
+        params.insert("method", "buy/market");
+        params.insert("pair", currency_pair.as_str());
+        params.insert("amount", amount);
+        self.private_query(&params)
+    }
+
+    /// Places a market sell order. Parameters and output are the same as for the
+    /// buy_market_order method.
+    pub fn sell_market_order(&mut self,
+                             pair: Pair,
+                             amount: &str)
+                             -> Result<Map<String, Value>, error::Error> {
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
+
+        let mut params = HashMap::new();
+        params.insert("method", "sell/market");
+        params.insert("pair", currency_pair.as_str());
+        params.insert("amount", amount);
+        self.private_query(&params)
+    }
+
+    /// Returns the status of the order specified by its id.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"status": "Finished", "transactions": [{"tid": 13526279, "usd": "117.00",
+    /// "price": "1170.00", "fee": "0.29", "btc": "0.10000000", "datetime": "2017-04-11 14:21:07",
+    /// "type": 2}]}
+    /// ```
+    /// status: "In Queue", "Open" or "Finished".
+    pub fn return_order_status(&mut self, id: &str) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "order_status");
+        params.insert("id", id);
         self.private_query(&params)
     }
-}
\ No newline at end of file
+
+    /// Cancels the order specified by its id.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"id": 2253465, "amount": "0.10000000", "price": "1170.00", "type": 0}
+    /// ```
+    pub fn cancel_order(&mut self, id: &str) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "cancel_order");
+        params.insert("id", id);
+        self.private_query(&params)
+    }
+
+    /// Cancels all your open orders, for every pair.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"success": true, "canceled": [{"id": 2253465, "amount": "0.10000000",
+    /// "price": "1170.00", "type": 0, "currency_pair": "BTC/USD"}, ... ]}
+    /// ```
+    pub fn cancel_all_orders(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "cancel_all_orders");
+        self.private_query(&params)
+    }
+
+    /// Returns your open orders for the pair specified. Bitstamp returns an array, which is
+    /// wrapped in an object under the "data" key.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"data": [{"id": "2253465", "datetime": "2017-04-11 14:21:07", "type": "0",
+    /// "price": "1170.00", "amount": "0.10000000", "currency_pair": "BTC/USD"}, ... ]}
+    /// ```
+    pub fn return_open_orders(&mut self, pair: Pair) -> Result<Map<String, Value>, error::Error> {
+        let currency_pair = helpers::get_pair_string(self, &pair)?;
+
+        let mut params = HashMap::new();
+        params.insert("method", "open_orders");
+        params.insert("pair", currency_pair.as_str());
+        self.private_query(&params)
+    }
+
+    /// Returns your open orders for every pair. Output is the same as for the return_open_orders
+    /// method.
+    pub fn return_all_open_orders(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "open_orders");
+        params.insert("pair", "all");
+        self.private_query(&params)
+    }
+
+    /// Returns your withdrawal requests made during the last timedelta seconds (optional.
+    /// default = 86400, maximum = 50000000). Bitstamp returns an array, which is wrapped in an
+    /// object under the "data" key.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"data": [{"id": 1234, "datetime": "2017-04-11 14:21:07", "type": 1, "currency": "BTC",
+    /// "amount": "0.10000000", "status": 2, "data": {"address": "1N2i5n8Dw...",
+    /// "transaction_id": "36e483efa6..."}}, ... ]}
+    /// ```
+    /// type: 0 = SEPA, 1 = Bitcoin, 2 = WIRE, 14 = XRP, 15 = Litecoin, 16 = Ethereum.
+    /// status: 0 = open, 1 = in process, 2 = finished, 3 = canceled, 4 = failed.
+    pub fn return_withdrawal_requests(&mut self,
+                                      timedelta: &str)
+                                      -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "withdrawal-requests");
+        params.insert("timedelta", timedelta);
+        self.private_query(&params)
+    }
+
+    /// Withdraws bitcoins to the address specified. Set instant to "1" for an instant
+    /// withdrawal (additional fees apply).
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"id": 1234}
+    /// ```
+    pub fn bitcoin_withdrawal(&mut self,
+                              amount: &str,
+                              address: &str,
+                              instant: &str)
+                              -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "bitcoin_withdrawal");
+        params.insert("amount", amount);
+        params.insert("address", address);
+        params.insert("instant", instant);
+        self.private_query_v1(&params)
+    }
+
+    /// Withdraws litecoins to the address specified.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"id": 1234}
+    /// ```
+    pub fn litecoin_withdrawal(&mut self,
+                               amount: &str,
+                               address: &str)
+                               -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "ltc_withdrawal");
+        params.insert("amount", amount);
+        params.insert("address", address);
+        self.private_query(&params)
+    }
+
+    /// Withdraws ethers to the address specified.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"id": 1234}
+    /// ```
+    pub fn ethereum_withdrawal(&mut self,
+                               amount: &str,
+                               address: &str)
+                               -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "eth_withdrawal");
+        params.insert("amount", amount);
+        params.insert("address", address);
+        self.private_query(&params)
+    }
+
+    /// Withdraws XRP to the address specified, with an optional destination tag.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"id": 1234}
+    /// ```
+    pub fn ripple_withdrawal(&mut self,
+                             amount: &str,
+                             address: &str,
+                             destination_tag: &str)
+                             -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "xrp_withdrawal");
+        params.insert("amount", amount);
+        params.insert("address", address);
+        params.insert("destination_tag", destination_tag);
+        self.private_query(&params)
+    }
+
+    /// Returns your bitcoin deposit address. Bitstamp returns a string, which is wrapped in an
+    /// object under the "data" key.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"data": "1N2i5n8DwTGzUq2Vmn9TUL8J1vdr1XBDFg"}
+    /// ```
+    pub fn bitcoin_deposit_address(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "bitcoin_deposit_address");
+        self.private_query_v1(&params)
+    }
+
+    /// Returns the bitcoin deposits which are not confirmed yet.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"data": [{"amount": "0.10000000", "address": "1N2i5n8DwTGzUq2Vmn9TUL8J1vdr1XBDFg",
+    /// "confirmations": 1}, ... ]}
+    /// ```
+    pub fn unconfirmed_bitcoin_deposits(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "unconfirmed_btc");
+        self.private_query_v1(&params)
+    }
+
+    /// Returns your litecoin deposit address.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"address": "LPgf9kjv9H1Vuh4XSaKhzBe8JHdou1WgUB"}
+    /// ```
+    pub fn litecoin_deposit_address(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "ltc_address");
+        self.private_query(&params)
+    }
+
+    /// Returns your ethereum deposit address.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"address": "0x7f5e63ed1ea3d3e2b43e6cd3ba8a1ac5c3d3a2e7"}
+    /// ```
+    pub fn ethereum_deposit_address(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "eth_address");
+        self.private_query(&params)
+    }
+
+    /// Returns your XRP deposit address and destination tag.
+    ///
+    /// Sample output:
+    ///
+    /// ```ignore
+    /// {"address": "rDsbeomae4FXwgQTJp9Rs64Qg9vDiTCdBv", "destination_tag": 89123456}
+    /// ```
+    pub fn ripple_deposit_address(&mut self) -> Result<Map<String, Value>, error::Error> {
+        let mut params = HashMap::new();
+        params.insert("method", "xrp_address");
+        self.private_query(&params)
+    }
+}
@@ -0,0 +1,77 @@
+//! Use this module to identify the errors returned by Bitstamp.
+//!
+//! Bitstamp errors are sentences, some of them holding values: "You have only 10.00 USD
+//! available. Check your account balance for details.". The generic API maps them into the
+//! `ErrorKind` of the Error returned, which keeps the original sentence (see `Error::message`).
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::error::ErrorKind;
+//! use coinnect::bitstamp::error::BitstampError;
+//!
+//! let error = BitstampError::from_message("Order not found");
+//! assert_eq!(error, BitstampError::OrderNotFound);
+//! assert_eq!(error.kind(), ErrorKind::UnknownOrder);
+//! ```
+
+use error::ErrorKind;
+
+/// An error of the Bitstamp API.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitstampError {
+    MissingCredentials,
+    ApiKeyNotFound,
+    InvalidSignature,
+    InvalidNonce,
+    NoPermission,
+    TooManyRequests,
+    InvalidCurrencyPair,
+    InsufficientFunds,
+    MinimumOrderSize,
+    MaximumOrderSize,
+    PriceOutOfRange,
+    OrderNotFound,
+    /// An unknown error, holding the message returned.
+    Other(String),
+}
+
+impl BitstampError {
+    /// Return the BitstampError of a message returned by Bitstamp.
+    pub fn from_message(message: &str) -> BitstampError {
+        match message.trim_right_matches('.') {
+            "Missing key, signature and nonce parameters" => BitstampError::MissingCredentials,
+            "API key not found" => BitstampError::ApiKeyNotFound,
+            "Invalid signature" => BitstampError::InvalidSignature,
+            "Invalid nonce" => BitstampError::InvalidNonce,
+            "No permission found" => BitstampError::NoPermission,
+            "Invalid currency pair" => BitstampError::InvalidCurrencyPair,
+            "Order not found" => BitstampError::OrderNotFound,
+            m if m.starts_with("Too many requests") => BitstampError::TooManyRequests,
+            m if m.starts_with("You have only") => BitstampError::InsufficientFunds,
+            m if m.starts_with("Minimum order size is") => BitstampError::MinimumOrderSize,
+            m if m.starts_with("Maximum order size is") => BitstampError::MaximumOrderSize,
+            m if m.starts_with("Price is more than") => BitstampError::PriceOutOfRange,
+            _ => BitstampError::Other(message.to_string()),
+        }
+    }
+
+    /// Return the generic category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            BitstampError::MissingCredentials |
+            BitstampError::ApiKeyNotFound |
+            BitstampError::InvalidSignature => ErrorKind::InvalidLogin,
+            BitstampError::InvalidNonce => ErrorKind::InvalidNonce,
+            BitstampError::NoPermission => ErrorKind::PermissionDenied,
+            BitstampError::TooManyRequests => ErrorKind::RateLimitExceeded,
+            BitstampError::InvalidCurrencyPair => ErrorKind::PairUnsupported,
+            BitstampError::InsufficientFunds => ErrorKind::InsufficientFunds,
+            BitstampError::MinimumOrderSize |
+            BitstampError::MaximumOrderSize |
+            BitstampError::PriceOutOfRange => ErrorKind::InvalidOrder,
+            BitstampError::OrderNotFound => ErrorKind::UnknownOrder,
+            BitstampError::Other(ref message) => ErrorKind::ExchangeSpecificError(message.clone()),
+        }
+    }
+}
@@ -6,40 +6,306 @@
 use serde_json::Value;
 use serde_json::value::Map;
 
+use std::collections::HashMap;
+
 use exchange::ExchangeApi;
 use bitstamp::api::BitstampApi;
 
-use error::Error;
+use decimal::{Decimal, MAX_SCALE};
+use error::{Error, ErrorKind, ResponseContext};
+use market::MarketCache;
 use pair::Pair;
-use types::Ticker;
+use types::{Ticker, Orderbook, OrderSide, Trade, TradeHistory, Balance, Balances};
+use types::{Market, MarketInfo, Order, OrderId, OrderRequest, OrderStatus, OrderType};
+use bitstamp::utils;
+use validator::OrderValidator;
 use helpers;
+use helpers::{GenericApi, response_error, validate_order};
+
+impl GenericApi for BitstampApi {
+    fn market_cache(&self) -> &MarketCache {
+        BitstampApi::market_cache(self)
+    }
+
+    fn order_validator(&self) -> &OrderValidator {
+        BitstampApi::order_validator(self)
+    }
+
+    fn last_response(&self) -> Option<&ResponseContext> {
+        BitstampApi::last_response(self)
+    }
+
+    fn fallback_pair_string(pair: &Pair) -> Option<String> {
+        utils::get_pair_string(pair)
+    }
+}
 
 impl ExchangeApi for BitstampApi {
+    fn markets(&mut self) -> Result<Vec<Market>, Error> {
+        if let Some(markets) = self.market_cache().get() {
+            return Ok(markets);
+        }
+
+        let raw_response = self.return_trading_pairs_info()?;
+
+        let markets = parse_markets(raw_response).map_err(|e| response_error(self, e))?;
+
+        self.market_cache().set(markets.clone());
+        Ok(markets)
+    }
+
     fn ticker(&mut self, pair: Pair) -> Result<Ticker, Error> {
+        let raw_response = self.return_ticker(pair)?;
 
-        let result = self.return_ticker(pair)?;
+        parse_ticker(raw_response, pair).map_err(|e| response_error(self, e))
+    }
+    fn orderbook(&mut self, pair: Pair) -> Result<Orderbook, Error> {
+        let raw_response = self.return_order_book(pair)?;
 
-        let price = result["last"].as_str().unwrap().parse::<f64>().unwrap();
-        let ask = result["ask"].as_str().unwrap().parse::<f64>().unwrap();
-        let bid = result["bid"].as_str().unwrap().parse::<f64>().unwrap();
-        let vol = result["volume"].as_str().unwrap().parse::<f64>().unwrap();
+        parse_orderbook(raw_response, pair).map_err(|e| response_error(self, e))
+    }
+    fn trades(&mut self, pair: Pair, since: Option<&str>) -> Result<TradeHistory, Error> {
+        // Only the trades of the last hour are returned by default: ask for the last day instead to
+        // reach back to `since`
+        let time = if since.is_some() { "day" } else { "" };
+        let raw_response = self.return_trade_history(pair, time)?;
 
-        Ok(Ticker {
-            timestamp: helpers::get_unix_timestamp_ms(),
-            pair: pair,
-            last_trade_price: price,
-            lowest_ask: ask,
-            highest_bid: bid,
-            volume: Some(vol),
-        })
+        parse_trades(raw_response, pair, since).map_err(|e| response_error(self, e))
+    }
+    fn balances(&mut self) -> Result<Balances, Error> {
+        let raw_response = self.return_all_balances()?;
+
+        parse_balances(raw_response).map_err(|e| response_error(self, e))
+    }
+    fn place_order(&mut self, order: OrderRequest) -> Result<OrderId, Error> {
+        let order = validate_order(self, order)?;
+        let volume = order.volume.to_string();
+        let raw_response = match (order.side, order.order_type) {
+            (OrderSide::Buy, OrderType::Limit) => {
+                let price = order.price.ok_or(ErrorKind::InvalidArguments)?.to_string();
+                self.buy_limit_order(order.pair, &volume, &price)?
+            }
+            (OrderSide::Sell, OrderType::Limit) => {
+                let price = order.price.ok_or(ErrorKind::InvalidArguments)?.to_string();
+                self.sell_limit_order(order.pair, &volume, &price)?
+            }
+            (OrderSide::Buy, OrderType::Market) => self.buy_market_order(order.pair, &volume)?,
+            (OrderSide::Sell, OrderType::Market) => self.sell_market_order(order.pair, &volume)?,
+        };
+
+        parse_order_id(raw_response).map_err(|e| response_error(self, e))
+    }
+    fn cancel_order(&mut self, id: OrderId) -> Result<(), Error> {
+        let raw_response = BitstampApi::cancel_order(self, &id.0)?;
+
+        utils::parse_result(raw_response).map_err(|e| response_error(self, e))?;
+        Ok(())
+    }
+    fn open_orders(&mut self, pair: Pair) -> Result<Vec<Order>, Error> {
+        let raw_response = self.return_open_orders(pair)?;
+
+        parse_open_orders(raw_response, pair).map_err(|e| response_error(self, e))
+    }
+}
+
+/// Return the markets of a trading-pairs-info response.
+fn parse_markets(raw_response: Map<String, Value>) -> Result<Vec<Market>, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let entries = result.get("data")
+        .and_then(|data| data.as_array())
+        .ok_or(ErrorKind::BadParse)?;
+    let mut markets = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if entry["trading"].as_str() == Some("Disabled") {
+            continue;
+        }
+        if let Ok(market) = parse_market(entry) {
+            markets.push(market);
+        }
     }
-    fn return_trade_history(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+    Ok(markets)
+}
+
+/// Return the market of a trading-pairs-info entry.
+fn parse_market(entry: &Value) -> Result<Market, Error> {
+    // "BTC/USD"
+    let name = entry["name"].as_str().ok_or(ErrorKind::BadParse)?;
+    let symbol = entry["url_symbol"].as_str().ok_or(ErrorKind::BadParse)?;
+    // A Decimal has at most MAX_SCALE decimals
+    let decimals = |field: &str| {
+        entry[field]
+            .as_u64()
+            .filter(|&decimals| decimals <= MAX_SCALE as u64)
+            .map(|decimals| decimals as u32)
+            .ok_or(ErrorKind::BadParse)
+    };
+    // "5.0 USD"
+    let min_value = match entry["minimum_order"].as_str() {
+        Some(minimum) => {
+            let amount = minimum.split_whitespace().next().unwrap_or("");
+            Some(amount.parse::<Decimal>().map_err(|_| ErrorKind::BadParse)?)
+        }
+        None => None,
+    };
+
+    Ok(Market {
+        pair: name.parse().map_err(|_| ErrorKind::BadParse)?,
+        symbol: symbol.to_string(),
+        info: MarketInfo {
+            price_decimals: decimals("counter_decimals")?,
+            volume_decimals: decimals("base_decimals")?,
+            min_volume: None,
+            min_value: min_value,
+            leverage: Vec::new(),
+            maker_fee: utils::FEE,
+            taker_fee: utils::FEE,
+        },
+    })
+}
+
+/// Return the ticker of a ticker response.
+fn parse_ticker(raw_response: Map<String, Value>, pair: Pair) -> Result<Ticker, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let price = helpers::parse_json_decimal(result.get("last").ok_or(ErrorKind::BadParse)?)?;
+    let ask = helpers::parse_json_decimal(result.get("ask").ok_or(ErrorKind::BadParse)?)?;
+    let bid = helpers::parse_json_decimal(result.get("bid").ok_or(ErrorKind::BadParse)?)?;
+    let vol = helpers::parse_json_decimal(result.get("volume").ok_or(ErrorKind::BadParse)?)?;
+
+    Ok(Ticker {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        last_trade_price: price,
+        lowest_ask: ask,
+        highest_bid: bid,
+        volume: Some(vol),
+    })
+}
+
+/// Return the order book of an order_book response.
+fn parse_orderbook(raw_response: Map<String, Value>, pair: Pair) -> Result<Orderbook, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let mut asks = helpers::parse_offers(result.get("asks").ok_or(ErrorKind::BadParse)?)?;
+    let mut bids = helpers::parse_offers(result.get("bids").ok_or(ErrorKind::BadParse)?)?;
+    helpers::sort_offers(&mut asks, &mut bids);
+
+    Ok(Orderbook {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        asks: asks,
+        bids: bids,
+    })
+}
+
+/// Return the trades following the one specified from a transactions response.
+fn parse_trades(raw_response: Map<String, Value>,
+                pair: Pair,
+                since: Option<&str>)
+                -> Result<TradeHistory, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let entries = result.get("data")
+        .and_then(|data| data.as_array())
+        .ok_or(ErrorKind::BadParse)?;
+    let mut trades = Vec::with_capacity(entries.len());
+    for entry in entries {
+        // 0 (buy) or 1 (sell)
+        let side = match helpers::json_to_string(&entry["type"])?.as_ref() {
+            "0" => OrderSide::Buy,
+            "1" => OrderSide::Sell,
+            _ => return Err(ErrorKind::BadParse.into()),
+        };
+
+        trades.push(Trade {
+            id: Some(helpers::json_to_string(&entry["tid"])?),
+            timestamp: (helpers::parse_json_f64(&entry["date"])? * 1000.0) as i64,
+            price: helpers::parse_json_decimal(&entry["price"])?,
+            volume: helpers::parse_json_decimal(&entry["amount"])?,
+            side: side,
+            order_type: None,
+        });
     }
-    fn return_order_book(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+
+    // Bitstamp has no cursor, the trades are filtered by id
+    let (trades, last, gap) = helpers::filter_trades_since(trades, since)?;
+
+    Ok(TradeHistory {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        trades: trades,
+        last: last,
+        gap: gap,
+    })
+}
+
+/// Return the balances of a balance response.
+fn parse_balances(raw_response: Map<String, Value>) -> Result<Balances, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    // Each currency has 3 keys: <currency>_balance, <currency>_available, <currency>_reserved
+    let mut balances = HashMap::new();
+    for (key, total) in result.iter() {
+        if !key.ends_with("_balance") {
+            continue;
+        }
+        let currency = &key[..key.len() - "_balance".len()];
+        let amount = |suffix: &str| match result.get(&(currency.to_string() + suffix)) {
+            Some(amount) => helpers::parse_json_decimal(amount).map(Some),
+            None => Ok(None),
+        };
+
+        balances.insert(currency.to_uppercase(),
+                        Balance {
+                            total: helpers::parse_json_decimal(total)?,
+                            available: amount("_available")?,
+                            on_hold: amount("_reserved")?,
+                        });
     }
-    fn return_balances(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+
+    Ok(Balances {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        balances: balances,
+    })
+}
+
+/// Return the id of the order placed from a buy or sell response.
+fn parse_order_id(raw_response: Map<String, Value>) -> Result<OrderId, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let id = result.get("id").ok_or(ErrorKind::BadParse)?;
+    Ok(OrderId(helpers::json_to_string(id)?))
+}
+
+/// Return the open orders of an open_orders response.
+fn parse_open_orders(raw_response: Map<String, Value>, pair: Pair) -> Result<Vec<Order>, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let entries = result.get("data")
+        .and_then(|data| data.as_array())
+        .ok_or(ErrorKind::BadParse)?;
+    let mut orders = Vec::with_capacity(entries.len());
+    for entry in entries {
+        // 0 (buy) or 1 (sell)
+        let side = match helpers::json_to_string(&entry["type"])?.as_ref() {
+            "0" => OrderSide::Buy,
+            "1" => OrderSide::Sell,
+            _ => return Err(ErrorKind::BadParse.into()),
+        };
+
+        // Bitstamp only returns the remaining amount of open limit orders
+        orders.push(Order {
+            id: OrderId(helpers::json_to_string(&entry["id"])?),
+            pair: pair,
+            side: side,
+            order_type: Some(OrderType::Limit),
+            price: Some(helpers::parse_json_decimal(&entry["price"])?),
+            volume: helpers::parse_json_decimal(&entry["amount"])?,
+            filled: None,
+            status: OrderStatus::Open,
+        });
     }
-}
\ No newline at end of file
+
+    Ok(orders)
+}
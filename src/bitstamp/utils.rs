@@ -7,62 +7,181 @@ use serde_json;
 use serde_json::Value;
 use serde_json::value::Map;
 
-use std::thread;
-use std::time::Duration;
-
+use currency::Currency;
 use error;
+use exchange::Exchange;
 use helpers;
+use pair::Pair;
+use rate_limiter::RateLimiter;
+use scope::Scope;
+use bitstamp::error::BitstampError;
+
+/// Root of the official REST API, used unless `ClientConfig::base_url` is set.
+pub const BASE_URL: &'static str = "https://www.bitstamp.net";
+
+/// Fee of the orders for the lowest volume tier, the same for makers and takers: 0.25 %.
+pub const FEE: f64 = 0.0025;
+
+/// The currencies Bitstamp prices its markets in.
+const QUOTE_CURRENCIES: &'static [Currency] = &[Currency::USD,
+                                                Currency::EUR,
+                                                Currency::GBP,
+                                                Currency::BTC,
+                                                Currency::ETH,
+                                                Currency::USDT];
 
-pub fn block_or_continue(last_request: i64) {
-    let threshold = 1000; // 600 requests per 10 mins = 1 request per second
-    let delay = helpers::get_unix_timestamp_ms() - last_request;
-    if delay < threshold {
-        let duration_ms = Duration::from_millis(delay as u64);
-        thread::sleep(duration_ms);
+/// Return the name of the pair used by Bitstamp ("btceur" for BTC/EUR).
+/// If the quote currency is not one Bitstamp prices its markets in, None is returned.
+pub fn get_pair_string(pair: &Pair) -> Option<String> {
+    if !QUOTE_CURRENCIES.contains(&pair.quote) {
+        return None;
     }
+    Some((pair.base.code().to_string() + pair.quote.code()).to_lowercase())
+}
+
+/// Return the Pair of a pair name used by Bitstamp ("btceur" is BTC/EUR).
+/// If the name does not end with a quote currency preceded by a valid code, None is returned.
+pub fn get_pair(pair_name: &str) -> Option<Pair> {
+    let name = pair_name.to_uppercase();
+    let quote = QUOTE_CURRENCIES.iter()
+        .find(|quote| name.len() > quote.code().len() && name.ends_with(quote.code()))?;
+    let base = name[..name.len() - quote.code().len()].parse().ok()?;
+    Some(Pair::new(base, *quote))
+}
+
+/// Return the default rate limiter of a client: 600 requests per 10 mins = 1 request per second,
+/// with bursts of up to 10 requests.
+pub fn rate_limiter() -> RateLimiter {
+    RateLimiter::new(10.0, 1.0)
 }
 
-pub fn build_signature(nonce: String,
-                       customer_id: String,
-                       api_key: String,
-                       api_secret: String)
-                       -> String {
+pub fn build_signature(nonce: &str, customer_id: &str, api_key: &str, api_secret: &str) -> String {
     const C: &'static [u8] = b"0123456789ABCDEF";
 
-    let message = nonce + &customer_id + &api_key;
+    let message = nonce.to_string() + customer_id + api_key;
     let mut hmac = Hmac::new(Sha256::new(), api_secret.as_bytes());
 
     hmac.input(message.as_bytes());
     let result = hmac.result();
 
     let raw_signature = result.code();
-    let mut signature = Vec::with_capacity(raw_signature.len() * 2);
+    let mut signature = String::with_capacity(raw_signature.len() * 2);
     for &byte in raw_signature {
-        signature.push(C[(byte >> 4) as usize]);
-        signature.push(C[(byte & 0xf) as usize]);
+        signature.push(C[(byte >> 4) as usize] as char);
+        signature.push(C[(byte & 0xf) as usize] as char);
     }
-    String::from_utf8(signature).unwrap()
+    signature
 }
 
-pub fn build_url(method: &str, pair: &str) -> String {
-    "https://www.bitstamp.net/api/v2/".to_string() + method + "/" + &pair + "/"
+/// Build the URL of a v2 method. The pair can be empty for methods which do not need one.
+pub fn build_url(base_url: &str, method: &str, pair: &str) -> String {
+    if pair.is_empty() {
+        return base_url.to_string() + "/api/v2/" + method + "/";
+    }
+    base_url.to_string() + "/api/v2/" + method + "/" + &pair + "/"
+}
+
+/// Build the URL of a method which only exists in the v1 API (Bitcoin deposits and withdrawals,
+/// EUR/USD conversion rate).
+pub fn build_url_v1(base_url: &str, method: &str) -> String {
+    base_url.to_string() + "/api/" + method + "/"
+}
+
+/// Return true if the private method specified only reads the account, so it can be sent again
+/// safely.
+pub fn is_read_only(method: &str) -> bool {
+    match method {
+        "balance" |
+        "user_transactions" |
+        "open_orders" |
+        "order_status" |
+        "withdrawal-requests" |
+        "bitcoin_deposit_address" |
+        "unconfirmed_btc" |
+        "ltc_address" |
+        "eth_address" |
+        "xrp_address" => true,
+        _ => false,
+    }
+}
+
+/// Return the scope a client needs to call the private method specified: withdrawals need
+/// `Scope::Withdraw`, the methods which do not change anything `Scope::ReadOnly` and the other
+/// ones (orders) `Scope::Trade`.
+pub fn required_scope(method: &str) -> Scope {
+    match method {
+        "bitcoin_withdrawal" |
+        "ltc_withdrawal" |
+        "eth_withdrawal" |
+        "xrp_withdrawal" => Scope::Withdraw,
+        method if is_read_only(method) => Scope::ReadOnly,
+        _ => Scope::Trade,
+    }
+}
+
+/// Return true if a request which returned the result specified may succeed if sent again: the
+/// host could not be reached, answered with a server error, or returned an error meaning that it
+/// is overloaded or rate limiting the client.
+pub fn is_retryable(result: &Result<Map<String, Value>, error::Error>) -> bool {
+    match *result {
+        Ok(ref response) => {
+            parse_result(response.clone()).err().map_or(false, |e| e.is_retryable())
+        }
+        Err(ref e) => e.is_retryable(),
+    }
 }
 
 pub fn deserialize_json(json_string: String) -> Result<Map<String, Value>, error::Error> {
     let data: Value = match serde_json::from_str(&json_string) {
         Ok(data) => data,
-        Err(_) => return Err(error::Error::BadParse),
+        Err(e) => return Err(error::Error::new(error::ErrorKind::BadParse).with_source(e)),
     };
 
-    match data.as_object() {
-        Some(value) => Ok(value.clone()),
-        None => Err(error::Error::BadParse),
+    match data {
+        Value::Object(map) => Ok(map),
+        // Some methods return an array, a string (deposit addresses) or a boolean, it is wrapped
+        // in an object under the "data" key
+        _ => {
+            let mut map = Map::new();
+            map.insert("data".to_string(), data);
+            Ok(map)
+        }
     }
 }
 
+/// If the response contains an error, return it as an ExchangeSpecificError
+/// else return the response.
+pub fn parse_result(response: Map<String, Value>) -> Result<Map<String, Value>, error::Error> {
+    // v2 methods return {"status": "error", "reason": ...}, v1 methods return {"error": ...}
+    let error = match response.get("status").and_then(|status| status.as_str()) {
+        Some("error") => response.get("reason"),
+        _ => response.get("error"),
+    };
+
+    // Form errors are grouped by field: {"__all__": ["You have only 10.00 USD available..."]}
+    let reason = match error {
+        Some(&Value::String(ref reason)) => reason.clone(),
+        Some(reason) => {
+            match reason["__all__"][0].as_str() {
+                Some(reason) => reason.to_string(),
+                None => reason.to_string(),
+            }
+        }
+        None => return Ok(response),
+    };
+
+    let kind = BitstampError::from_message(&reason).kind();
+    Err(exchange_error(kind).with_message(&reason))
+}
+
+/// Return an Error of the kind specified, raised by Bitstamp.
+fn exchange_error(kind: error::ErrorKind) -> error::Error {
+    error::Error::new(kind).with_exchange(Exchange::Bitstamp)
+}
+
 pub fn generate_nonce(fixed_nonce: Option<String>) -> String {
     match fixed_nonce {
         Some(v) => v,
         None => helpers::get_unix_timestamp_ms().to_string(),
     }
-}
\ No newline at end of file
+}
@@ -4,13 +4,12 @@
 
 use std::collections::HashMap;
 use std::path::PathBuf;
-use serde_json::value::Map;
-use serde_json::value::Value;
 
+use config::ClientConfig;
+use credentials::Account;
 use error::Error;
-use types::Ticker;
 use exchange::{Exchange, ExchangeApi};
-use pair::Pair;
+use transport::{Transport, HttpsTransport};
 use bitstamp::api::BitstampApi;
 use kraken::api::KrakenApi;
 use poloniex::api::PoloniexApi;
@@ -24,53 +23,111 @@ impl Coinnect {
                api_key: &str,
                api_secret: &str,
                customer_id: Option<&str>)
-               -> Box<ExchangeApi> {
+               -> Result<Box<ExchangeApi>, Error> {
+        Ok(Coinnect::with_transport(exchange,
+                                    api_key,
+                                    api_secret,
+                                    customer_id,
+                                    HttpsTransport::new()?))
+    }
+
+    /// Create a new CoinnectApi sending its requests through the Transport specified instead of
+    /// HTTPS. This is mostly useful to test your code with a `MockTransport`.
+    pub fn with_transport<T>(exchange: Exchange,
+                             api_key: &str,
+                             api_secret: &str,
+                             customer_id: Option<&str>,
+                             transport: T)
+                             -> Box<ExchangeApi>
+        where T: Transport + 'static
+    {
+        Coinnect::with_config(exchange,
+                              api_key,
+                              api_secret,
+                              customer_id,
+                              ClientConfig::default(),
+                              transport)
+    }
+
+    /// Create a new CoinnectApi using the configuration specified (base URL, ...) and sending its
+    /// requests through the Transport specified.
+    pub fn with_config<T>(exchange: Exchange,
+                          api_key: &str,
+                          api_secret: &str,
+                          customer_id: Option<&str>,
+                          config: ClientConfig,
+                          transport: T)
+                          -> Box<ExchangeApi>
+        where T: Transport + 'static
+    {
         match exchange {
             Exchange::Bitstamp => {
                 let mut params = HashMap::new();
                 params.insert("api_key", api_key);
                 params.insert("api_secret", api_secret);
-                if customer_id.is_some() {
-                    params.insert("customer_id", customer_id.unwrap());
+                if let Some(customer_id) = customer_id {
+                    params.insert("customer_id", customer_id);
                 }
-                Box::new(BitstampApi::new(&params))
+                Box::new(BitstampApi::with_config(&params, config, transport))
             }
 
-            Exchange::Kraken => Box::new(KrakenApi::new(api_key, api_secret)),
+            Exchange::Kraken => {
+                Box::new(KrakenApi::with_config(api_key, api_secret, config, transport))
+            }
 
-            Exchange::Poloniex => Box::new(PoloniexApi::new(api_key, api_secret)),
+            Exchange::Poloniex => {
+                Box::new(PoloniexApi::with_config(api_key, api_secret, config, transport))
+            }
         }
     }
 
-    /// Create a new CoinnectApi from a json configuration file. This file must follow this
-    /// structure:
-    ///
-    /// For this example, you could use load your Bitstamp account with
-    /// `new_from_file(Exchange::Bitstamp, "account_bitstamp", Path::new("/keys.json"))`
-    pub fn new_from_file(exchange: Exchange, config_name: &str, path: PathBuf) -> Box<ExchangeApi> {
-        match exchange {
-            Exchange::Bitstamp => Box::new(BitstampApi::new_from_file(config_name, path)),
-            Exchange::Kraken => Box::new(UnimplementedApi),
-            Exchange::Poloniex => Box::new(UnimplementedApi),
-        }
+    /// Create a new CoinnectApi for the account specified, loaded from a configuration file or
+    /// from environment variables (see the `credentials` module).
+    pub fn from_account(account: &Account) -> Result<Box<ExchangeApi>, Error> {
+        Ok(Coinnect::from_account_with_config(account,
+                                              ClientConfig::default(),
+                                              HttpsTransport::new()?))
     }
-}
 
+    /// Create a new CoinnectApi for the account specified, using the configuration specified and
+    /// sending its requests through the Transport specified.
+    pub fn from_account_with_config<T>(account: &Account,
+                                       config: ClientConfig,
+                                       transport: T)
+                                       -> Box<ExchangeApi>
+        where T: Transport + 'static
+    {
+        let credentials = account.credentials();
+        match (account.exchange(), account.otp()) {
+            (Exchange::Kraken, Some(otp)) => {
+                let api = KrakenApi::with_config(credentials.api_key(),
+                                                 credentials.api_secret(),
+                                                 config,
+                                                 transport);
+                Box::new(api.with_otp(otp.clone()))
+            }
 
-#[derive(Debug)]
-struct UnimplementedApi;
-
-impl ExchangeApi for UnimplementedApi {
-    fn ticker(&mut self, _pair: Pair) -> Result<Ticker, Error> {
-        unimplemented!();
-    }
-    fn return_trade_history(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
-    }
-    fn return_order_book(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+            (exchange, _) => {
+                Coinnect::with_config(exchange,
+                                      credentials.api_key(),
+                                      credentials.api_secret(),
+                                      account.customer_id(),
+                                      config,
+                                      transport)
+            }
+        }
     }
-    fn return_balances(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+
+    /// Create a new CoinnectApi from a JSON or TOML configuration file. This file must follow the
+    /// structure documented by the `credentials` module, the `exchange` field of the account
+    /// being optional.
+    ///
+    /// For this example, you could use load your Bitstamp account with
+    /// `new_from_file(Exchange::Bitstamp, "account_bitstamp", Path::new("/keys.json"))`
+    pub fn new_from_file(exchange: Exchange,
+                         config_name: &str,
+                         path: PathBuf)
+                         -> Result<Box<ExchangeApi>, Error> {
+        Coinnect::from_account(&Account::from_exchange_file(exchange, config_name, &path)?)
     }
 }
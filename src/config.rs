@@ -0,0 +1,147 @@
+//! Use this module to configure the exchange clients beyond their credentials.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::config::ClientConfig;
+//! use coinnect::kraken::api::KrakenApi;
+//! use coinnect::transport::HttpsTransport;
+//!
+//! // Send the requests to a local mock server (see the `coinnect-mock` binary)
+//! let config = ClientConfig::new().base_url("http://127.0.0.1:8080");
+//! let transport = HttpsTransport::new().unwrap();
+//! let api = KrakenApi::with_config("api_key", "api_secret", config, transport);
+//! ```
+
+use market::MarketCache;
+use nonce::NonceGenerator;
+use rate_limiter::RateLimiter;
+use retry::RetryPolicy;
+use scope::Scope;
+use validator::OrderValidator;
+
+/// Settings of an exchange client. Every setting left unset uses the exchange default.
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    base_url: Option<String>,
+    rate_limiter: Option<RateLimiter>,
+    private_rate_limiter: Option<RateLimiter>,
+    retry_policy: Option<RetryPolicy>,
+    nonce_generator: Option<NonceGenerator>,
+    scope: Option<Scope>,
+    market_cache: Option<MarketCache>,
+    order_validator: Option<OrderValidator>,
+}
+
+impl ClientConfig {
+    /// Create a new ClientConfig using the exchange defaults.
+    pub fn new() -> ClientConfig {
+        ClientConfig::default()
+    }
+
+    /// Send the requests to the base URL specified ("https://api.kraken.com") instead of the
+    /// official endpoint, e.g. a sandbox, a gateway or a local server. The URL may contain a path
+    /// ("https://gateway.example.com/kraken"), the API paths are appended to it.
+    pub fn base_url(mut self, base_url: &str) -> ClientConfig {
+        self.base_url = Some(base_url.trim_right_matches('/').to_string());
+        self
+    }
+
+    /// Throttle the requests with the RateLimiter specified instead of a new one using the
+    /// exchange limits. Give clones of the same RateLimiter to several clients so they share it.
+    /// Kraken only applies it to public requests, see `private_rate_limiter`.
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> ClientConfig {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Throttle the private requests with the RateLimiter specified. It defaults to the
+    /// `rate_limiter` for Poloniex and Bitstamp, and to the call counter of the API key for
+    /// Kraken (see `kraken::utils::private_rate_limiter`), which should be shared by every client
+    /// using the same key.
+    pub fn private_rate_limiter(mut self, rate_limiter: RateLimiter) -> ClientConfig {
+        self.private_rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Retry the requests which failed temporarily as specified instead of trying up to 3 times.
+    /// Only public requests and private requests which do not change anything are retried, see
+    /// the `retry` module.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> ClientConfig {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Generate the nonces of private requests with the NonceGenerator specified instead of the
+    /// one shared by the clients of the process. Use a generator persisted to a file when several
+    /// processes share an API key.
+    pub fn nonce_generator(mut self, nonce_generator: NonceGenerator) -> ClientConfig {
+        self.nonce_generator = Some(nonce_generator);
+        self
+    }
+
+    /// Restrict the private calls of the client to the scope specified (see the `scope` module)
+    /// instead of allowing every call.
+    pub fn scope(mut self, scope: Scope) -> ClientConfig {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// Keep the markets of the exchange in the MarketCache specified instead of a new one keeping
+    /// them for an hour (see the `market` module). Give clones of the same MarketCache to several
+    /// clients of the same exchange so they share it.
+    pub fn market_cache(mut self, market_cache: MarketCache) -> ClientConfig {
+        self.market_cache = Some(market_cache);
+        self
+    }
+
+    /// Check the orders placed through the generic API with the OrderValidator specified instead
+    /// of the default one (see the `validator` module).
+    pub fn order_validator(mut self, order_validator: OrderValidator) -> ClientConfig {
+        self.order_validator = Some(order_validator);
+        self
+    }
+
+    /// Return the base URL configured, or the default specified if none was.
+    pub fn base_url_or(&self, default: &str) -> String {
+        match self.base_url {
+            Some(ref base_url) => base_url.clone(),
+            None => default.to_string(),
+        }
+    }
+
+    /// Return the rate limiter configured, or the default specified if none was.
+    pub fn rate_limiter_or(&self, default: RateLimiter) -> RateLimiter {
+        self.rate_limiter.clone().unwrap_or(default)
+    }
+
+    /// Return the private rate limiter configured, or the default specified if none was.
+    pub fn private_rate_limiter_or(&self, default: RateLimiter) -> RateLimiter {
+        self.private_rate_limiter.clone().unwrap_or(default)
+    }
+
+    /// Return the retry policy configured, or the default specified if none was.
+    pub fn retry_policy_or(&self, default: RetryPolicy) -> RetryPolicy {
+        self.retry_policy.clone().unwrap_or(default)
+    }
+
+    /// Return the nonce generator configured, or the default specified if none was.
+    pub fn nonce_generator_or(&self, default: NonceGenerator) -> NonceGenerator {
+        self.nonce_generator.clone().unwrap_or(default)
+    }
+
+    /// Return the scope configured, or the default specified if none was.
+    pub fn scope_or(&self, default: Scope) -> Scope {
+        self.scope.unwrap_or(default)
+    }
+
+    /// Return the market cache configured, or the default specified if none was.
+    pub fn market_cache_or(&self, default: MarketCache) -> MarketCache {
+        self.market_cache.clone().unwrap_or(default)
+    }
+
+    /// Return the order validator configured, or the default specified if none was.
+    pub fn order_validator_or(&self, default: OrderValidator) -> OrderValidator {
+        self.order_validator.unwrap_or(default)
+    }
+}
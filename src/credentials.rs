@@ -0,0 +1,375 @@
+//! Use this module to keep and load the API keys of an account.
+//!
+//! The clients keep their API key and secret in a `Credentials`, made of `Secret`s: a `Secret`
+//! never shows its value in `Debug` or `Display` (so a client can be logged, or be part of a panic
+//! message, without leaking its keys), and overwrites it with zeros when dropped.
+//!
+//! An `Account` is the exchange, credentials and exchange specific settings of an account. It is
+//! loaded from a configuration file holding several accounts, in JSON or in TOML (when the file
+//! name ends with `.toml`):
+//!
+//! ```ignore
+//! {
+//!     "account_kraken": {
+//!         "exchange"  : "kraken",
+//!         "api_key"   : "123456789ABCDEF",
+//!         "api_secret": "ABC&EF?abcdef",
+//!         "otp_secret": "JBSWY3DPEHPK3PXP"
+//!     },
+//!     "account_bitstamp": {
+//!         "exchange"   : "bitstamp",
+//!         "api_key"    : "1234567890ABCDEF1234567890ABCDEF",
+//!         "api_secret" : "1234567890ABCDEF1234567890ABCDEF",
+//!         "customer_id": "123456"
+//!     }
+//! }
+//! ```
+//!
+//! ```ignore
+//! [account_kraken]
+//! exchange = "kraken"
+//! api_key = "123456789ABCDEF"
+//! api_secret = "ABC&EF?abcdef"
+//! ```
+//!
+//! from an encrypted keystore (see the `keystore` module), or from environment variables named
+//! after the exchange and the field:
+//! `COINNECT_KRAKEN_API_KEY`, `COINNECT_KRAKEN_API_SECRET`, `COINNECT_BITSTAMP_CUSTOMER_ID`...
+//!
+//! `customer_id` is required for Bitstamp accounts. `otp` (a static password) or `otp_secret`
+//! (the base32 secret of a TOTP) set the two-factor authentication of Kraken accounts.
+//! `coinnect::Coinnect::from_account` then creates the client of any account.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::credentials::Credentials;
+//!
+//! let credentials = Credentials::new("api_key", "api_secret");
+//! assert_eq!(credentials.api_secret(), "api_secret");
+//! assert_eq!(format!("{:?}", credentials),
+//!            "Credentials { api_key: Secret(***), api_secret: Secret(***) }");
+//! ```
+
+use serde_json;
+use serde_json::Value;
+use toml;
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use error::{Error, ErrorKind};
+use exchange::Exchange;
+use helpers;
+use keystore::Keystore;
+use otp::Otp;
+
+/// A string which is not shown by `Debug` and `Display` and is zeroized when dropped.
+#[derive(Clone, PartialEq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Create a new Secret holding a copy of the value specified.
+    pub fn new(value: &str) -> Secret {
+        Secret(value.to_string())
+    }
+
+    /// Return the value of the secret. Do not keep copies of it.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Return true if the secret is an empty string.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Secret {
+        Secret(value)
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        // The bytes are only set to 0, which keeps the string valid UTF-8
+        unsafe { helpers::zeroize(self.0.as_mut_vec()) };
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secret(***)")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "***")
+    }
+}
+
+/// The API key and secret of an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Credentials {
+    api_key: Secret,
+    api_secret: Secret,
+}
+
+impl Credentials {
+    /// Create new Credentials from the API key and secret specified.
+    pub fn new(api_key: &str, api_secret: &str) -> Credentials {
+        Credentials {
+            api_key: Secret::new(api_key),
+            api_secret: Secret::new(api_secret),
+        }
+    }
+
+    /// Return the API key.
+    pub fn api_key(&self) -> &str {
+        self.api_key.expose()
+    }
+
+    /// Return the API secret.
+    pub fn api_secret(&self) -> &str {
+        self.api_secret.expose()
+    }
+}
+
+/// The exchange, credentials and exchange specific settings of an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    exchange: Exchange,
+    credentials: Credentials,
+    customer_id: Option<String>,
+    otp: Option<Otp>,
+}
+
+impl Account {
+    /// Create a new Account on the exchange specified.
+    pub fn new(exchange: Exchange, credentials: Credentials) -> Account {
+        Account {
+            exchange: exchange,
+            credentials: credentials,
+            customer_id: None,
+            otp: None,
+        }
+    }
+
+    /// Set the customer id of the account (required by Bitstamp).
+    pub fn with_customer_id(mut self, customer_id: &str) -> Account {
+        self.customer_id = Some(customer_id.to_string());
+        self
+    }
+
+    /// Set the one-time password of the account (only used by Kraken).
+    pub fn with_otp(mut self, otp: Otp) -> Account {
+        self.otp = Some(otp);
+        self
+    }
+
+    /// Load the account `name` of the JSON or TOML file specified. Return a ConfigError telling
+    /// what is wrong if the file cannot be read or parsed, or if the account or one of its
+    /// required fields (`exchange` included) is missing.
+    pub fn from_file(name: &str, path: &Path) -> Result<Account, Error> {
+        Account::read_file(None, name, path)
+    }
+
+    /// Load the account `name` of the JSON or TOML file specified, as `from_file`, for the
+    /// exchange specified: the `exchange` field may be omitted, and must match if present.
+    pub fn from_exchange_file(exchange: Exchange,
+                              name: &str,
+                              path: &Path)
+                              -> Result<Account, Error> {
+        Account::read_file(Some(exchange), name, path)
+    }
+
+    /// Load the account `name` of the encrypted keystore specified (see the `keystore` module).
+    /// Return a ConfigError if the passphrase is wrong or the account is missing or invalid.
+    pub fn from_keystore(name: &str, path: &Path, passphrase: &str) -> Result<Account, Error> {
+        Keystore::open(path.to_path_buf(), passphrase)?.account(name)
+    }
+
+    /// Load the account on the exchange specified from the environment variables
+    /// `COINNECT_<EXCHANGE>_<FIELD>` (`COINNECT_KRAKEN_API_KEY`, ...). Return a ConfigError naming
+    /// the variable if a required one is missing.
+    pub fn from_env(exchange: Exchange) -> Result<Account, Error> {
+        let prefix = format!("COINNECT_{:?}_", exchange).to_uppercase();
+        let variable = |field: &str| prefix.clone() + &field.to_uppercase();
+
+        Account::from_fields(exchange,
+                             |field| env::var(variable(field)).ok().map(Secret::from),
+                             |field| {
+                                 config_error(format!("Missing environment variable {}",
+                                                      variable(field)))
+                             })
+    }
+
+    /// Return the exchange of the account.
+    pub fn exchange(&self) -> Exchange {
+        self.exchange
+    }
+
+    /// Return the credentials of the account.
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    /// Return the customer id of the account, if any.
+    pub fn customer_id(&self) -> Option<&str> {
+        self.customer_id.as_ref().map(|customer_id| customer_id.as_str())
+    }
+
+    /// Return the one-time password of the account, if any.
+    pub fn otp(&self) -> Option<&Otp> {
+        self.otp.as_ref()
+    }
+
+    fn read_file(exchange: Option<Exchange>, name: &str, path: &Path) -> Result<Account, Error> {
+        let mut content = String::new();
+        if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut content)) {
+            let reason = format!("Cannot read {}", path.display());
+            return Err(Error::new(ErrorKind::ConfigError(reason)).with_source(e));
+        }
+        let content = Secret::from(content);
+
+        let is_toml = path.extension().map_or(false, |extension| extension == "toml");
+        let fields = if is_toml {
+            toml_account(content.expose(), name, path)?
+        } else {
+            json_account(content.expose(), name, path)?
+        };
+        let fields = match fields {
+            Some(fields) => fields,
+            None => {
+                return Err(config_error(format!("No account {} in {}", name, path.display())))
+            }
+        };
+
+        let location = format!("account {} of {}", name, path.display());
+        Account::from_map(exchange, &fields, &location)
+    }
+
+    /// Create the account described by the string fields of an entry of a configuration file or
+    /// keystore, `location` ("account account_kraken of keys.json") naming the entry in errors.
+    /// When `exchange` is given, the `exchange` field may be omitted, and must match if present.
+    pub fn from_map(exchange: Option<Exchange>,
+                    fields: &HashMap<String, Secret>,
+                    location: &str)
+                    -> Result<Account, Error> {
+        let missing =
+            |field: &str| config_error(format!("Missing field {} in {}", field, location));
+        let exchange = match (fields.get("exchange"), exchange) {
+            (None, Some(exchange)) => exchange,
+            (None, None) => return Err(missing("exchange")),
+            (Some(field), expected) => {
+                let found = field.expose().parse::<Exchange>().map_err(|_| {
+                    config_error(format!("Unknown exchange {} in {}", field.expose(), location))
+                })?;
+                match expected {
+                    Some(expected) if expected != found => {
+                        return Err(config_error(format!("Expected a {:?} account, {} is a {:?} \
+                                                         account",
+                                                        expected,
+                                                        location,
+                                                        found)))
+                    }
+                    _ => found,
+                }
+            }
+        };
+        Account::from_fields(exchange, |field| fields.get(field).cloned(), missing)
+    }
+
+    /// Create the account on the exchange specified from its fields, returned by `field`, calling
+    /// `missing` to build the error returned when a required field is missing.
+    fn from_fields<F, M>(exchange: Exchange, field: F, missing: M) -> Result<Account, Error>
+        where F: Fn(&str) -> Option<Secret>,
+              M: Fn(&str) -> Error
+    {
+        let required = |name: &str| field(name).ok_or_else(|| missing(name));
+
+        let credentials = Credentials {
+            api_key: required("api_key")?,
+            api_secret: required("api_secret")?,
+        };
+        let mut account = Account::new(exchange, credentials);
+        if exchange == Exchange::Bitstamp {
+            account.customer_id = Some(required("customer_id")?.expose().to_string());
+        }
+        if let Some(password) = field("otp") {
+            account.otp = Some(Otp::Password(password));
+        } else if let Some(secret) = field("otp_secret") {
+            account.otp = Some(Otp::totp(secret.expose())?);
+        }
+        Ok(account)
+    }
+}
+
+fn config_error(reason: String) -> Error {
+    ErrorKind::ConfigError(reason).into()
+}
+
+/// Return the string fields of the account `name` of a JSON file, or None if there is no such
+/// account.
+fn json_account(content: &str,
+                name: &str,
+                path: &Path)
+                -> Result<Option<HashMap<String, Secret>>, Error> {
+    let mut data: Value = serde_json::from_str(content).map_err(|e| {
+            config_error(format!("{} is not valid JSON", path.display())).with_source(e)
+        })?;
+
+    let fields = data.get(name).and_then(|account| account.as_object()).map(|account| {
+        account.iter()
+            .filter_map(|(field, value)| value.as_str().map(|value| (field, value)))
+            .map(|(field, value)| (field.clone(), Secret::new(value)))
+            .collect()
+    });
+    // The values were copied to Secrets, the document holds the keys of every account
+    helpers::zeroize_json(&mut data);
+    Ok(fields)
+}
+
+/// Return the string fields of the account `name` of a TOML file, or None if there is no such
+/// account.
+fn toml_account(content: &str,
+                name: &str,
+                path: &Path)
+                -> Result<Option<HashMap<String, Secret>>, Error> {
+    let mut data: toml::Value = content.parse().map_err(|e| {
+            config_error(format!("{} is not valid TOML", path.display())).with_source(e)
+        })?;
+
+    let fields = data.get(name).and_then(|account| account.as_table()).map(|account| {
+        account.iter()
+            .filter_map(|(field, value)| value.as_str().map(|value| (field, value)))
+            .map(|(field, value)| (field.clone(), Secret::new(value)))
+            .collect()
+    });
+    zeroize_toml(&mut data);
+    Ok(fields)
+}
+
+/// Overwrite every string of the TOML value specified with zeros, like `helpers::zeroize_json`.
+fn zeroize_toml(value: &mut toml::Value) {
+    match *value {
+        toml::Value::String(ref mut string) => unsafe { helpers::zeroize(string.as_mut_vec()) },
+        toml::Value::Array(ref mut values) => {
+            for value in values.iter_mut() {
+                zeroize_toml(value);
+            }
+        }
+        toml::Value::Table(ref mut table) => {
+            for (_, value) in table.iter_mut() {
+                zeroize_toml(value);
+            }
+        }
+        _ => (),
+    }
+}
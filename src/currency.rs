@@ -0,0 +1,131 @@
+//! Use this module to name the currencies traded on the exchanges.
+//!
+//! A `Currency` is a normalized currency code ("BTC", "EUR", "XLM"...): the exchanges use their
+//! own names for some currencies (Kraken names Bitcoin XBT and prefixes it with X, Poloniex names
+//! Stellar STR), which are translated by the `utils` module of each exchange. Any code is a valid
+//! currency, so a new coin can be traded without updating the crate. The most common ones are
+//! available as constants.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::currency::Currency;
+//!
+//! let currency: Currency = "btc".parse().unwrap();
+//! assert_eq!(currency, Currency::BTC);
+//! assert_eq!(Currency::new("NEWCOIN").code(), "NEWCOIN");
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use error::{Error, ErrorKind};
+
+/// The longest currency code accepted by `FromStr`.
+const MAX_CODE_LEN: usize = 12;
+
+/// The most currency codes created at runtime, which bounds the memory they use.
+const MAX_CODES: usize = 4096;
+
+lazy_static! {
+    /// The codes of the currencies created at runtime. Each code is allocated once and never
+    /// freed, so that a Currency can be copied freely.
+    static ref CODES: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
+/// A currency, identified by its upper case code.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Currency(&'static str);
+
+impl Currency {
+    pub const AUD: Currency = Currency("AUD");
+    pub const CAD: Currency = Currency("CAD");
+    pub const CHF: Currency = Currency("CHF");
+    pub const EUR: Currency = Currency("EUR");
+    pub const GBP: Currency = Currency("GBP");
+    pub const JPY: Currency = Currency("JPY");
+    pub const USD: Currency = Currency("USD");
+
+    pub const BCH: Currency = Currency("BCH");
+    pub const BTC: Currency = Currency("BTC");
+    pub const DASH: Currency = Currency("DASH");
+    pub const DOGE: Currency = Currency("DOGE");
+    pub const ETC: Currency = Currency("ETC");
+    pub const ETH: Currency = Currency("ETH");
+    pub const GNT: Currency = Currency("GNT");
+    pub const ICN: Currency = Currency("ICN");
+    pub const LTC: Currency = Currency("LTC");
+    pub const MLN: Currency = Currency("MLN");
+    pub const REP: Currency = Currency("REP");
+    pub const USDT: Currency = Currency("USDT");
+    pub const XLM: Currency = Currency("XLM");
+    pub const XMR: Currency = Currency("XMR");
+    pub const XRP: Currency = Currency("XRP");
+    pub const ZEC: Currency = Currency("ZEC");
+
+    /// Return the currency of the code specified, in upper case. Prefer `FromStr` for codes which
+    /// are not trusted, since it checks them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than 4096 distinct codes were created.
+    pub fn new(code: &str) -> Currency {
+        Currency::intern(code).expect("Too many currency codes")
+    }
+
+    /// Return the currency of the code specified, in upper case, or None if the code is new and
+    /// `MAX_CODES` codes were already created.
+    fn intern(code: &str) -> Option<Currency> {
+        let code = code.to_uppercase();
+        let mut codes = match CODES.lock() {
+            Ok(codes) => codes,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(code) = codes.get(code.as_str()) {
+            return Some(Currency(code));
+        }
+        if codes.len() >= MAX_CODES {
+            return None;
+        }
+        let code: &'static str = Box::leak(code.into_boxed_str());
+        codes.insert(code);
+        Some(Currency(code))
+    }
+
+    /// Return the code of the currency ("BTC").
+    pub fn code(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Debug for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Currency {
+    type Err = Error;
+
+    /// Parse a currency code, in any case. Return an InvalidArguments error if the code is empty,
+    /// too long or not alphanumeric, or if it is new and too many codes were already created.
+    fn from_str(code: &str) -> Result<Currency, Error> {
+        if code.is_empty() || code.len() > MAX_CODE_LEN ||
+           !code.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(Error::new(ErrorKind::InvalidArguments)
+                .with_message(&format!("Invalid currency code {}", code)));
+        }
+        Currency::intern(code).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidArguments)
+                .with_message(&format!("Too many currency codes to create {}", code))
+        })
+    }
+}
@@ -0,0 +1,454 @@
+//! Use this module to compute prices and volumes exactly.
+//!
+//! A `Decimal` is a number with a fixed number of decimals, such as the amounts sent by the
+//! exchanges ("0.12345678"): it is parsed from their strings without the rounding errors of a
+//! `f64`, so that balances and fills add up to the cent, and it is formatted back as is, without
+//! scientific notation, when building the parameters of an order. Convert it to a `f64` with
+//! `to_f64` for analytics only.
+//!
+//! The operators panic if the result overflows: use `checked_add`, `checked_sub`, `checked_mul`
+//! and `checked_div` on amounts read from the exchanges.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::decimal::Decimal;
+//!
+//! let price: Decimal = "0.1".parse().unwrap();
+//! let volume: Decimal = "0.2".parse().unwrap();
+//! assert_eq!((price + volume).to_string(), "0.3");
+//! assert_eq!(Decimal::new(12345, 2).to_string(), "123.45");
+//! assert_eq!("1e-8".parse::<Decimal>().unwrap().to_string(), "0.00000001");
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use error::{Error, ErrorKind};
+
+/// The largest number of decimals of a Decimal. Products with more decimals are rounded.
+pub const MAX_SCALE: u32 = 28;
+
+/// The number of decimals of a quotient.
+const DIVISION_SCALE: u32 = 18;
+
+/// A decimal number: `mantissa * 10^-scale`.
+#[derive(Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal {
+        mantissa: 0,
+        scale: 0,
+    };
+    pub const ONE: Decimal = Decimal {
+        mantissa: 1,
+        scale: 0,
+    };
+
+    /// Create a new Decimal equal to `mantissa * 10^-scale`: `Decimal::new(12345, 2)` is 123.45.
+    /// Panic if the scale is above `MAX_SCALE`.
+    pub const fn new(mantissa: i64, scale: u32) -> Decimal {
+        assert!(scale <= MAX_SCALE, "The scale of a Decimal is at most 28");
+        Decimal {
+            mantissa: mantissa as i128,
+            scale: scale,
+        }
+    }
+
+    /// Return the Decimal closest to the f64 specified (the shortest one which converts back to
+    /// it), or None if it is not finite or too large.
+    pub fn from_f64(number: f64) -> Option<Decimal> {
+        if !number.is_finite() {
+            return None;
+        }
+        // The Display of f64 never uses scientific notation
+        number.to_string().parse().ok()
+    }
+
+    /// Return the f64 closest to the Decimal. Use it for analytics only, not to compute amounts.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(0.0)
+    }
+
+    /// Return the number of decimals of the Decimal, trailing zeros included.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    pub fn is_sign_negative(&self) -> bool {
+        self.mantissa < 0
+    }
+
+    pub fn abs(&self) -> Decimal {
+        Decimal {
+            mantissa: self.mantissa.abs(),
+            scale: self.scale,
+        }
+    }
+
+    /// Return the same number without trailing zeros: 1.2300 becomes 1.23.
+    pub fn normalize(&self) -> Decimal {
+        let mut decimal = *self;
+        while decimal.scale > 0 && decimal.mantissa % 10 == 0 {
+            decimal.mantissa /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+
+    /// Round to the number of decimals specified, half away from zero.
+    pub fn round_dp(&self, decimals: u32) -> Decimal {
+        self.rescale_down(decimals, |quotient, remainder, divisor| {
+            if remainder.abs() * 2 >= divisor {
+                quotient + remainder.signum()
+            } else {
+                quotient
+            }
+        })
+    }
+
+    /// Round down to the number of decimals specified, towards negative infinity.
+    pub fn floor_dp(&self, decimals: u32) -> Decimal {
+        self.rescale_down(decimals, |quotient, remainder, _| if remainder < 0 {
+            quotient - 1
+        } else {
+            quotient
+        })
+    }
+
+    /// Round up to the number of decimals specified, towards positive infinity.
+    pub fn ceil_dp(&self, decimals: u32) -> Decimal {
+        self.rescale_down(decimals, |quotient, remainder, _| if remainder > 0 {
+            quotient + 1
+        } else {
+            quotient
+        })
+    }
+
+    /// Drop the decimals beyond the number specified, then adjust the remaining mantissa with
+    /// `round(quotient, remainder, divisor)`. Numbers with fewer decimals are returned as is.
+    fn rescale_down<F>(&self, decimals: u32, round: F) -> Decimal
+        where F: FnOnce(i128, i128, i128) -> i128
+    {
+        if self.scale <= decimals {
+            return *self;
+        }
+        let divisor = pow10(self.scale - decimals);
+        Decimal {
+            mantissa: round(self.mantissa / divisor, self.mantissa % divisor, divisor),
+            scale: decimals,
+        }
+    }
+
+    /// Return the sum, or an InvalidArguments error if it overflows.
+    pub fn checked_add(&self, other: Decimal) -> Result<Decimal, Error> {
+        let (left, right, scale) = self.align(&other).ok_or_else(overflow)?;
+        Ok(Decimal {
+            mantissa: left.checked_add(right).ok_or_else(overflow)?,
+            scale: scale,
+        })
+    }
+
+    /// Return the difference, or an InvalidArguments error if it overflows.
+    pub fn checked_sub(&self, other: Decimal) -> Result<Decimal, Error> {
+        self.checked_add(-other)
+    }
+
+    /// Return the exact product, rounded to `MAX_SCALE` decimals if it has more, or an
+    /// InvalidArguments error if it overflows.
+    pub fn checked_mul(&self, other: Decimal) -> Result<Decimal, Error> {
+        let (left, right) = (self.normalize(), other.normalize());
+        let product = Decimal {
+            mantissa: left.mantissa.checked_mul(right.mantissa).ok_or_else(overflow)?,
+            scale: left.scale + right.scale,
+        };
+        Ok(product.round_dp(MAX_SCALE))
+    }
+
+    /// Return the quotient rounded to 18 decimals, or an InvalidArguments error if the divisor is
+    /// zero or if the quotient overflows.
+    pub fn checked_div(&self, other: Decimal) -> Result<Decimal, Error> {
+        if other.is_zero() {
+            return Err(Error::new(ErrorKind::InvalidArguments)
+                .with_message("Decimal division by zero"));
+        }
+        let (left, right) = (self.normalize(), other.normalize());
+        // The quotient is left * 10^(DIVISION_SCALE + 1 + right.scale - left.scale) / right, with
+        // one decimal more than needed to round it. Its digits are computed one by one, so that
+        // the numerator is never scaled beyond the quotient.
+        let exponent = (DIVISION_SCALE + 1 + right.scale) as i64 - left.scale as i64;
+        let (numerator, digits) = if exponent >= 0 {
+            (left.mantissa, exponent as u32)
+        } else {
+            // Truncating the numerator first does not change the truncated quotient
+            (left.mantissa / pow10(-exponent as u32), 0)
+        };
+
+        let mut mantissa = numerator / right.mantissa;
+        let mut remainder = numerator % right.mantissa;
+        for _ in 0..digits {
+            let scaled = remainder.checked_mul(10).ok_or_else(overflow)?;
+            mantissa = mantissa.checked_mul(10)
+                .and_then(|mantissa| mantissa.checked_add(scaled / right.mantissa))
+                .ok_or_else(overflow)?;
+            remainder = scaled % right.mantissa;
+        }
+        let quotient = Decimal {
+            mantissa: mantissa,
+            scale: DIVISION_SCALE + 1,
+        };
+        Ok(quotient.round_dp(DIVISION_SCALE).normalize())
+    }
+
+    /// Return the mantissa of the Decimal written with the number of decimals specified, or
+    /// None if it overflows.
+    fn mantissa_at(&self, scale: u32) -> Option<i128> {
+        if scale >= self.scale {
+            self.mantissa.checked_mul(pow10(scale - self.scale))
+        } else {
+            Some(self.mantissa / pow10(self.scale - scale))
+        }
+    }
+
+    /// Return both mantissas written with the largest number of decimals of the two, or None if
+    /// one of them overflows.
+    fn align(&self, other: &Decimal) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        match (self.mantissa_at(scale), other.mantissa_at(scale)) {
+            (Some(left), Some(right)) => Some((left, right, scale)),
+            _ => None,
+        }
+    }
+}
+
+fn pow10(exponent: u32) -> i128 {
+    10i128.pow(exponent)
+}
+
+fn overflow() -> Error {
+    Error::new(ErrorKind::InvalidArguments).with_message("Decimal overflow")
+}
+
+impl Default for Decimal {
+    fn default() -> Decimal {
+        Decimal::ZERO
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = Error;
+
+    /// Parse a decimal number ("123.45", "-0.001", "1e-8", ".5"). Return an InvalidArguments
+    /// error if it is not one, or if it has too many digits.
+    fn from_str(number: &str) -> Result<Decimal, Error> {
+        let invalid = || {
+            Error::new(ErrorKind::InvalidArguments)
+                .with_message(&format!("Invalid decimal number {}", number))
+        };
+        let (digits, exponent) = match number.find(&['e', 'E'][..]) {
+            Some(position) => {
+                let exponent = number[position + 1..].parse::<i32>().map_err(|_| invalid())?;
+                (&number[..position], exponent)
+            }
+            None => (number, 0),
+        };
+        let (negative, digits) = match digits.chars().next() {
+            Some('-') => (true, &digits[1..]),
+            Some('+') => (false, &digits[1..]),
+            _ => (false, digits),
+        };
+        let (integer, fraction) = match digits.find('.') {
+            Some(position) => (&digits[..position], &digits[position + 1..]),
+            None => (digits, ""),
+        };
+        if integer.is_empty() && fraction.is_empty() ||
+           !integer.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let mut mantissa: i128 = 0;
+        for digit in integer.chars().chain(fraction.chars()) {
+            mantissa = mantissa.checked_mul(10)
+                .and_then(|mantissa| mantissa.checked_add(digit as i128 - '0' as i128))
+                .ok_or_else(invalid)?;
+        }
+        let mut scale = fraction.len() as i64 - exponent as i64;
+        if scale < 0 {
+            mantissa = mantissa.checked_mul(pow10(-scale as u32)).ok_or_else(invalid)?;
+            scale = 0;
+        }
+        let mut decimal = Decimal {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale: scale as u32,
+        };
+        if decimal.scale > MAX_SCALE {
+            decimal = decimal.normalize();
+            if decimal.scale > MAX_SCALE {
+                return Err(invalid());
+            }
+        }
+        Ok(decimal)
+    }
+}
+
+impl fmt::Display for Decimal {
+    /// Write the number without scientific notation, with all its decimals or with the precision
+    /// given (`{:.2}`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (mantissa, scale) = match f.precision() {
+            Some(precision) => {
+                let precision = precision.min(MAX_SCALE as usize) as u32;
+                let rounded = self.round_dp(precision);
+                match rounded.mantissa_at(precision) {
+                    Some(mantissa) => (mantissa, precision),
+                    // Too many decimals to be written at this precision: written as is instead
+                    None => (rounded.mantissa, rounded.scale),
+                }
+            }
+            None => (self.mantissa, self.scale),
+        };
+        let digits = mantissa.abs().to_string();
+        let scale = scale as usize;
+        let sign = if mantissa < 0 { "-" } else { "" };
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, integer, fraction)
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl PartialEq for Decimal {
+    /// Decimals are equal if they are the same number, whatever their scale: 1.50 == 1.5.
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let decimal = self.normalize();
+        decimal.mantissa.hash(state);
+        decimal.scale.hash(state);
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        // The integer parts are compared first, then the fractional parts: written with at most
+        // MAX_SCALE decimals, they cannot overflow
+        let (left_integer, right_integer) = (self.mantissa / pow10(self.scale),
+                                             other.mantissa / pow10(other.scale));
+        let scale = self.scale.max(other.scale);
+        let fraction = |decimal: &Decimal| {
+            decimal.mantissa % pow10(decimal.scale) * pow10(scale - decimal.scale)
+        };
+        left_integer.cmp(&right_integer).then_with(|| fraction(self).cmp(&fraction(other)))
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal {
+            mantissa: -self.mantissa,
+            scale: self.scale,
+        }
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+
+    /// Add exactly. Panic if the sum overflows, see `checked_add`.
+    fn add(self, other: Decimal) -> Decimal {
+        self.checked_add(other).expect("Decimal overflow")
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: Decimal) -> Decimal {
+        self + -other
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+
+    /// Multiply exactly, rounding the product to `MAX_SCALE` decimals if it has more. Panic if
+    /// the product overflows, see `checked_mul`.
+    fn mul(self, other: Decimal) -> Decimal {
+        self.checked_mul(other).expect("Decimal overflow")
+    }
+}
+
+impl Div for Decimal {
+    type Output = Decimal;
+
+    /// Divide, rounding the quotient to 18 decimals. Panic if the divisor is zero or if the
+    /// quotient overflows, see `checked_div`.
+    fn div(self, other: Decimal) -> Decimal {
+        assert!(!other.is_zero(), "Decimal division by zero");
+        self.checked_div(other).expect("Decimal overflow")
+    }
+}
+
+impl AddAssign for Decimal {
+    fn add_assign(&mut self, other: Decimal) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Decimal {
+    fn sub_assign(&mut self, other: Decimal) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Decimal {
+    fn sum<I: Iterator<Item = Decimal>>(iter: I) -> Decimal {
+        iter.fold(Decimal::ZERO, |sum, decimal| sum + decimal)
+    }
+}
+
+impl<'a> Sum<&'a Decimal> for Decimal {
+    fn sum<I: Iterator<Item = &'a Decimal>>(iter: I) -> Decimal {
+        iter.fold(Decimal::ZERO, |sum, decimal| sum + *decimal)
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(number: i64) -> Decimal {
+        Decimal::new(number, 0)
+    }
+}
@@ -1,41 +1,285 @@
-//! This module contains enum Error.
+//! This module contains the Error type and the enum ErrorKind.
 //! Error type represents all possible errors that can occur when dealing
-//! with the generic or any dedicated-exchange API
+//! with the generic or any dedicated-exchange API. Besides its kind, an Error carries as much
+//! context as is known where it occurred: the exchange and endpoint called, the HTTP status and
+//! body of the response, and the underlying I/O, TLS or JSON error (see `source()`).
 
 use std::error;
 use std::fmt;
 
-#[derive(Debug)]
-pub enum Error {
+use exchange::Exchange;
+use scope::Scope;
+use validator::Rejection;
+
+/// Maximum number of characters of the response body kept in an Error.
+const BODY_SNIPPET_LEN: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
     ServiceUnavailable,
     BadParse,
     InvalidLogin,
     InvalidArguments,
     RateLimitExceeded,
     PairUnsupported,
+    OrderTypeUnsupported,
+    InsufficientFunds,
+    InvalidNonce,
+    PermissionDenied,
+    InvalidOrder,
+    UnknownOrder,
+    AccountLocked,
+    /// The call was not sent: it requires a larger scope than the one of the client.
+    ScopeExceeded { required: Scope, allowed: Scope },
+    /// The order was not sent: it does not follow the rules of its market (see the `validator`
+    /// module).
+    OrderRejected(Rejection),
     ExchangeSpecificError(String),
+    CassetteError(String),
+    ConfigError(String),
+    NonceError(String),
+    TlsError,
     UndefinedError,
 }
 
-impl error::Error for Error {
+impl ErrorKind {
     fn description(&self) -> &str {
         match *self {
-            Error::ServiceUnavailable => "Host could not be reached.",
-            Error::BadParse => "The response could not be parsed.",
-            Error::InvalidLogin => "Wrong API key or secret.",
-            Error::InvalidArguments => "Arguments passed do not conform to the protocol.",
-            Error::RateLimitExceeded => "API call rate limit exceeded.",
-            Error::PairUnsupported => "This pair is not supported.",
-            Error::ExchangeSpecificError(ref s) => s,
-            Error::UndefinedError => "An unknown error occurred.",
+            ErrorKind::ServiceUnavailable => "Host could not be reached.",
+            ErrorKind::BadParse => "The response could not be parsed.",
+            ErrorKind::InvalidLogin => "Wrong API key or secret.",
+            ErrorKind::InvalidArguments => "Arguments passed do not conform to the protocol.",
+            ErrorKind::RateLimitExceeded => "API call rate limit exceeded.",
+            ErrorKind::PairUnsupported => "This pair is not supported.",
+            ErrorKind::OrderTypeUnsupported => "This order type is not supported by the exchange.",
+            ErrorKind::InsufficientFunds => "Insufficient funds.",
+            ErrorKind::InvalidNonce => "The nonce is not greater than the previous one.",
+            ErrorKind::PermissionDenied => "The API key is not allowed to do this.",
+            ErrorKind::InvalidOrder => "The order was rejected by the exchange.",
+            ErrorKind::UnknownOrder => "This order does not exist.",
+            ErrorKind::AccountLocked => "The account is locked.",
+            ErrorKind::ScopeExceeded { .. } => "The client scope does not allow this call.",
+            ErrorKind::OrderRejected(_) => "The order does not follow the rules of its market.",
+            ErrorKind::ExchangeSpecificError(ref s) => s,
+            ErrorKind::CassetteError(ref s) => s,
+            ErrorKind::ConfigError(ref s) => s,
+            ErrorKind::NonceError(ref s) => s,
+            ErrorKind::TlsError => "The TLS connector could not be initialized.",
+            ErrorKind::UndefinedError => "An unknown error occurred.",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    exchange: Option<Exchange>,
+    endpoint: Option<String>,
+    message: Option<String>,
+    status: Option<u16>,
+    body: Option<String>,
+    source: Option<Box<error::Error + Send + Sync>>,
+}
+
+impl Error {
+    /// Create a new Error of the kind specified, without any context.
+    pub fn new(kind: ErrorKind) -> Error {
+        Error {
+            kind: kind,
+            exchange: None,
+            endpoint: None,
+            message: None,
+            status: None,
+            body: None,
+            source: None,
+        }
+    }
+
+    /// Set the exchange and the endpoint (method or command) whose call failed.
+    pub fn with_endpoint(mut self, exchange: Exchange, endpoint: &str) -> Error {
+        self.exchange = Some(exchange);
+        self.endpoint = Some(endpoint.to_string());
+        self
+    }
+
+    /// Set the exchange whose call failed, if it is not known yet.
+    pub fn with_exchange(mut self, exchange: Exchange) -> Error {
+        if self.exchange.is_none() {
+            self.exchange = Some(exchange);
+        }
+        self
+    }
+
+    /// Set the error message sent by the exchange.
+    pub fn with_message(mut self, message: &str) -> Error {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    /// Set the HTTP status and the beginning of the body of the response received.
+    pub fn with_response(mut self, status: u16, body: &str) -> Error {
+        self.status = Some(status);
+        self.body = Some(body.chars().take(BODY_SNIPPET_LEN).collect());
+        self
+    }
+
+    /// Set the underlying error which caused this one.
+    pub fn with_source<E>(mut self, source: E) -> Error
+        where E: error::Error + Send + Sync + 'static
+    {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn exchange(&self) -> Option<Exchange> {
+        self.exchange
+    }
+
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_ref().map(|endpoint| endpoint.as_str())
+    }
+
+    /// Return the error message sent by the exchange, if any. Give it to
+    /// `kraken::error::KrakenError::from_message` (or its Poloniex and Bitstamp counterparts) to
+    /// get the exact error.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|message| message.as_str())
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    /// Return the beginning of the body of the response received, if any.
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_ref().map(|body| body.as_str())
+    }
+
+    /// Return true if the same request may succeed later: the exchange is unavailable, overloaded
+    /// or rate limiting the client.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind {
+            ErrorKind::ServiceUnavailable | ErrorKind::RateLimitExceeded => true,
+            _ => {
+                match self.status {
+                    Some(status) => status == 408 || status == 429 || status >= 500,
+                    None => false,
+                }
+            }
+        }
+    }
+
+    /// Return true if the exchange rejected the credentials or the signature of the request.
+    pub fn is_auth(&self) -> bool {
+        self.kind == ErrorKind::InvalidLogin || self.status == Some(401) ||
+        self.status == Some(403)
+    }
+
+    /// Return true if the request was rejected because of the rate limit of the exchange.
+    pub fn is_rate_limit(&self) -> bool {
+        self.kind == ErrorKind::RateLimitExceeded || self.status == Some(429)
+    }
+}
+
+/// The endpoint called by a client and the response it received, kept to give context to the
+/// errors raised while reading the response.
+#[derive(Clone)]
+pub struct ResponseContext {
+    exchange: Exchange,
+    endpoint: String,
+    status: u16,
+    body: String,
+}
+
+impl ResponseContext {
+    pub fn new(exchange: Exchange, endpoint: &str, status: u16, body: &str) -> ResponseContext {
+        ResponseContext {
+            exchange: exchange,
+            endpoint: endpoint.to_string(),
+            status: status,
+            body: body.chars().take(BODY_SNIPPET_LEN).collect(),
+        }
+    }
+
+    /// Set the exchange, endpoint, status and body of the response to the error specified, unless
+    /// it carries an endpoint already (errors of the request itself).
+    pub fn apply(&self, error: Error) -> Error {
+        if error.endpoint.is_some() {
+            return error;
+        }
+        error.with_endpoint(self.exchange, &self.endpoint).with_response(self.status, &self.body)
+    }
+}
+
+// The body may hold account data, it is only shown in the errors
+impl fmt::Debug for ResponseContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResponseContext")
+            .field("exchange", &self.exchange)
+            .field("endpoint", &self.endpoint)
+            .field("status", &self.status)
+            .finish()
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error::new(kind)
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        self.kind.description()
+    }
+
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match self.source {
+            Some(ref source) => Some(&**source),
+            None => None,
         }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            _ => error::Error::description(self).fmt(f),
+        self.kind.description().fmt(f)?;
+
+        // "The response could not be parsed. (Kraken Ticker, HTTP 502): <html>..."
+        let mut location = Vec::new();
+        if let Some(ref exchange) = self.exchange {
+            location.push(format!("{:?}", exchange));
+        }
+        if let Some(ref endpoint) = self.endpoint {
+            location.push(endpoint.clone());
+        }
+        let mut context = Vec::new();
+        if !location.is_empty() {
+            context.push(location.join(" "));
+        }
+        if let Some(status) = self.status {
+            context.push(format!("HTTP {}", status));
+        }
+        if !context.is_empty() {
+            write!(f, " ({})", context.join(", "))?;
+        }
+
+        // The message is the description of ExchangeSpecificError
+        if let Some(ref message) = self.message {
+            if *message != self.kind.description() {
+                write!(f, ": {}", message)?;
+            }
+        }
+        if let Some(ref body) = self.body {
+            write!(f, ": {}", body)?;
+        }
+        if let Some(ref source) = self.source {
+            write!(f, ": {}", source)?;
         }
+        Ok(())
     }
 }
@@ -1,15 +1,14 @@
 //! This module contains Exchange enum.
 
-use serde_json::value::Map;
-use serde_json::value::Value;
-
 use std::fmt::Debug;
+use std::str::FromStr;
 
-use error::Error;
+use error::{Error, ErrorKind};
 use pair::Pair;
-use types::Ticker;
+use types::{Ticker, Orderbook, TradeHistory, Balances, Market, MarketInfo};
+use types::{Order, OrderId, OrderRequest};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 #[derive(PartialEq)]
 pub enum Exchange {
     Bitstamp,
@@ -17,12 +16,63 @@ pub enum Exchange {
     Poloniex,
 }
 
+impl FromStr for Exchange {
+    type Err = Error;
+
+    /// Parse the name of an exchange ("kraken", "Kraken", ...).
+    fn from_str(name: &str) -> Result<Exchange, Error> {
+        match name.to_lowercase().as_str() {
+            "bitstamp" => Ok(Exchange::Bitstamp),
+            "kraken" => Ok(Exchange::Kraken),
+            "poloniex" => Ok(Exchange::Poloniex),
+            _ => {
+                Err(Error::new(ErrorKind::InvalidArguments)
+                    .with_message(&format!("Unknown exchange {}", name)))
+            }
+        }
+    }
+}
+
 pub trait ExchangeApi: Debug {
+    /// Return the markets listed by the exchange, retrieved from its metadata endpoints the first
+    /// time and then kept in the market cache of the client (see the `market` module). A listing
+    /// which does not parse, like a currency code the crate does not accept, is skipped instead of
+    /// failing every market.
+    fn markets(&mut self) -> Result<Vec<Market>, Error>;
+
+    /// Return the trading rules and fees of the market of the Pair specified, from the markets
+    /// of the exchange. Return a PairUnsupported error if the exchange does not list the Pair.
+    fn market_info(&mut self, pair: Pair) -> Result<MarketInfo, Error> {
+        self.markets()?
+            .into_iter()
+            .find(|market| market.pair == pair)
+            .map(|market| market.info)
+            .ok_or_else(|| ErrorKind::PairUnsupported.into())
+    }
+
     /// Return a Ticker for the Pair specified.
     fn ticker(&mut self, pair: Pair) -> Result<Ticker, Error>;
 
-    fn return_order_book(&mut self, pair: Pair) -> Option<Map<String, Value>>;
-    fn return_trade_history(&mut self, pair: Pair) -> Option<Map<String, Value>>;
-    fn return_balances(&mut self, pair: Pair) -> Option<Map<String, Value>>;
-    // fn balances(&mut self, pair: Option<Asset>) -> Result<Vec<Asset, Volume>, Error>;
+    /// Return an Orderbook for the Pair specified. Asks are sorted by ascending price and bids by
+    /// descending price.
+    fn orderbook(&mut self, pair: Pair) -> Result<Orderbook, Error>;
+
+    /// Return the recent public trades for the Pair specified, from the oldest to the most recent.
+    /// Give the `last` cursor of a previous TradeHistory as `since` to only retrieve the trades
+    /// that happened after it. Exchanges which only return their recent trades set `gap` if the
+    /// trades since then could not all be retrieved.
+    fn trades(&mut self, pair: Pair, since: Option<&str>) -> Result<TradeHistory, Error>;
+
+    /// Return the balances of every currency held on the account.
+    /// Currencies are keyed by their normalized code whatever the exchange is.
+    fn balances(&mut self) -> Result<Balances, Error>;
+
+    /// Place an order and return the id given by the exchange.
+    fn place_order(&mut self, order: OrderRequest) -> Result<OrderId, Error>;
+
+    /// Cancel an open order.
+    fn cancel_order(&mut self, id: OrderId) -> Result<(), Error>;
+
+    /// Return the open orders of the account for the Pair specified.
+    fn open_orders(&mut self, pair: Pair) -> Result<Vec<Order>, Error>;
 }
@@ -1,21 +1,103 @@
 
+use serde_json::Value;
+
 use std::collections::HashMap;
+use std::ptr;
+use std::sync::atomic;
 use time;
 
+use decimal::Decimal;
+use error;
+use error::{ErrorKind, ResponseContext};
+use exchange::ExchangeApi;
+use market::MarketCache;
+use pair::Pair;
+use types::{OrderRequest, Price, Volume, Trade};
+use validator::OrderValidator;
+
+/// The parts of a client the generic API of every exchange relies on, for the functions below.
+pub trait GenericApi: ExchangeApi {
+    /// Return the cache of the markets listed by the exchange.
+    fn market_cache(&self) -> &MarketCache;
+
+    /// Return the validator of the orders placed through the generic API.
+    fn order_validator(&self) -> &OrderValidator;
+
+    /// Return the endpoint called by the last request and the response received, if any.
+    fn last_response(&self) -> Option<&ResponseContext>;
+
+    /// Return the name the exchange is expected to give to the pair specified, used when the
+    /// markets cannot be retrieved.
+    fn fallback_pair_string(pair: &Pair) -> Option<String>;
+}
+
+/// Attach the endpoint, status and body of the last response received to an error raised while
+/// reading it (see `ResponseContext::apply`).
+pub fn response_error<A: GenericApi>(api: &A, error: error::Error) -> error::Error {
+    match api.last_response() {
+        Some(response) => response.apply(error),
+        None => error,
+    }
+}
+
+/// Return the name of the pair used by the exchange: the name of its market, the markets being
+/// retrieved if they are not cached, else the one given by `fallback_pair_string` if they cannot
+/// be retrieved (see `MarketCache::symbol_or_else`).
+pub fn get_pair_string<A: GenericApi>(api: &mut A, pair: &Pair) -> Result<String, error::Error> {
+    if api.market_cache().should_retrieve() && api.markets().is_err() {
+        // The markets retrieved before or the fallback name are used meanwhile
+        api.market_cache().set_failed();
+    }
+    api.market_cache()
+        .symbol_or_else(pair, A::fallback_pair_string)
+        .ok_or_else(|| ErrorKind::PairUnsupported.into())
+}
+
+/// Return the order rounded to the rules of its market, or an OrderRejected error if it does not
+/// follow them (see the `validator` module). The markets are retrieved if they are not cached,
+/// and a PairUnsupported error is returned if the pair is not listed.
+pub fn validate_order<A: GenericApi>(api: &mut A,
+                                     order: OrderRequest)
+                                     -> Result<OrderRequest, error::Error> {
+    let info = api.markets()?
+        .into_iter()
+        .find(|market| market.pair == order.pair)
+        .map(|market| market.info)
+        .ok_or(ErrorKind::PairUnsupported)?;
+    let validator = *api.order_validator();
+    validator.check(api, Some(&info), order)
+}
+
 // Helper functions
 
+/// Return the parameters as an URL query or a form body ("name=value&..."), percent-encoded.
 pub fn url_encode_hashmap(hashmap: &HashMap<&str, &str>) -> String {
     if hashmap.is_empty() {
         return "".to_string();
     }
     let mut acc = "".to_string();
     for (name, param) in hashmap {
-        acc += &(name.to_string() + "=" + param + "&");
+        acc += &(url_encode(name) + "=" + &url_encode(param) + "&");
     }
     acc.pop(); // remove the last "&"
     acc
 }
 
+/// Percent-encode a parameter name or value: every byte but the unreserved characters (letters,
+/// digits, "-", ".", "_" and "~") is written as "%XX".
+pub fn url_encode(param: &str) -> String {
+    let mut encoded = String::with_capacity(param.len());
+    for byte in param.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
 pub fn get_unix_timestamp_ms() -> i64 {
     let current_time = time::get_time();
     //Calculate milliseconds
@@ -30,4 +112,130 @@ pub fn strip_empties(x: &mut HashMap<&str, &str>) {
     for empty in empties {
         x.remove(&empty);
     }
-}
\ No newline at end of file
+}
+
+/// Overwrite the bytes specified with zeros, in a way the compiler cannot optimize away.
+pub fn zeroize(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+/// Overwrite every string of the JSON value specified with zeros, for the documents holding
+/// secrets. Object keys are left as is.
+pub fn zeroize_json(value: &mut Value) {
+    match *value {
+        // The bytes are only set to 0, which keeps the string valid UTF-8
+        Value::String(ref mut string) => unsafe { zeroize(string.as_mut_vec()) },
+        Value::Array(ref mut values) => {
+            for value in values.iter_mut() {
+                zeroize_json(value);
+            }
+        }
+        Value::Object(ref mut map) => {
+            for (_, value) in map.iter_mut() {
+                zeroize_json(value);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Parse a JSON value holding a number into a f64. Exchanges send numbers either as JSON numbers
+/// or as strings (to avoid floating point rounding), so both are accepted.
+pub fn parse_json_f64(value: &Value) -> Result<f64, error::Error> {
+    match *value {
+        Value::String(ref s) => {
+            s.parse::<f64>()
+                .map_err(|e| error::Error::new(error::ErrorKind::BadParse).with_source(e))
+        }
+        Value::Number(_) => value.as_f64().ok_or_else(|| error::ErrorKind::BadParse.into()),
+        _ => Err(error::ErrorKind::BadParse.into()),
+    }
+}
+
+/// Parse a JSON value holding a number into a Decimal. Strings are parsed exactly, JSON numbers
+/// from their shortest representation.
+pub fn parse_json_decimal(value: &Value) -> Result<Decimal, error::Error> {
+    let number = match *value {
+        Value::String(ref s) => s.clone(),
+        Value::Number(ref n) => n.to_string(),
+        _ => return Err(error::ErrorKind::BadParse.into()),
+    };
+    number.parse::<Decimal>()
+        .map_err(|e| error::Error::new(error::ErrorKind::BadParse).with_source(e))
+}
+
+/// Return the content of a JSON value holding an identifier (string or number) as a String.
+pub fn json_to_string(value: &Value) -> Result<String, error::Error> {
+    match *value {
+        Value::String(ref s) => Ok(s.clone()),
+        Value::Number(ref n) => Ok(n.to_string()),
+        _ => Err(error::ErrorKind::BadParse.into()),
+    }
+}
+
+/// Parse a UTC date (such as "2014-02-10 04:23:23" with format "%Y-%m-%d %H:%M:%S") into a UNIX
+/// timestamp in ms.
+pub fn parse_utc_date_ms(date: &str, format: &str) -> Result<i64, error::Error> {
+    let tm = time::strptime(date, format).map_err(|_| error::ErrorKind::BadParse)?;
+    let timespec = tm.to_timespec();
+    Ok(timespec.sec * 1000 + (timespec.nsec as i64) / 1000 / 1000)
+}
+
+/// Filter out the trades which are not strictly more recent than the `since` trade id and compute
+/// the id to use as the next cursor. Used for exchanges which do not support a cursor natively.
+/// Ids are compared numerically.
+///
+/// The last value returned is true if every trade returned is more recent than `since`: the
+/// exchange only returned its recent trades, so the ones in between may have been missed.
+pub fn filter_trades_since(trades: Vec<Trade>,
+                           since: Option<&str>)
+                           -> Result<(Vec<Trade>, Option<String>, bool), error::Error> {
+    let since = match since {
+        Some(since) => Some(since.parse::<u64>().map_err(|_| error::ErrorKind::InvalidArguments)?),
+        None => None,
+    };
+
+    let mut result = Vec::with_capacity(trades.len());
+    let mut reached = false;
+    for trade in trades {
+        let id = match trade.id {
+            Some(ref id) => id.parse::<u64>().map_err(|_| error::ErrorKind::BadParse)?,
+            None => return Err(error::ErrorKind::BadParse.into()),
+        };
+        if since.map_or(true, |since| id > since) {
+            result.push((id, trade));
+        } else {
+            reached = true;
+        }
+    }
+    // Ids are increasing with time, so sorting by id gives the chronological order
+    result.sort_by_key(|&(id, _)| id);
+
+    let gap = since.is_some() && !reached && !result.is_empty();
+    let last = result.last().map(|&(id, _)| id).or(since);
+    let trades = result.into_iter().map(|(_, trade)| trade).collect();
+    Ok((trades, last.map(|last| last.to_string()), gap))
+}
+
+/// Parse a JSON array of `[price, volume, ...]` entries into a Vec of (price, volume).
+pub fn parse_offers(offers: &Value) -> Result<Vec<(Price, Volume)>, error::Error> {
+    let offers = offers.as_array().ok_or(error::ErrorKind::BadParse)?;
+    let mut result = Vec::with_capacity(offers.len());
+    for offer in offers {
+        let offer = offer.as_array().ok_or(error::ErrorKind::BadParse)?;
+        if offer.len() < 2 {
+            return Err(error::ErrorKind::BadParse.into());
+        }
+        result.push((parse_json_decimal(&offer[0])?, parse_json_decimal(&offer[1])?));
+    }
+    Ok(result)
+}
+
+/// Sort asks by ascending price and bids by descending price, so the best offer comes first.
+pub fn sort_offers(asks: &mut Vec<(Price, Volume)>, bids: &mut Vec<(Price, Volume)>) {
+    asks.sort_by(|a, b| a.0.cmp(&b.0));
+    bids.sort_by(|a, b| b.0.cmp(&a.0));
+}
@@ -0,0 +1,349 @@
+//! Use this module to keep the API keys of several accounts in an encrypted file.
+//!
+//! A keystore holds accounts made of the same fields as the accounts of a configuration file
+//! (see the `credentials` module), encrypted with ChaCha20-Poly1305 under a key derived from a
+//! passphrase with scrypt. The encryption is authenticated: a wrong passphrase or a modified file
+//! is reported as an error, and is never decrypted to garbage. Each save uses a new random salt
+//! and nonce, and the file is only readable by its owner on Unix.
+//!
+//! The `coinnect-keystore` binary adds, lists and removes the accounts of a keystore.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use std::env;
+//!
+//! use coinnect::credentials::{Account, Secret};
+//! use coinnect::keystore::Keystore;
+//!
+//! let path = env::temp_dir().join("coinnect_doc.keystore");
+//! let mut fields = HashMap::new();
+//! fields.insert("exchange".to_string(), Secret::new("kraken"));
+//! fields.insert("api_key".to_string(), Secret::new("123456789ABCDEF"));
+//! fields.insert("api_secret".to_string(), Secret::new("c2VjcmV0"));
+//!
+//! // A low cost keeps the example fast, keep the default in production
+//! let mut keystore = Keystore::create(path.clone(), "passphrase").cost(10);
+//! keystore.add("account_kraken", fields).unwrap();
+//! keystore.save().unwrap();
+//!
+//! let account = Account::from_keystore("account_kraken", &path, "passphrase").unwrap();
+//! ```
+
+use crypto::aead::{AeadDecryptor, AeadEncryptor};
+use crypto::chacha20poly1305::ChaCha20Poly1305;
+use crypto::scrypt::{self, ScryptParams};
+use rand::{OsRng, Rng};
+use rustc_serialize::base64::{STANDARD, ToBase64, FromBase64};
+use serde_json;
+use serde_json::Value;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use credentials::{Account, Secret};
+use error::{Error, ErrorKind};
+use helpers;
+
+/// Version of the file format.
+const VERSION: u64 = 1;
+/// Default scrypt cost: 2^15 iterations, using 32 MiB of memory.
+const DEFAULT_LOG_N: u8 = 15;
+/// Maximum scrypt cost: 2^20 iterations, using 1 GiB of memory. The cost is read from the file
+/// before it can be authenticated, so a modified file must not make `open` exhaust the memory.
+const MAX_LOG_N: u8 = 20;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 8;
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+
+/// The accounts of an encrypted file, decrypted in memory. Its Debug output only shows the path
+/// and the names of the accounts.
+pub struct Keystore {
+    path: PathBuf,
+    passphrase: Secret,
+    log_n: u8,
+    accounts: BTreeMap<String, HashMap<String, Secret>>,
+}
+
+impl Keystore {
+    /// Create a new empty Keystore, saved to the path specified by `save` only.
+    pub fn create(path: PathBuf, passphrase: &str) -> Keystore {
+        Keystore {
+            path: path,
+            passphrase: Secret::new(passphrase),
+            log_n: DEFAULT_LOG_N,
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    /// Open the keystore saved to the path specified. Return a ConfigError if the file cannot be
+    /// read, is not a keystore, or if the passphrase is wrong or the file was modified.
+    pub fn open(path: PathBuf, passphrase: &str) -> Result<Keystore, Error> {
+        let mut content = String::new();
+        if let Err(e) = File::open(&path).and_then(|mut f| f.read_to_string(&mut content)) {
+            let reason = format!("Cannot read {}", path.display());
+            return Err(Error::new(ErrorKind::ConfigError(reason)).with_source(e));
+        }
+
+        let corrupted = || config_error(format!("{} is not a valid keystore", path.display()));
+        let header: Value = serde_json::from_str(&content)
+            .map_err(|e| corrupted().with_source(e))?;
+        if header.get("version").and_then(|v| v.as_u64()) != Some(VERSION) ||
+           header.get("kdf").and_then(|v| v.as_str()) != Some("scrypt") {
+            return Err(corrupted());
+        }
+        let log_n = match header.get("log_n").and_then(|v| v.as_u64()) {
+            Some(log_n) if log_n >= 1 && log_n <= MAX_LOG_N as u64 => log_n as u8,
+            _ => return Err(corrupted()),
+        };
+        let bytes = |name: &str| {
+            header.get(name)
+                .and_then(|v| v.as_str())
+                .and_then(|v| v.from_base64().ok())
+                .ok_or_else(&corrupted)
+        };
+        let salt = bytes("salt")?;
+        let nonce = bytes("nonce")?;
+        let ciphertext = bytes("ciphertext")?;
+        let tag = bytes("tag")?;
+        if salt.len() != SALT_LEN || nonce.len() != NONCE_LEN || tag.len() != TAG_LEN {
+            return Err(corrupted());
+        }
+
+        let passphrase = Secret::new(passphrase);
+        let mut key = derive_key(&passphrase, &salt, log_n);
+        let mut cipher = ChaCha20Poly1305::new(&key, &nonce, aad(log_n).as_bytes());
+        helpers::zeroize(&mut key);
+        let mut plaintext = vec![0u8; ciphertext.len()];
+        if !cipher.decrypt(&ciphertext, &mut plaintext, &tag) {
+            // The buffer may hold a part of the plaintext
+            helpers::zeroize(&mut plaintext);
+            let reason = format!("Wrong passphrase for {}, or the file was modified",
+                                 path.display());
+            return Err(config_error(reason));
+        }
+        let plaintext = match String::from_utf8(plaintext) {
+            Ok(plaintext) => Secret::from(plaintext),
+            Err(e) => {
+                helpers::zeroize(&mut e.into_bytes());
+                return Err(corrupted());
+            }
+        };
+
+        let mut data: Value = serde_json::from_str(plaintext.expose())
+            .map_err(|e| corrupted().with_source(e))?;
+        let accounts = parse_accounts(&data);
+        // The values were copied to Secrets, or are dropped with the error
+        helpers::zeroize_json(&mut data);
+        let accounts = accounts.ok_or_else(&corrupted)?;
+
+        Ok(Keystore {
+            path: path,
+            passphrase: passphrase,
+            log_n: log_n,
+            accounts: accounts,
+        })
+    }
+
+    /// Set the cost of the key derivation to 2^log_n scrypt iterations (15 by default, from 1 to
+    /// 20). Each increment doubles the time and memory needed to try a passphrase.
+    pub fn cost(mut self, log_n: u8) -> Keystore {
+        self.log_n = log_n.max(1).min(MAX_LOG_N);
+        self
+    }
+
+    /// Return the path of the keystore.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the names of the accounts, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        self.accounts.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// Return the account specified. Return a ConfigError if there is no such account.
+    pub fn account(&self, name: &str) -> Result<Account, Error> {
+        match self.accounts.get(name) {
+            Some(fields) => {
+                let location = format!("account {} of {}", name, self.path.display());
+                Account::from_map(None, fields, &location)
+            }
+            None => Err(config_error(format!("No account {} in {}", name, self.path.display()))),
+        }
+    }
+
+    /// Add the account specified by its fields (`exchange`, `api_key`, `api_secret`, ...),
+    /// replacing the account of the same name if any. Return a ConfigError, and leave the
+    /// keystore unchanged, if a required field is missing.
+    pub fn add(&mut self, name: &str, fields: HashMap<String, Secret>) -> Result<(), Error> {
+        let location = format!("account {}", name);
+        Account::from_map(None, &fields, &location)?;
+        self.accounts.insert(name.to_string(), fields);
+        Ok(())
+    }
+
+    /// Remove the account specified. Return false if there was no such account.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.accounts.remove(name).is_some()
+    }
+
+    /// Encrypt the accounts and write them to the path of the keystore, replacing the file.
+    pub fn save(&self) -> Result<(), Error> {
+        let plaintext = accounts_json(&self.accounts);
+
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        let mut rng = OsRng::new().map_err(|e| {
+                config_error("Cannot generate random numbers".to_string()).with_source(e)
+            })?;
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce);
+
+        let mut key = derive_key(&self.passphrase, &salt, self.log_n);
+        let mut cipher = ChaCha20Poly1305::new(&key, &nonce, aad(self.log_n).as_bytes());
+        helpers::zeroize(&mut key);
+        let mut ciphertext = vec![0u8; plaintext.expose().len()];
+        let mut tag = [0u8; TAG_LEN];
+        cipher.encrypt(plaintext.expose().as_bytes(), &mut ciphertext, &mut tag);
+
+        let header = json!({
+            "version": VERSION,
+            "kdf": "scrypt",
+            "log_n": self.log_n,
+            "salt": salt.to_base64(STANDARD),
+            "nonce": nonce.to_base64(STANDARD),
+            "ciphertext": ciphertext.to_base64(STANDARD),
+            "tag": tag.to_base64(STANDARD),
+        });
+        write_file(&self.path, &header.to_string())
+    }
+}
+
+impl fmt::Debug for Keystore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Keystore")
+            .field("path", &self.path)
+            .field("accounts", &self.names())
+            .finish()
+    }
+}
+
+/// Return the accounts of the decrypted JSON document, or None if it is not an object of objects.
+fn parse_accounts(data: &Value) -> Option<BTreeMap<String, HashMap<String, Secret>>> {
+    let mut accounts = BTreeMap::new();
+    for (name, fields) in data.as_object()? {
+        let fields = fields.as_object()?
+            .iter()
+            .filter_map(|(field, value)| value.as_str().map(|value| (field, value)))
+            .map(|(field, value)| (field.clone(), Secret::new(value)))
+            .collect();
+        accounts.insert(name.clone(), fields);
+    }
+    Some(accounts)
+}
+
+/// Serialize the accounts to JSON straight into a Secret, so that no plain copy of the secrets is
+/// left in memory once it is dropped. The buffer is large enough to never be reallocated.
+fn accounts_json(accounts: &BTreeMap<String, HashMap<String, Secret>>) -> Secret {
+    // Escaping writes at most 6 bytes per byte, plus the quotes and separators
+    let mut capacity = 2;
+    for (name, fields) in accounts {
+        capacity += 6 * name.len() + 6;
+        for (field, value) in fields {
+            capacity += 6 * (field.len() + value.expose().len()) + 6;
+        }
+    }
+    let mut json = String::with_capacity(capacity);
+    let capacity = json.capacity();
+
+    json.push('{');
+    for (i, (name, fields)) in accounts.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        push_json_string(&mut json, name);
+        json.push_str(":{");
+        for (j, (field, value)) in fields.iter().enumerate() {
+            if j > 0 {
+                json.push(',');
+            }
+            push_json_string(&mut json, field);
+            json.push(':');
+            push_json_string(&mut json, value.expose());
+        }
+        json.push('}');
+    }
+    json.push('}');
+
+    debug_assert_eq!(json.capacity(), capacity);
+    Secret::from(json)
+}
+
+/// Append the value specified as a JSON string, without any intermediate allocation.
+fn push_json_string(json: &mut String, value: &str) {
+    const HEX: &'static [u8] = b"0123456789abcdef";
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                json.push_str("\\u00");
+                json.push(HEX[c as usize >> 4] as char);
+                json.push(HEX[c as usize & 0xf] as char);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+fn config_error(reason: String) -> Error {
+    ErrorKind::ConfigError(reason).into()
+}
+
+/// The additional authenticated data of the encryption: the parameters not encrypted, so that
+/// they cannot be changed without the tag being invalid.
+fn aad(log_n: u8) -> String {
+    format!("coinnect-keystore:{}:scrypt:{}", VERSION, log_n)
+}
+
+fn derive_key(passphrase: &Secret, salt: &[u8], log_n: u8) -> Vec<u8> {
+    let mut key = vec![0u8; KEY_LEN];
+    let params = ScryptParams::new(log_n, SCRYPT_R, SCRYPT_P);
+    scrypt::scrypt(passphrase.expose().as_bytes(), salt, &params, &mut key);
+    key
+}
+
+/// Write the file through a temporary file renamed over it, so that a crash never leaves a
+/// truncated keystore.
+fn write_file(path: &Path, content: &str) -> Result<(), Error> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    owner_only(&mut options);
+    options.open(&tmp_path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|e| config_error(format!("Cannot write {}", path.display())).with_source(e))
+}
+
+#[cfg(unix)]
+fn owner_only(options: &mut OpenOptions) {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+}
+
+#[cfg(not(unix))]
+fn owner_only(_: &mut OpenOptions) {}
@@ -1,77 +1,103 @@
 //! Use this module to interact with the raw-original API provided by Kraken.
 //! WARNING: Special attention should be paid to error management: parsing number, etc.
 
-use crypto::digest::Digest;
-use crypto::hmac::Hmac;
-use crypto::mac::Mac;
-use crypto::sha2::{Sha256, Sha512};
-
-use hyper_native_tls::NativeTlsClient;
-use hyper::Client;
-use hyper::header;
-use hyper::net::HttpsConnector;
-
-use rustc_serialize::base64::{STANDARD, ToBase64, FromBase64};
-
-use serde_json;
 use serde_json::Value;
 use serde_json::value::Map;
 
 use std::collections::HashMap;
-use std::io::Read;
-use std::thread;
-use std::time::Duration;
 use std::path::PathBuf;
-use std::fs::File;
 use std::str;
-use std::iter::repeat;
 
+use config::ClientConfig;
+use credentials::{Account, Credentials};
 use error;
+use error::ResponseContext;
+use exchange::Exchange;
 use helpers;
+use market::MarketCache;
+use nonce::NonceGenerator;
+use otp::Otp;
+use rate_limiter::RateLimiter;
+use retry::RetryPolicy;
+use scope::Scope;
+use transport::{Transport, HttpsTransport, HttpRequest};
+use validator::OrderValidator;
 
 use kraken::utils;
 
-header! {
-    #[doc(hidden)]
-    (KeyHeader, "API-Key") => [String]
-}
-
-header! {
-    #[doc(hidden)]
-    (SignHeader, "API-Sign") => [String]
-}
-
 #[derive(Debug)]
 pub struct KrakenApi {
-    last_request: i64, // unix timestamp in ms, to avoid ban
-    api_key: String,
-    api_secret: String,
-    http_client: Client,
+    credentials: Credentials,
+    base_url: String,
+    rate_limiter: RateLimiter,
+    private_rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
+    nonce_generator: NonceGenerator,
+    scope: Scope,
+    market_cache: MarketCache,
+    order_validator: OrderValidator,
+    last_response: Option<ResponseContext>,
+    otp: Option<Otp>,
+    transport: Box<Transport>,
 }
 
 
 impl KrakenApi {
     /// Create a new KrakenApi by providing an API key & API secret
-    pub fn new(api_key: &str, api_secret: &str) -> KrakenApi {
-        let ssl = NativeTlsClient::new().unwrap();
-        let connector = HttpsConnector::new(ssl);
+    pub fn new(api_key: &str, api_secret: &str) -> Result<KrakenApi, error::Error> {
+        Ok(KrakenApi::with_transport(api_key, api_secret, HttpsTransport::new()?))
+    }
+
+    /// Create a new KrakenApi sending its requests through the Transport specified instead of
+    /// HTTPS. This is mostly useful to test the client with a `MockTransport`.
+    pub fn with_transport<T>(api_key: &str, api_secret: &str, transport: T) -> KrakenApi
+        where T: Transport + 'static
+    {
+        KrakenApi::with_config(api_key, api_secret, ClientConfig::default(), transport)
+    }
 
+    /// Create a new KrakenApi using the configuration specified (base URL, ...) and sending its
+    /// requests through the Transport specified.
+    pub fn with_config<T>(api_key: &str,
+                          api_secret: &str,
+                          config: ClientConfig,
+                          transport: T)
+                          -> KrakenApi
+        where T: Transport + 'static
+    {
         KrakenApi {
-            last_request: 0,
-            api_key: api_key.to_string(),
-            api_secret: api_secret.to_string(),
-            http_client: Client::with_connector(connector),
+            credentials: Credentials::new(api_key, api_secret),
+            base_url: config.base_url_or(utils::BASE_URL),
+            rate_limiter: config.rate_limiter_or(utils::rate_limiter()),
+            private_rate_limiter: config.private_rate_limiter_or(utils::private_rate_limiter()),
+            retry_policy: config.retry_policy_or(RetryPolicy::new()),
+            nonce_generator: config.nonce_generator_or(NonceGenerator::shared()),
+            scope: config.scope_or(Scope::default()),
+            market_cache: config.market_cache_or(MarketCache::default()),
+            order_validator: config.order_validator_or(OrderValidator::default()),
+            last_response: None,
+            otp: None,
+            transport: Box::new(transport),
         }
     }
 
-    /// Create a new KrakenApi from a json configuration file. This file must follow this structure:
+    /// Send the one-time password specified with every private request. Required when the API
+    /// key is protected by a two-factor authentication.
+    pub fn with_otp(mut self, otp: Otp) -> KrakenApi {
+        self.otp = Some(otp);
+        self
+    }
+
+    /// Create a new KrakenApi from a JSON or TOML configuration file (see the `credentials`
+    /// module). A JSON file must follow this structure:
     ///
     /// ```ignore
     /// {
     ///     "account_kraken": {
     ///         "exchange"  : "kraken",
     ///         "api_key"   : "123456789ABCDEF",
-    ///         "api_secret": "ABC&EF?abcdef"
+    ///         "api_secret": "ABC&EF?abcdef",
+    ///         "otp_secret": "JBSWY3DPEHPK3PXP"
     ///     },
     ///     "account_poloniex": {
     ///         "exchange"  : "poloniex",
@@ -82,26 +108,53 @@ impl KrakenApi {
     /// ```
     /// For this example, you could use load your Kraken account with
     /// `new_from_file("account_kraken", Path::new("/keys.json"))`
-    pub fn new_from_file(config_name: &str, path: PathBuf) -> KrakenApi {
-        let mut f = File::open(&path).unwrap();
-        let mut buffer = String::new();
-        f.read_to_string(&mut buffer).unwrap();
+    ///
+    /// The optional field `otp` holds a static two-factor password, and `otp_secret` the base32
+    /// secret of a TOTP one.
+    pub fn new_from_file(config_name: &str, path: PathBuf) -> Result<KrakenApi, error::Error> {
+        let account = Account::from_exchange_file(Exchange::Kraken, config_name, &path)?;
+        let credentials = account.credentials();
 
-        let data: Value = serde_json::from_str(&buffer).unwrap();
-        let json_obj = data.as_object().unwrap().get(config_name).unwrap();
-        let api_key = json_obj.get("api_key").unwrap().as_str().unwrap();
-        let api_secret = json_obj.get("api_secret").unwrap().as_str().unwrap();
+        let api = KrakenApi::new(credentials.api_key(), credentials.api_secret())?;
+        match account.otp() {
+            Some(otp) => Ok(api.with_otp(otp.clone())),
+            None => Ok(api),
+        }
+    }
 
-        KrakenApi::new(api_key, api_secret)
+    /// Return the cache of the markets listed by Kraken (see `ExchangeApi::markets`).
+    pub fn market_cache(&self) -> &MarketCache {
+        &self.market_cache
     }
 
-    fn block_or_continue(&self) {
-        let threshold = 2000; // 1 request/2sec
-        let delay = helpers::get_unix_timestamp_ms() - self.last_request;
-        if delay < threshold {
-            let duration_ms = Duration::from_millis(delay as u64);
-            thread::sleep(duration_ms);
-        }
+    /// Return the validator of the orders placed through the generic API.
+    pub fn order_validator(&self) -> &OrderValidator {
+        &self.order_validator
+    }
+
+    /// Return the endpoint called by the last request and the response received, if any. The
+    /// generic API attaches them to the errors raised while reading the response.
+    pub fn last_response(&self) -> Option<&ResponseContext> {
+        self.last_response.as_ref()
+    }
+
+    /// Send a request to the endpoint specified and deserialize the response. Errors carry the
+    /// endpoint, and the status and body of the response when one was received.
+    fn send(&mut self,
+            endpoint: &str,
+            request: &HttpRequest)
+            -> Result<Map<String, Value>, error::Error> {
+        let response = self.transport
+            .send(request)
+            .map_err(|e| e.with_endpoint(Exchange::Kraken, endpoint))?;
+        self.last_response = Some(ResponseContext::new(Exchange::Kraken,
+                                                       endpoint,
+                                                       response.status,
+                                                       &response.body));
+        utils::deserialize_json(response.body.clone()).map_err(|e| {
+            e.with_endpoint(Exchange::Kraken, endpoint)
+                .with_response(response.status, &response.body)
+        })
     }
 
     fn public_query(&mut self,
@@ -109,73 +162,63 @@ impl KrakenApi {
                     params: &mut HashMap<&str, &str>)
                     -> Result<Map<String, Value>, error::Error> {
         helpers::strip_empties(params);
-        let url = "https://api.kraken.com/0/public/".to_string() + method + "?" +
+        let url = self.base_url.clone() + "/0/public/" + method + "?" +
                   &helpers::url_encode_hashmap(&params);
 
-        self.block_or_continue();
-        let mut response = match self.http_client.get(&url).send() {
-            Ok(response) => response,
-            Err(_) => return Err(error::Error::ServiceUnavailable),
-        };
-        self.last_request = helpers::get_unix_timestamp_ms();
-        let mut buffer = String::new();
-        response.read_to_string(&mut buffer).unwrap();
-        return utils::deserialize_json(buffer);
+        let retry_policy = self.retry_policy.clone();
+        retry_policy.run(|| {
+                             self.rate_limiter.acquire(1.0);
+                             self.send(method, &HttpRequest::get(&url))
+                         },
+                         utils::is_retryable)
     }
 
     fn private_query(&mut self,
                      method: &str,
                      mut params: &mut HashMap<&str, &str>)
                      -> Result<Map<String, Value>, error::Error> {
-        let url = "https://api.kraken.com/0/private/".to_string() + method;
-
-        let urlpath = "/0/private/".to_string() + method;
-
-        let nonce = helpers::get_unix_timestamp_ms().to_string();
         helpers::strip_empties(&mut params);
 
-        let mut params = params.clone(); // TODO: Remove .clone()
-        params.insert("nonce", &nonce);
+        self.scope
+            .check(utils::required_scope(method))
+            .map_err(|e| e.with_endpoint(Exchange::Kraken, method))?;
 
-        let postdata = helpers::url_encode_hashmap(&params);
+        let retry_policy = self.retry_policy.for_request(utils::is_read_only(method));
+        retry_policy.run(|| self.signed_query(method, params), utils::is_retryable)
+    }
 
-        let signature = self.create_signature(urlpath, &postdata, &nonce);
+    /// Sign the private request specified with a new nonce and send it.
+    fn signed_query(&mut self,
+                    method: &str,
+                    params: &HashMap<&str, &str>)
+                    -> Result<Map<String, Value>, error::Error> {
+        // The signature covers the path of the official API, whatever the base URL is
+        let urlpath = "/0/private/".to_string() + method;
+        let url = self.base_url.clone() + &urlpath;
 
-        let mut custom_header = header::Headers::new();
-        custom_header.set(KeyHeader(self.api_key.clone()));
-        custom_header.set(SignHeader(signature));
+        self.private_rate_limiter.acquire(utils::call_cost(method));
+        let nonce = self.nonce_generator.next()?.to_string();
 
-        let mut res = match self.http_client
-            .post(&url)
-            .body(&postdata)
-            .headers(custom_header)
-            .send() {
-            Ok(res) => res,
-            Err(_) => return Err(error::Error::ServiceUnavailable),
-        };
+        let otp = self.otp.as_ref().map(|otp| otp.current());
 
-        let mut buffer = String::new();
-        res.read_to_string(&mut buffer).unwrap();
-        return utils::deserialize_json(buffer);
-    }
+        let mut params = params.clone(); // TODO: Remove .clone()
+        params.insert("nonce", &nonce);
+        if let Some(ref otp) = otp {
+            params.insert("otp", otp);
+        }
 
-    fn create_signature(&self, urlpath: String, postdata: &str, nonce: &str) -> String {
-        let message_presha256 = nonce.to_string() + postdata;
+        let postdata = helpers::url_encode_hashmap(&params);
 
-        let mut sha256 = Sha256::new();
-        sha256.input_str(&message_presha256);
-        let mut buffer: Vec<u8> = repeat(0).take((sha256.output_bits() + 7) / 8).collect();
-        sha256.result(&mut buffer);
+        let signature = utils::create_signature(self.credentials.api_secret(),
+                                                &urlpath,
+                                                &postdata,
+                                                &nonce)?;
 
-        let mut concatenated = urlpath.as_bytes().to_vec();
-        for elem in buffer {
-            concatenated.push(elem);
-        }
+        let request = HttpRequest::post(&url, &postdata)
+            .with_header("API-Key", self.credentials.api_key())
+            .with_header("API-Sign", &signature);
 
-        let hmac_key = self.api_secret.from_base64().unwrap();
-        let mut hmac = Hmac::new(Sha512::new(), &hmac_key);
-        hmac.input(&concatenated);
-        hmac.result().code().to_base64(STANDARD)
+        self.send(method, &request)
     }
 
     /// Result: Server's time
@@ -0,0 +1,178 @@
+//! Use this module to identify the errors returned by Kraken.
+//!
+//! Kraken errors are strings made of a category and a message, sometimes followed by details:
+//! "EOrder:Insufficient funds", "EGeneral:Invalid arguments:volume". The generic API maps them
+//! into the `ErrorKind` of the Error returned, which keeps the original string (see
+//! `Error::message`).
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::error::ErrorKind;
+//! use coinnect::kraken::error::KrakenError;
+//!
+//! let error = KrakenError::from_message("EOrder:Order minimum not met");
+//! assert_eq!(error, KrakenError::OrderMinimumNotMet);
+//! assert_eq!(error.kind(), ErrorKind::InvalidOrder);
+//! ```
+
+use error::ErrorKind;
+
+/// An error of the Kraken API, as listed in its documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KrakenError {
+    // EGeneral
+    InvalidArguments,
+    TemporaryLockout,
+    PermissionDenied,
+    UnknownMethod,
+    InternalError,
+    // EAPI
+    InvalidKey,
+    InvalidSignature,
+    InvalidNonce,
+    ApiRateLimitExceeded,
+    FeatureDisabled,
+    // EQuery
+    UnknownAssetPair,
+    UnknownAsset,
+    // EService
+    Unavailable,
+    Busy,
+    MarketInCancelOnlyMode,
+    MarketInPostOnlyMode,
+    MarketInLimitOnlyMode,
+    DeadlineElapsed,
+    // EDatabase
+    DatabaseInternalError,
+    // ETrade
+    Locked,
+    InvalidRequest,
+    // EOrder
+    CannotOpenPosition,
+    CannotOpenOpposingPosition,
+    MarginAllowanceExceeded,
+    MarginLevelTooLow,
+    MarginPositionSizeExceeded,
+    InsufficientMargin,
+    InsufficientFunds,
+    OrderMinimumNotMet,
+    OrdersLimitExceeded,
+    PositionsLimitExceeded,
+    RateLimitExceeded,
+    ScheduledOrdersLimitExceeded,
+    UnknownPosition,
+    UnknownOrder,
+    InvalidPrice,
+    TradingAgreementRequired,
+    // EFunding
+    UnknownWithdrawKey,
+    InvalidAmount,
+    UnknownFundingAsset,
+    /// An error missing from the documentation, holding the string returned.
+    Other(String),
+}
+
+impl KrakenError {
+    /// Return the KrakenError of a string returned by Kraken. The details following the message
+    /// ("EGeneral:Invalid arguments:volume") are ignored.
+    pub fn from_message(message: &str) -> KrakenError {
+        let code = match message.match_indices(':').nth(1) {
+            Some((index, _)) => &message[..index],
+            None => message,
+        };
+
+        match code {
+            "EGeneral:Invalid arguments" => KrakenError::InvalidArguments,
+            "EGeneral:Temporary lockout" => KrakenError::TemporaryLockout,
+            "EGeneral:Permission denied" => KrakenError::PermissionDenied,
+            "EGeneral:Unknown method" => KrakenError::UnknownMethod,
+            "EGeneral:Internal error" => KrakenError::InternalError,
+            "EAPI:Invalid key" => KrakenError::InvalidKey,
+            "EAPI:Invalid signature" => KrakenError::InvalidSignature,
+            "EAPI:Invalid nonce" => KrakenError::InvalidNonce,
+            "EAPI:Rate limit exceeded" => KrakenError::ApiRateLimitExceeded,
+            "EAPI:Feature disabled" => KrakenError::FeatureDisabled,
+            "EQuery:Unknown asset pair" => KrakenError::UnknownAssetPair,
+            "EQuery:Unknown asset" => KrakenError::UnknownAsset,
+            "EService:Unavailable" => KrakenError::Unavailable,
+            "EService:Busy" => KrakenError::Busy,
+            "EService:Market in cancel_only mode" => KrakenError::MarketInCancelOnlyMode,
+            "EService:Market in post_only mode" => KrakenError::MarketInPostOnlyMode,
+            "EService:Market in limit_only mode" => KrakenError::MarketInLimitOnlyMode,
+            "EService:Deadline elapsed" => KrakenError::DeadlineElapsed,
+            "EDatabase:Internal error" => KrakenError::DatabaseInternalError,
+            "ETrade:Locked" => KrakenError::Locked,
+            "ETrade:Invalid request" => KrakenError::InvalidRequest,
+            "EOrder:Cannot open position" => KrakenError::CannotOpenPosition,
+            "EOrder:Cannot open opposing position" => KrakenError::CannotOpenOpposingPosition,
+            "EOrder:Margin allowance exceeded" => KrakenError::MarginAllowanceExceeded,
+            "EOrder:Margin level too low" => KrakenError::MarginLevelTooLow,
+            "EOrder:Margin position size exceeded" => KrakenError::MarginPositionSizeExceeded,
+            "EOrder:Insufficient margin" => KrakenError::InsufficientMargin,
+            "EOrder:Insufficient funds" => KrakenError::InsufficientFunds,
+            "EOrder:Order minimum not met" => KrakenError::OrderMinimumNotMet,
+            "EOrder:Orders limit exceeded" => KrakenError::OrdersLimitExceeded,
+            "EOrder:Positions limit exceeded" => KrakenError::PositionsLimitExceeded,
+            "EOrder:Rate limit exceeded" => KrakenError::RateLimitExceeded,
+            "EOrder:Scheduled orders limit exceeded" => KrakenError::ScheduledOrdersLimitExceeded,
+            "EOrder:Unknown position" => KrakenError::UnknownPosition,
+            "EOrder:Unknown order" => KrakenError::UnknownOrder,
+            "EOrder:Invalid price" => KrakenError::InvalidPrice,
+            "EOrder:Trading agreement required" => KrakenError::TradingAgreementRequired,
+            "EFunding:Unknown withdraw key" => KrakenError::UnknownWithdrawKey,
+            "EFunding:Invalid amount" => KrakenError::InvalidAmount,
+            "EFunding:Unknown asset" => KrakenError::UnknownFundingAsset,
+            _ => KrakenError::Other(message.to_string()),
+        }
+    }
+
+    /// Return the generic category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            KrakenError::InvalidArguments |
+            KrakenError::UnknownMethod |
+            KrakenError::UnknownAsset |
+            KrakenError::InvalidRequest |
+            KrakenError::UnknownWithdrawKey |
+            KrakenError::InvalidAmount |
+            KrakenError::UnknownFundingAsset => ErrorKind::InvalidArguments,
+            KrakenError::TemporaryLockout |
+            KrakenError::Locked => ErrorKind::AccountLocked,
+            KrakenError::PermissionDenied |
+            KrakenError::FeatureDisabled |
+            KrakenError::TradingAgreementRequired => ErrorKind::PermissionDenied,
+            KrakenError::InternalError |
+            KrakenError::Unavailable |
+            KrakenError::Busy |
+            KrakenError::DatabaseInternalError => ErrorKind::ServiceUnavailable,
+            KrakenError::InvalidKey |
+            KrakenError::InvalidSignature => ErrorKind::InvalidLogin,
+            KrakenError::InvalidNonce => ErrorKind::InvalidNonce,
+            KrakenError::ApiRateLimitExceeded |
+            KrakenError::RateLimitExceeded => ErrorKind::RateLimitExceeded,
+            KrakenError::UnknownAssetPair => ErrorKind::PairUnsupported,
+            KrakenError::MarginAllowanceExceeded |
+            KrakenError::MarginLevelTooLow |
+            KrakenError::MarginPositionSizeExceeded |
+            KrakenError::InsufficientMargin |
+            KrakenError::InsufficientFunds => ErrorKind::InsufficientFunds,
+            // The order does not fit the current mode of the market or its deadline, sending it
+            // again as is would be rejected the same way
+            KrakenError::MarketInCancelOnlyMode |
+            KrakenError::MarketInPostOnlyMode |
+            KrakenError::MarketInLimitOnlyMode |
+            KrakenError::DeadlineElapsed |
+            KrakenError::CannotOpenPosition |
+            KrakenError::CannotOpenOpposingPosition |
+            KrakenError::OrderMinimumNotMet |
+            KrakenError::OrdersLimitExceeded |
+            KrakenError::PositionsLimitExceeded |
+            KrakenError::ScheduledOrdersLimitExceeded |
+            KrakenError::UnknownPosition |
+            KrakenError::InvalidPrice => ErrorKind::InvalidOrder,
+            KrakenError::UnknownOrder => ErrorKind::UnknownOrder,
+            KrakenError::Other(ref message) => ErrorKind::ExchangeSpecificError(message.clone()),
+        }
+    }
+}
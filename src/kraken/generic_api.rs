@@ -6,48 +6,384 @@
 use serde_json::Value;
 use serde_json::value::Map;
 
+use std::collections::HashMap;
+
 use exchange::ExchangeApi;
 use kraken::api::KrakenApi;
 
-use error::Error;
+use currency::Currency;
+use decimal::{Decimal, MAX_SCALE};
+use error::{Error, ErrorKind, ResponseContext};
+use market::MarketCache;
 use pair::Pair;
-use types::Ticker;
+use types::{Ticker, Orderbook, OrderSide, OrderType, Trade, TradeHistory, Balance, Balances};
+use types::{Market, MarketInfo, Order, OrderId, OrderRequest, OrderStatus};
 use kraken::utils;
+use validator::OrderValidator;
 use helpers;
+use helpers::{GenericApi, get_pair_string, response_error, validate_order};
+
+impl GenericApi for KrakenApi {
+    fn market_cache(&self) -> &MarketCache {
+        KrakenApi::market_cache(self)
+    }
+
+    fn order_validator(&self) -> &OrderValidator {
+        KrakenApi::order_validator(self)
+    }
+
+    fn last_response(&self) -> Option<&ResponseContext> {
+        KrakenApi::last_response(self)
+    }
+
+    fn fallback_pair_string(pair: &Pair) -> Option<String> {
+        utils::get_pair_string(pair)
+    }
+}
 
 impl ExchangeApi for KrakenApi {
+    fn markets(&mut self) -> Result<Vec<Market>, Error> {
+        if let Some(markets) = self.market_cache().get() {
+            return Ok(markets);
+        }
+
+        let raw_response = self.get_tradable_asset_pairs("", "")?;
+
+        let markets = parse_markets(raw_response).map_err(|e| response_error(self, e))?;
+
+        self.market_cache().set(markets.clone());
+        Ok(markets)
+    }
+
     fn ticker(&mut self, pair: Pair) -> Result<Ticker, Error> {
-        let pair_name = match utils::get_pair_string(&pair) {
-            Some(name) => name,
-            None => return Err(Error::PairUnsupported),
-        };
+        let pair_name = get_pair_string(self, &pair)?;
 
         let raw_response = self.get_ticker_information(&pair_name)?;
 
-        let result = utils::parse_result(raw_response)?;
+        parse_ticker(raw_response, pair, &pair_name).map_err(|e| response_error(self, e))
+    }
 
-        let price = result[*pair_name]["c"][0].as_str().unwrap().parse::<f64>().unwrap();
-        let ask = result[*pair_name]["a"][0].as_str().unwrap().parse::<f64>().unwrap();
-        let bid = result[*pair_name]["b"][0].as_str().unwrap().parse::<f64>().unwrap();
-        let vol = result[*pair_name]["v"][1].as_str().unwrap().parse::<f64>().unwrap();
+    fn orderbook(&mut self, pair: Pair) -> Result<Orderbook, Error> {
+        let pair_name = get_pair_string(self, &pair)?;
 
-        Ok(Ticker {
-            timestamp: helpers::get_unix_timestamp_ms(),
-            pair: pair,
-            last_trade_price: price,
-            lowest_ask: ask,
-            highest_bid: bid,
-            volume: Some(vol),
-        })
+        let raw_response = self.get_order_book(&pair_name, "")?;
+
+        parse_orderbook(raw_response, pair, &pair_name).map_err(|e| response_error(self, e))
+    }
+
+    fn trades(&mut self, pair: Pair, since: Option<&str>) -> Result<TradeHistory, Error> {
+        let pair_name = get_pair_string(self, &pair)?;
+
+        let raw_response = self.get_recent_trades(&pair_name, since.unwrap_or(""))?;
+
+        parse_trades(raw_response, pair, &pair_name).map_err(|e| response_error(self, e))
+    }
+
+    fn balances(&mut self) -> Result<Balances, Error> {
+        let raw_response = self.get_account_balance()?;
+
+        parse_balances(raw_response).map_err(|e| response_error(self, e))
+    }
+
+    fn place_order(&mut self, order: OrderRequest) -> Result<OrderId, Error> {
+        let pair_name = get_pair_string(self, &order.pair)?;
+        let order = validate_order(self, order)?;
+        let type_order = match order.side {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        };
+        let (ordertype, price) = match order.order_type {
+            OrderType::Limit => {
+                let price = order.price.ok_or(ErrorKind::InvalidArguments)?;
+                ("limit", price.to_string())
+            }
+            OrderType::Market => ("market", "".to_string()),
+        };
+
+        let raw_response = self.add_standard_order(&pair_name,
+                                                   type_order,
+                                                   ordertype,
+                                                   &price,
+                                                   "",
+                                                   &order.volume.to_string(),
+                                                   "",
+                                                   "",
+                                                   "",
+                                                   "",
+                                                   "",
+                                                   "")?;
+
+        parse_order_id(raw_response).map_err(|e| response_error(self, e))
+    }
+
+    fn cancel_order(&mut self, id: OrderId) -> Result<(), Error> {
+        let raw_response = self.cancel_open_order(&id.0)?;
+
+        utils::parse_result(raw_response).map_err(|e| response_error(self, e))?;
+        Ok(())
+    }
+
+    fn open_orders(&mut self, pair: Pair) -> Result<Vec<Order>, Error> {
+        let pair_name = get_pair_string(self, &pair)?;
+
+        let raw_response = self.get_open_orders("", "")?;
 
+        parse_open_orders(raw_response, pair, &pair_name).map_err(|e| response_error(self, e))
     }
-    fn return_trade_history(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+}
+
+/// Return the markets of an AssetPairs response.
+fn parse_markets(raw_response: Map<String, Value>) -> Result<Vec<Market>, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let mut markets = Vec::with_capacity(result.len());
+    for (name, info) in result.iter() {
+        // Dark pools are the same pairs as their regular market
+        if utils::is_dark_pool(name) {
+            continue;
+        }
+        if let Ok(market) = parse_market(name, info) {
+            markets.push(market);
+        }
     }
-    fn return_order_book(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+    Ok(markets)
+}
+
+/// Return the market of an AssetPairs entry.
+fn parse_market(name: &str, info: &Value) -> Result<Market, Error> {
+    let base = info["base"].as_str().ok_or(ErrorKind::BadParse)?;
+    let quote = info["quote"].as_str().ok_or(ErrorKind::BadParse)?;
+    let base: Currency = utils::normalize_asset(base).parse()?;
+    let quote: Currency = utils::normalize_asset(quote).parse()?;
+
+    Ok(Market {
+        pair: Pair::new(base, quote),
+        symbol: name.to_string(),
+        info: market_info(info)?,
+    })
+}
+
+/// Return the ticker of the pair specified from a Ticker response.
+fn parse_ticker(raw_response: Map<String, Value>,
+                pair: Pair,
+                pair_name: &str)
+                -> Result<Ticker, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let ticker = result.get(pair_name).ok_or(ErrorKind::BadParse)?;
+    let price = helpers::parse_json_decimal(&ticker["c"][0])?;
+    let ask = helpers::parse_json_decimal(&ticker["a"][0])?;
+    let bid = helpers::parse_json_decimal(&ticker["b"][0])?;
+    let vol = helpers::parse_json_decimal(&ticker["v"][1])?;
+
+    Ok(Ticker {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        last_trade_price: price,
+        lowest_ask: ask,
+        highest_bid: bid,
+        volume: Some(vol),
+    })
+}
+
+/// Return the order book of the pair specified from a Depth response.
+fn parse_orderbook(raw_response: Map<String, Value>,
+                   pair: Pair,
+                   pair_name: &str)
+                   -> Result<Orderbook, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let book = result.get(pair_name).ok_or(ErrorKind::BadParse)?;
+    let mut asks = helpers::parse_offers(&book["asks"])?;
+    let mut bids = helpers::parse_offers(&book["bids"])?;
+    helpers::sort_offers(&mut asks, &mut bids);
+
+    Ok(Orderbook {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        asks: asks,
+        bids: bids,
+    })
+}
+
+/// Return the trades of the pair specified from a Trades response.
+fn parse_trades(raw_response: Map<String, Value>,
+                pair: Pair,
+                pair_name: &str)
+                -> Result<TradeHistory, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let entries = result.get(pair_name)
+        .and_then(|data| data.as_array())
+        .ok_or(ErrorKind::BadParse)?;
+    let mut trades = Vec::with_capacity(entries.len());
+    for entry in entries {
+        // <price>, <volume>, <time>, <buy/sell>, <market/limit>, <miscellaneous>
+        let entry = entry.as_array().ok_or(ErrorKind::BadParse)?;
+        if entry.len() < 5 {
+            return Err(ErrorKind::BadParse.into());
+        }
+
+        let side = match entry[3].as_str() {
+            Some("b") => OrderSide::Buy,
+            Some("s") => OrderSide::Sell,
+            _ => return Err(ErrorKind::BadParse.into()),
+        };
+        let order_type = match entry[4].as_str() {
+            Some("l") => Some(OrderType::Limit),
+            Some("m") => Some(OrderType::Market),
+            _ => None,
+        };
+
+        trades.push(Trade {
+            id: None,
+            timestamp: (helpers::parse_json_f64(&entry[2])? * 1000.0) as i64,
+            price: helpers::parse_json_decimal(&entry[0])?,
+            volume: helpers::parse_json_decimal(&entry[1])?,
+            side: side,
+            order_type: order_type,
+        });
     }
-    fn return_balances(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+
+    Ok(TradeHistory {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        trades: trades,
+        last: Some(helpers::json_to_string(result.get("last").ok_or(ErrorKind::BadParse)?)?),
+        gap: false,
+    })
+}
+
+/// Return the balances of a Balance response.
+fn parse_balances(raw_response: Map<String, Value>) -> Result<Balances, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    // Kraken only returns the total amount of each asset, staked and held amounts ("DOT.S",
+    // "USD.HOLD") being listed apart: add them to the total of their currency
+    let mut balances: HashMap<String, Balance> = HashMap::new();
+    for (asset, amount) in result.iter() {
+        let amount = helpers::parse_json_decimal(amount)?;
+        let balance = balances.entry(utils::normalize_asset(asset))
+            .or_insert(Balance {
+                total: Decimal::ZERO,
+                available: None,
+                on_hold: None,
+            });
+        balance.total = balance.total.checked_add(amount).map_err(|_| ErrorKind::BadParse)?;
     }
-}
\ No newline at end of file
+
+    Ok(Balances {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        balances: balances,
+    })
+}
+
+/// Return the id of the order placed from an AddOrder response.
+fn parse_order_id(raw_response: Map<String, Value>) -> Result<OrderId, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    match result.get("txid").and_then(|txid| txid[0].as_str()) {
+        Some(txid) => Ok(OrderId(txid.to_string())),
+        None => Err(ErrorKind::BadParse.into()),
+    }
+}
+
+/// Return the open orders of the pair specified from an OpenOrders response.
+fn parse_open_orders(raw_response: Map<String, Value>,
+                     pair: Pair,
+                     pair_name: &str)
+                     -> Result<Vec<Order>, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    // Order descriptions use the alternate pair name
+    let altname = utils::get_pair_altname(pair_name);
+
+    let open = result.get("open").and_then(|open| open.as_object()).ok_or(ErrorKind::BadParse)?;
+    let mut orders = Vec::new();
+    for (txid, info) in open.iter() {
+        let descr = &info["descr"];
+        match descr["pair"].as_str() {
+            Some(name) if name == altname || name == pair_name => (),
+            Some(_) => continue,
+            None => return Err(ErrorKind::BadParse.into()),
+        }
+
+        let side = match descr["type"].as_str() {
+            Some("buy") => OrderSide::Buy,
+            Some("sell") => OrderSide::Sell,
+            _ => return Err(ErrorKind::BadParse.into()),
+        };
+        let order_type = match descr["ordertype"].as_str() {
+            Some("limit") => Some(OrderType::Limit),
+            Some("market") => Some(OrderType::Market),
+            _ => None,
+        };
+        let price = match order_type {
+            Some(OrderType::Market) => None,
+            _ => Some(helpers::parse_json_decimal(&descr["price"])?),
+        };
+        let status = match info["status"].as_str() {
+            Some("pending") => OrderStatus::Pending,
+            Some("open") => OrderStatus::Open,
+            Some("closed") => OrderStatus::Filled,
+            Some("canceled") => OrderStatus::Canceled,
+            Some("expired") => OrderStatus::Expired,
+            _ => return Err(ErrorKind::BadParse.into()),
+        };
+
+        orders.push(Order {
+            id: OrderId(txid.clone()),
+            pair: pair,
+            side: side,
+            order_type: order_type,
+            price: price,
+            volume: helpers::parse_json_decimal(&info["vol"])?,
+            filled: Some(helpers::parse_json_decimal(&info["vol_exec"])?),
+            status: status,
+        });
+    }
+
+    Ok(orders)
+}
+
+/// Return the trading rules and fees of an entry of the AssetPairs response.
+fn market_info(info: &Value) -> Result<MarketInfo, Error> {
+    // A Decimal has at most MAX_SCALE decimals
+    let decimals = |field: &str| {
+        info[field]
+            .as_u64()
+            .filter(|&decimals| decimals <= MAX_SCALE as u64)
+            .map(|decimals| decimals as u32)
+            .ok_or(ErrorKind::BadParse)
+    };
+    // Fee schedules are [volume, percent fee] tuples, the first one is the lowest volume tier.
+    // Pairs without a maker schedule charge the same fee to makers and takers.
+    let taker_fee = helpers::parse_json_f64(&info["fees"][0][1])? / 100.0;
+    let maker_fee = match info["fees_maker"][0][1] {
+        Value::Null => taker_fee,
+        ref fee => helpers::parse_json_f64(fee)? / 100.0,
+    };
+    let min_volume = match info["ordermin"] {
+        Value::Null => None,
+        ref ordermin => Some(helpers::parse_json_decimal(ordermin)?),
+    };
+    let leverage = match info["leverage_buy"].as_array() {
+        Some(leverage) => {
+            leverage.iter()
+                .filter_map(|leverage| leverage.as_u64())
+                .map(|leverage| leverage as u32)
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    Ok(MarketInfo {
+        price_decimals: decimals("pair_decimals")?,
+        volume_decimals: decimals("lot_decimals")?,
+        min_volume: min_volume,
+        min_value: None,
+        leverage: leverage,
+        maker_fee: maker_fee,
+        taker_fee: taker_fee,
+    })
+}
@@ -2,5 +2,6 @@
 //! See examples for more informations.
 
 pub mod api;
+pub mod error;
 pub mod generic_api;
 pub mod utils;
@@ -1,110 +1,297 @@
 
-use bidir_map::BidirMap;
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::{Sha256, Sha512};
+
+use rustc_serialize::base64::{STANDARD, ToBase64, FromBase64};
+
 use serde_json;
 use serde_json::Value;
 use serde_json::value::Map;
 
+use currency::Currency;
 use error;
+use exchange::Exchange;
+use helpers;
 use pair::Pair;
-use pair::Pair::*;
-
-lazy_static! {
-    static ref PAIRS_STRING: BidirMap<Pair, &'static str> = {
-        let mut m = BidirMap::new();
-        m.insert(BTC_CAD_d, "XXBTZCAD.d");
-        m.insert(BTC_CAD,   "XXBTZCAD");
-        m.insert(BTC_EUR_d, "XXBTZEUR.d");
-        m.insert(BTC_EUR,   "XXBTZEUR");
-        m.insert(BTC_GBP_d, "XXBTZGBP.d");
-        m.insert(BTC_GBP,   "XXBTZGBP");
-        m.insert(BTC_JPY_d, "XXBTZJPY.d");
-        m.insert(BTC_JPY,   "XXBTZJPY");
-        m.insert(BTC_USD_d, "XXBTZUSD.d");
-        m.insert(BTC_USD,   "XXBTZUSD");
-        m.insert(ETC_BTC,   "XETCXXBT");
-        m.insert(ETC_ETH,   "XETCXETH");
-        m.insert(ETC_EUR,   "XETCZEUR");
-        m.insert(ETC_USD,   "XETCZUSD");
-        m.insert(ETH_BTC,   "XETHXXBT");
-        m.insert(ETH_CAD_d, "XETHZCAD.d");
-        m.insert(ETH_CAD,   "XETHZCAD");
-        m.insert(ETH_EUR_d, "XETHZEUR.d");
-        m.insert(ETH_EUR,   "XETHZEUR");
-        m.insert(ETH_GBP_d, "XETHZGBP.d");
-        m.insert(ETH_GBP,   "XETHZGBP");
-        m.insert(ETH_JPY_d, "XETHZJPY.d");
-        m.insert(ETH_JPY,   "XETHZJPY");
-        m.insert(ETH_USD_d, "XETHZUSD.d");
-        m.insert(ETH_USD,   "XETHZUSD");
-        m.insert(ETH_XBT_d, "XETHXXBT.d");
-        m.insert(ICN_BTC,   "XICNXXBT");
-        m.insert(ICN_ETH,   "XICNXETH");
-        m.insert(LTC_BTC,   "XLTCXXBT");
-        m.insert(LTC_EUR,   "XLTCZEUR");
-        m.insert(LTC_USD,   "XLTCZUSD");
-        m.insert(MLN_BTC,   "XMLNXXBT");
-        m.insert(MLN_ETH,   "XMLNXETH");
-        m.insert(REP_BTC,   "XREPXXBT");
-        m.insert(REP_ETH,   "XREPXETH");
-        m.insert(REP_EUR,   "XREPZEUR");
-        m.insert(REP_USD,   "XREPZUSD");
-        m.insert(USDT_USD,  "USDTZUSD");
-        m.insert(XDG_BTC,   "XXDGXXBT");
-        m.insert(XLM_BTC,   "XXLMXXBT");
-        m.insert(XLM_EUR,   "XXLMZEUR");
-        m.insert(XLM_USD,   "XXLMZUSD");
-        m.insert(XMR_BTC,   "XXMRXXBT");
-        m.insert(XMR_EUR,   "XXMRZEUR");
-        m.insert(XMR_USD,   "XXMRZUSD");
-        m.insert(XRP_BTC,   "XXRPXXBT");
-        m.insert(ZEC_BTC,   "XZECXXBT");
-        m.insert(ZEC_EUR,   "XZECZEUR");
-        m.insert(ZEC_USD,   "XZECZUSD");
-        m
+use rate_limiter::RateLimiter;
+use scope::Scope;
+use kraken::error::KrakenError;
+
+use std::iter::repeat;
+
+/// Root of the official REST API, used unless `ClientConfig::base_url` is set.
+pub const BASE_URL: &'static str = "https://api.kraken.com";
+
+/// The currencies Kraken prices its markets in.
+const QUOTE_CURRENCIES: &'static [Currency] = &[Currency::AUD,
+                                                Currency::CAD,
+                                                Currency::CHF,
+                                                Currency::EUR,
+                                                Currency::GBP,
+                                                Currency::JPY,
+                                                Currency::USD,
+                                                Currency::BTC,
+                                                Currency::ETH,
+                                                Currency::USDT];
+
+/// The asset names Kraken prefixes with X (crypto-currencies) or Z (fiat currencies). Only the
+/// assets listed before the prefixes were dropped have one, BCH, DASH and CHF have none.
+const PREFIXED_ASSETS: &'static [&'static str] = &["XETC", "XETH", "XICN", "XLTC", "XMLN",
+                                                   "XREP", "XXBT", "XXDG", "XXLM", "XXMR",
+                                                   "XXRP", "XZEC", "ZAUD", "ZCAD", "ZEUR",
+                                                   "ZGBP", "ZJPY", "ZUSD"];
+
+/// Return the name of the pair used by Kraken ("XXBTZEUR" for BTC/EUR, "DASHEUR" for DASH/EUR).
+/// The asset names are prefixed only in the legacy pairs of two prefixed assets, and in USDTZUSD.
+/// If the quote currency is not one Kraken prices its markets in, None is returned.
+pub fn get_pair_string(pair: &Pair) -> Option<String> {
+    if !QUOTE_CURRENCIES.contains(&pair.quote) {
+        return None;
+    }
+    let base = get_asset_string(&pair.base);
+    let quote = get_asset_string(&pair.quote);
+    let legacy = base != get_asset_altname(&pair.base) && quote != get_asset_altname(&pair.quote);
+    if legacy || *pair == Pair::USDT_USD {
+        Some(base + &quote)
+    } else {
+        Some(get_asset_altname(&pair.base).to_string() + get_asset_altname(&pair.quote))
+    }
+}
+
+/// Return the name of the dark pool of the pair used by Kraken ("XXBTZEUR.d" for BTC/EUR).
+/// If the quote currency is not one Kraken prices its markets in, None is returned.
+pub fn get_dark_pool_string(pair: &Pair) -> Option<String> {
+    get_pair_string(pair).map(|name| name + ".d")
+}
+
+/// Return the Pair of a pair name or alternate name used by Kraken, dark pools included
+/// ("XXBTZEUR", "XBTEUR" and "XXBTZEUR.d" are BTC/EUR, "DASHEUR" is DASH/EUR).
+/// The quote asset is the longest suffix naming one of the currencies Kraken prices its markets
+/// in, with or without its prefix. If the name cannot be parsed, None is returned.
+pub fn get_pair(pair_name: &str) -> Option<Pair> {
+    let name = if is_dark_pool(pair_name) {
+        &pair_name[..pair_name.len() - 2]
+    } else {
+        pair_name
     };
+
+    let (suffix, quote) = QUOTE_CURRENCIES.iter()
+        .flat_map(|quote| {
+            vec![(get_asset_string(quote), *quote),
+                 (get_asset_altname(quote).to_string(), *quote)]
+        })
+        .filter(|&(ref suffix, _)| name.len() > suffix.len() && name.ends_with(&suffix[..]))
+        .max_by_key(|&(ref suffix, _)| suffix.len())?;
+
+    let base = normalize_asset(&name[..name.len() - suffix.len()]).parse().ok()?;
+    Some(Pair::new(base, quote))
 }
 
-/// Return the name associated to pair used by Kraken
-/// If the Pair is not supported, None is returned.
-pub fn get_pair_string(pair: &Pair) -> Option<&&str> {
-    PAIRS_STRING.get_by_first(pair)
+/// Return true if the pair name used by Kraken is the one of a dark pool ("XXBTZEUR.d").
+pub fn is_dark_pool(pair_name: &str) -> bool {
+    pair_name.ends_with(".d")
 }
 
-/// Return the Pair enum associated to the string used by Kraken
-/// If the Pair is not supported, None is returned.
-pub fn get_pair_enum(pair: &str) -> Option<&Pair> {
-    PAIRS_STRING.get_by_second(&pair)
+/// Return the asset name used by Kraken for the currency specified: Bitcoin and Dogecoin are named
+/// XBT and XDG, and the legacy assets are prefixed with Z (fiat currencies) or X (the other ones).
+/// "BTC" becomes "XXBT", "EUR" becomes "ZEUR" and "BCH" stays "BCH". This is the inverse of
+/// `normalize_asset`.
+pub fn get_asset_string(currency: &Currency) -> String {
+    let name = get_asset_altname(currency);
+    PREFIXED_ASSETS.iter()
+        .find(|asset| &asset[1..] == name)
+        .map_or(name, |asset| *asset)
+        .to_string()
+}
+
+/// Return the asset name used by Kraken for the currency specified, without prefix ("XBT" for
+/// BTC, "EUR" for EUR).
+fn get_asset_altname(currency: &Currency) -> &'static str {
+    match *currency {
+        Currency::BTC => "XBT",
+        Currency::DOGE => "XDG",
+        other => other.code(),
+    }
+}
+
+/// Return the alternate name of a pair name used by Kraken ("XXBTZEUR" becomes "XBTEUR").
+/// Kraken uses the alternate name in order descriptions.
+pub fn get_pair_altname(pair_name: &str) -> String {
+    let (name, suffix) = match pair_name.find('.') {
+        Some(index) => pair_name.split_at(index),
+        None => (pair_name, ""),
+    };
+    if name.len() != 8 || !PREFIXED_ASSETS.contains(&&name[4..]) {
+        return pair_name.to_string();
+    }
+
+    let strip = |asset: &str| if PREFIXED_ASSETS.contains(&asset) {
+        asset[1..].to_string()
+    } else {
+        asset.to_string()
+    };
+    strip(&name[..4]) + &strip(&name[4..]) + suffix
+}
+
+/// Return the normalized currency code of an asset name used by Kraken.
+/// Kraken prefixes its legacy asset names with X (crypto-currencies) or Z (fiat currencies), and
+/// uses XBT for Bitcoin and XDG for Dogecoin: "XXBT" becomes "BTC" and "ZEUR" becomes "EUR".
+/// The suffixes of staked, opt-in rewards and held balances are removed as well: "DOT.S",
+/// "XBT.M" and "USD.HOLD" become "DOT", "BTC" and "USD".
+pub fn normalize_asset(asset: &str) -> String {
+    let asset = match asset.find('.') {
+        Some(index) => &asset[..index],
+        None => asset,
+    };
+    let name = if PREFIXED_ASSETS.contains(&asset) {
+        &asset[1..]
+    } else {
+        asset
+    };
+
+    match name {
+        "XBT" => "BTC".to_string(),
+        "XDG" => "DOGE".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Return the API-Sign header of a private request: the base64 HMAC-SHA512 of the URI path
+/// followed by the SHA256 of nonce + POST data, keyed with the base64-decoded API secret.
+/// Return an InvalidLogin error if the API secret is not valid base64.
+pub fn create_signature(api_secret: &str,
+                        urlpath: &str,
+                        postdata: &str,
+                        nonce: &str)
+                        -> Result<String, error::Error> {
+    let message_presha256 = nonce.to_string() + postdata;
+
+    let mut sha256 = Sha256::new();
+    sha256.input_str(&message_presha256);
+    let mut buffer: Vec<u8> = repeat(0).take((sha256.output_bits() + 7) / 8).collect();
+    sha256.result(&mut buffer);
+
+    let mut concatenated = urlpath.as_bytes().to_vec();
+    for elem in buffer {
+        concatenated.push(elem);
+    }
+
+    let mut hmac_key = match api_secret.from_base64() {
+        Ok(hmac_key) => hmac_key,
+        Err(_) => return Err(error::ErrorKind::InvalidLogin.into()),
+    };
+    let mut hmac = Hmac::new(Sha512::new(), &hmac_key);
+    helpers::zeroize(&mut hmac_key);
+    hmac.input(&concatenated);
+    Ok(hmac.result().code().to_base64(STANDARD))
+}
+
+/// Return the default rate limiter of the public methods of a client: 1 request per second, with
+/// bursts of up to 5 requests.
+pub fn rate_limiter() -> RateLimiter {
+    RateLimiter::new(5.0, 1.0)
+}
+
+/// Return the default call counter of the private methods of a client, as enforced by Kraken for a
+/// starter account: the counter is decreased by 1 every 3 seconds and is limited to 15.
+pub fn private_rate_limiter() -> RateLimiter {
+    RateLimiter::new(15.0, 1.0 / 3.0)
+}
+
+/// Return how much a call to the private method specified increases the call counter: ledger and
+/// trade history queries cost 2, order placement and cancellation are not counted and every other
+/// method costs 1.
+pub fn call_cost(method: &str) -> f64 {
+    match method {
+        "Ledgers" | "QueryLedgers" | "TradesHistory" | "QueryTrades" => 2.0,
+        "AddOrder" | "CancelOrder" => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// Return true if the private method specified only reads the account, so it can be sent again
+/// safely. DepositAddresses is not one of them since it may generate a new address.
+pub fn is_read_only(method: &str) -> bool {
+    match method {
+        "Balance" |
+        "TradeBalance" |
+        "OpenOrders" |
+        "ClosedOrders" |
+        "QueryOrders" |
+        "TradesHistory" |
+        "QueryTrades" |
+        "OpenPositions" |
+        "Ledgers" |
+        "QueryLedgers" |
+        "TradeVolume" |
+        "DepositMethods" |
+        "DepositStatus" |
+        "WithdrawInfo" |
+        "WithdrawStatus" => true,
+        _ => false,
+    }
+}
+
+/// Return the scope a client needs to call the private method specified: withdrawals need
+/// `Scope::Withdraw`, the methods which do not change anything `Scope::ReadOnly` and the other
+/// ones (orders, deposit addresses) `Scope::Trade`.
+pub fn required_scope(method: &str) -> Scope {
+    match method {
+        "Withdraw" | "WithdrawCancel" => Scope::Withdraw,
+        method if is_read_only(method) => Scope::ReadOnly,
+        _ => Scope::Trade,
+    }
+}
+
+/// Return true if a request which returned the result specified may succeed if sent again: the
+/// host could not be reached, answered with a server error, or returned an error meaning that it
+/// is overloaded or rate limiting the client.
+pub fn is_retryable(result: &Result<Map<String, Value>, error::Error>) -> bool {
+    match *result {
+        Ok(ref response) => {
+            parse_result(response.clone()).err().map_or(false, |e| e.is_retryable())
+        }
+        Err(ref e) => e.is_retryable(),
+    }
 }
 
 pub fn deserialize_json(json_string: String) -> Result<Map<String, Value>, error::Error> {
     let data: Value = match serde_json::from_str(&json_string) {
         Ok(data) => data,
-        Err(_) => return Err(error::Error::BadParse),
+        Err(e) => return Err(error::Error::new(error::ErrorKind::BadParse).with_source(e)),
     };
 
     match data.as_object() {
         Some(value) => Ok(value.clone()),
-        None => Err(error::Error::BadParse),
+        None => Err(error::ErrorKind::BadParse.into()),
     }
 }
 
 /// If error array is null, return the result (encoded in a json object)
-/// else return the error string found in array
+/// else return an error of the kind of the first error string found in array
 pub fn parse_result(response: Map<String, Value>) -> Result<Map<String, Value>, error::Error> {
-    let error_array = match response.get("error") {
-        Some(array) => array.as_array().unwrap(),
-        None => return Err(error::Error::BadParse),
+    let error_array = match response.get("error").and_then(|error| error.as_array()) {
+        Some(array) => array,
+        None => return Err(exchange_error(error::ErrorKind::BadParse)),
     };
     if error_array.is_empty() {
-        return Ok(response.get("result").unwrap().as_object().unwrap().clone());
-    }
-    let error_msg = error_array[0].as_str().unwrap().to_string();
-
-    match error_msg.as_ref() {
-        "EService:Unavailable" => Err(error::Error::ServiceUnavailable),
-        "EOrder:Rate limit exceeded" => Err(error::Error::RateLimitExceeded),
-        "EQuery:Unknown asset pair" => Err(error::Error::PairUnsupported),
-        "EGeneral:Invalid arguments" => Err(error::Error::InvalidArguments),
-        other => Err(error::Error::ExchangeSpecificError(other.to_string())),
+        return match response.get("result").and_then(|result| result.as_object()) {
+            Some(result) => Ok(result.clone()),
+            None => Err(exchange_error(error::ErrorKind::BadParse)),
+        };
     }
-}
\ No newline at end of file
+    let error_msg = match error_array[0].as_str() {
+        Some(error_msg) => error_msg.to_string(),
+        None => error_array[0].to_string(),
+    };
+
+    let kind = KrakenError::from_message(&error_msg).kind();
+    Err(exchange_error(kind).with_message(&error_msg))
+}
+
+/// Return an Error of the kind specified, raised by Kraken.
+fn exchange_error(kind: error::ErrorKind) -> error::Error {
+    error::Error::new(kind).with_exchange(Exchange::Kraken)
+}
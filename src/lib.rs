@@ -18,22 +18,39 @@
 //! can't afford to loose. This is a personal project, I can not be held responsible for
 //! the library malfunction, which can lead to a loss of money.
 
-#[macro_use]
 extern crate hyper;
 extern crate crypto;
 extern crate hyper_native_tls;
 extern crate rustc_serialize;
+#[macro_use]
 extern crate serde_json;
 extern crate time;
 #[macro_use]
 extern crate lazy_static;
-extern crate bidir_map;
+extern crate toml;
+extern crate rand;
 
 pub mod coinnect;
+pub mod config;
+pub mod credentials;
+pub mod currency;
+pub mod decimal;
 pub mod exchange;
 pub mod error;
+pub mod keystore;
+pub mod market;
+pub mod nonce;
+pub mod otp;
 pub mod pair;
+pub mod rate_limiter;
+pub mod retry;
+pub mod scope;
 pub mod types;
+pub mod transport;
+pub mod validator;
+// A testing tool, used by the tests and the coinnect-mock binary: not part of the API
+#[doc(hidden)]
+pub mod mock_server;
 mod helpers;
 
 pub mod bitstamp;
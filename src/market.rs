@@ -0,0 +1,156 @@
+//! Use this module to cache the markets listed by an exchange.
+//!
+//! `ExchangeApi::markets` retrieves the markets from the metadata endpoints of the exchange (the
+//! asset pairs of Kraken, the tickers and currencies of Poloniex, the trading pairs of Bitstamp),
+//! so that a new listing can be traded without a new version of the crate. The markets are kept
+//! in a `MarketCache` for an hour by default, and are then used by the generic API to find the
+//! name of each pair on the exchange. Until they are retrieved, the names are built by the
+//! `get_pair_string` function of the exchange `utils` module.
+//!
+//! Clones share the same cache, so a single cache can be given to several clients of the same
+//! exchange through `ClientConfig::market_cache`.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use coinnect::config::ClientConfig;
+//! use coinnect::market::MarketCache;
+//!
+//! // Retrieve the markets again every 10 minutes
+//! let config = ClientConfig::new().market_cache(MarketCache::new(Duration::from_secs(600)));
+//! ```
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use pair::Pair;
+use types::{Market, MarketInfo};
+
+/// How long the markets are kept by default: one hour.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(3600);
+
+/// How long a failure to retrieve the markets is remembered: one minute.
+const FAILURE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Markets {
+    markets: Vec<Market>,
+    updated: Instant,
+}
+
+/// The markets of an exchange, kept for a given time. Clones share the same cache.
+#[derive(Clone)]
+pub struct MarketCache {
+    ttl: Duration,
+    markets: Arc<Mutex<Option<Markets>>>,
+    failed: Arc<Mutex<Option<Instant>>>,
+}
+
+impl MarketCache {
+    /// Create a new empty MarketCache keeping the markets for the time specified.
+    pub fn new(ttl: Duration) -> MarketCache {
+        MarketCache {
+            ttl: ttl,
+            markets: Arc::new(Mutex::new(None)),
+            failed: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Return the markets cached, or None if they were never retrieved or are older than the
+    /// time to live of the cache.
+    pub fn get(&self) -> Option<Vec<Market>> {
+        match *self.lock() {
+            Some(ref markets) if markets.updated.elapsed() < self.ttl => {
+                Some(markets.markets.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Replace the markets cached by the markets specified.
+    pub fn set(&self, markets: Vec<Market>) {
+        *self.lock() = Some(Markets {
+            markets: markets,
+            updated: Instant::now(),
+        });
+        *self.lock_failed() = None;
+    }
+
+    /// Forget the markets cached, so that they are retrieved again.
+    pub fn clear(&self) {
+        *self.lock() = None;
+        *self.lock_failed() = None;
+    }
+
+    /// Remember that the markets could not be retrieved, so that the generic API names the pairs
+    /// without retrying for a minute (see `should_retrieve`).
+    pub fn set_failed(&self) {
+        *self.lock_failed() = Some(Instant::now());
+    }
+
+    /// Return true if the markets should be retrieved before naming a pair: they are not cached,
+    /// or too old, and they did not fail to be retrieved in the last minute.
+    pub fn should_retrieve(&self) -> bool {
+        let recently_failed = match *self.lock_failed() {
+            Some(failed) => failed.elapsed() < FAILURE_TTL,
+            None => false,
+        };
+        !recently_failed && self.get().is_none()
+    }
+
+    /// Return the name used by the exchange for the pair specified. If markets were retrieved,
+    /// even too long ago, it is the name of the market of the pair, or None if the pair is not
+    /// listed. Else it is the name returned by `fallback`.
+    pub fn symbol_or_else<F>(&self, pair: &Pair, fallback: F) -> Option<String>
+        where F: FnOnce(&Pair) -> Option<String>
+    {
+        match *self.lock() {
+            Some(ref markets) => {
+                markets.markets
+                    .iter()
+                    .find(|market| market.pair == *pair)
+                    .map(|market| market.symbol.clone())
+            }
+            None => fallback(pair),
+        }
+    }
+
+    /// Return the trading rules of the market of the pair specified, if markets were retrieved,
+    /// even too long ago, and the pair is listed.
+    pub fn info(&self, pair: &Pair) -> Option<MarketInfo> {
+        match *self.lock() {
+            Some(ref markets) => {
+                markets.markets
+                    .iter()
+                    .find(|market| market.pair == *pair)
+                    .map(|market| market.info.clone())
+            }
+            None => None,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<Option<Markets>> {
+        self.markets.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_failed(&self) -> MutexGuard<Option<Instant>> {
+        self.failed.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for MarketCache {
+    fn default() -> MarketCache {
+        MarketCache::new(DEFAULT_TTL)
+    }
+}
+
+impl fmt::Debug for MarketCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MarketCache")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
@@ -0,0 +1,328 @@
+//! Emulation of the Bitstamp REST API: `/api/v2/*` and the few v1 methods still used by the
+//! client.
+
+use serde_json::Value;
+use serde_json::value::Map;
+
+use std::collections::HashMap;
+
+use bitstamp::utils;
+use helpers;
+use transport::{HttpRequest, HttpResponse, Method};
+use types::{OrderSide, OrderType};
+
+use super::{Credentials, parse_params, parse_amount, json_response, format_amount, format_date};
+use super::market::{MarketState, MarketError, Market, BookOrder};
+
+/// Return the initial state of the Bitstamp mock: btcusd, btceur, ethbtc and etheur markets, with
+/// an account holding btc, eth, usd and eur.
+pub fn markets() -> MarketState {
+    MarketState::new(&[("btcusd", "btc", "usd", 1200.0),
+                       ("btceur", "btc", "eur", 1100.0),
+                       ("ethbtc", "eth", "btc", 0.04),
+                       ("etheur", "eth", "eur", 45.0)],
+                     &[("btc", 10.0), ("eth", 100.0), ("usd", 100000.0), ("eur", 100000.0)])
+}
+
+pub fn handle(state: &mut MarketState,
+              credentials: &Credentials,
+              request: &HttpRequest,
+              path: &str)
+              -> HttpResponse {
+    // "/api/v2/buy/market/btcusd/" becomes (true, "buy/market", Some("btcusd"))
+    let path = path["/api/".len()..].trim_matches('/');
+    let (v2, path) = if path.starts_with("v2/") {
+        (true, &path["v2/".len()..])
+    } else {
+        (false, path)
+    };
+    let (method, pair) = match path.rfind('/') {
+        Some(index) => (&path[..index], Some(&path[index + 1..])),
+        None => (path, None),
+    };
+
+    let result = match (request.method, v2) {
+        (Method::Get, true) => public(state, method, pair),
+        (Method::Get, false) if method == "eur_usd" => {
+            Ok(json!({"sell": "1.0548", "buy": "1.0624"}))
+        }
+        (Method::Post, true) => {
+            let params = parse_params(&request.body);
+            match authenticate(credentials, &params) {
+                Ok(()) => private(state, method, pair, &params),
+                Err(error) => Err((403, error)),
+            }
+        }
+        _ => Err((404, "Unknown method".to_string())),
+    };
+
+    match result {
+        Ok(result) => json_response(200, result),
+        Err((status, reason)) => {
+            if v2 {
+                json_response(status, json!({"status": "error", "reason": reason}))
+            } else {
+                json_response(status, json!({"error": reason}))
+            }
+        }
+    }
+}
+
+fn authenticate(credentials: &Credentials,
+                params: &HashMap<String, String>)
+                -> Result<(), String> {
+    if params.get("key") != Some(&credentials.api_key) {
+        return Err("Missing key, signature and nonce parameters".to_string());
+    }
+    let nonce = match params.get("nonce") {
+        Some(nonce) => nonce.clone(),
+        None => return Err("Missing key, signature and nonce parameters".to_string()),
+    };
+    let signature = utils::build_signature(&nonce,
+                                           &credentials.customer_id,
+                                           &credentials.api_key,
+                                           &credentials.api_secret);
+    if params.get("signature") != Some(&signature) {
+        return Err("Invalid signature".to_string());
+    }
+    Ok(())
+}
+
+fn get_market<'a>(state: &'a MarketState, pair: Option<&str>) -> Result<&'a Market, (u16, String)> {
+    pair.and_then(|pair| state.markets.get(pair))
+        .ok_or((404, "Invalid currency pair".to_string()))
+}
+
+fn public(state: &MarketState, method: &str, pair: Option<&str>) -> Result<Value, (u16, String)> {
+    if method == "trading-pairs-info" {
+        let pairs: Vec<Value> = state.markets
+            .iter()
+            .map(|(name, market)| {
+                let (base, quote) = (market.base.to_uppercase(), market.quote.to_uppercase());
+                let (counter_decimals, minimum_order) = match quote.as_str() {
+                    "BTC" => (8, "0.001"),
+                    _ => (2, "5.0"),
+                };
+                json!({
+                    "name": format!("{}/{}", base, quote),
+                    "url_symbol": name,
+                    "base_decimals": 8,
+                    "counter_decimals": counter_decimals,
+                    "minimum_order": format!("{} {}", minimum_order, quote),
+                    "trading": "Enabled",
+                    "description": format!("{} / {}", base, quote),
+                })
+            })
+            .collect();
+        return Ok(Value::Array(pairs));
+    }
+
+    let market = get_market(state, pair)?;
+    let timestamp = helpers::get_unix_timestamp_ms() / 1000;
+
+    match method {
+        "ticker" => {
+            let last = format_amount(market.last_price());
+            Ok(json!({
+                "high": last,
+                "last": last,
+                "timestamp": timestamp.to_string(),
+                "bid": format_amount(market.best_bid().unwrap_or(0.0)),
+                "vwap": last,
+                "volume": format_amount(market.volume()),
+                "low": last,
+                "ask": format_amount(market.best_ask().unwrap_or(0.0)),
+                "open": last,
+            }))
+        }
+        "order_book" => {
+            let offers = |orders: &Vec<BookOrder>| -> Vec<Value> {
+                orders.iter()
+                    .map(|order| {
+                        json!([format_amount(order.price), format_amount(order.remaining())])
+                    })
+                    .collect()
+            };
+            Ok(json!({
+                "timestamp": timestamp.to_string(),
+                "bids": offers(&market.bids),
+                "asks": offers(&market.asks),
+            }))
+        }
+        "transactions" => {
+            // Most recent trades first
+            let trades: Vec<Value> = market.trades
+                .iter()
+                .rev()
+                .map(|trade| {
+                    json!({
+                        "date": (trade.timestamp / 1000).to_string(),
+                        "tid": trade.id.to_string(),
+                        "price": format_amount(trade.price),
+                        "type": side_to_str(trade.side),
+                        "amount": format_amount(trade.volume),
+                    })
+                })
+                .collect();
+            Ok(Value::Array(trades))
+        }
+        _ => Err((404, "Unknown method".to_string())),
+    }
+}
+
+fn private(state: &mut MarketState,
+           method: &str,
+           pair: Option<&str>,
+           params: &HashMap<String, String>)
+           -> Result<Value, (u16, String)> {
+    match method {
+        "balance" => {
+            if pair.is_some() {
+                get_market(state, pair)?;
+            }
+            Ok(balances(state, pair))
+        }
+        "buy" | "sell" | "buy/market" | "sell/market" => {
+            let pair = get_market(state, pair).map(|_| pair.unwrap_or(""))?;
+            let side = if method.starts_with("buy") {
+                OrderSide::Buy
+            } else {
+                OrderSide::Sell
+            };
+            let order_type = if method.ends_with("/market") {
+                OrderType::Market
+            } else {
+                OrderType::Limit
+            };
+            let amount = parse_amount(params, "amount")
+                .ok_or((400, "Invalid amount".to_string()))?;
+            let price = match order_type {
+                OrderType::Limit => {
+                    Some(parse_amount(params, "price").ok_or((400, "Invalid price".to_string()))?)
+                }
+                OrderType::Market => None,
+            };
+
+            let (id, trades) = state.place_order(pair, side, order_type, price, amount)
+                .map_err(|error| market_error(state, pair, side, error))?;
+
+            // Market orders report their average price
+            let price = match price {
+                Some(price) => price,
+                None => {
+                    let volume: f64 = trades.iter().map(|trade| trade.volume).sum();
+                    let cost: f64 = trades.iter().map(|trade| trade.price * trade.volume).sum();
+                    if volume > 0.0 { cost / volume } else { 0.0 }
+                }
+            };
+            Ok(json!({
+                "id": id.to_string(),
+                "datetime": format_date(helpers::get_unix_timestamp_ms()),
+                "type": side_to_str(side),
+                "price": format_amount(price),
+                "amount": format_amount(amount),
+            }))
+        }
+        "cancel_order" => {
+            let id = params.get("id").and_then(|id| id.parse::<u64>().ok());
+            match id.map(|id| state.cancel_order(id)) {
+                Some(Ok((_, order))) => {
+                    Ok(json!({
+                        "id": order.id,
+                        "amount": format_amount(order.remaining()),
+                        "price": format_amount(order.price),
+                        "type": match order.side {
+                            OrderSide::Buy => 0,
+                            OrderSide::Sell => 1,
+                        },
+                    }))
+                }
+                _ => Err((404, "Order not found".to_string())),
+            }
+        }
+        "open_orders" if pair == Some("all") => {
+            let mut orders = Vec::new();
+            for market in state.markets.values() {
+                orders.extend(open_orders(market));
+            }
+            Ok(Value::Array(orders))
+        }
+        "open_orders" => Ok(Value::Array(open_orders(get_market(state, pair)?))),
+        _ => Err((404, "Unknown method".to_string())),
+    }
+}
+
+fn market_error(state: &MarketState,
+                pair: &str,
+                side: OrderSide,
+                error: MarketError)
+                -> (u16, String) {
+    match error {
+        MarketError::UnknownMarket => (404, "Invalid currency pair".to_string()),
+        MarketError::UnknownOrder => (404, "Order not found".to_string()),
+        MarketError::InvalidArguments => (400, "Invalid amount or price".to_string()),
+        MarketError::InsufficientFunds => {
+            let currency = state.markets.get(pair).map_or("", |market| match side {
+                OrderSide::Buy => market.quote.as_str(),
+                OrderSide::Sell => market.base.as_str(),
+            });
+            (400,
+             format!("You have only {} {} available. Check your account balance for details.",
+                     format_amount(state.balance(currency).available),
+                     currency.to_uppercase()))
+        }
+    }
+}
+
+/// Return the balances of every currency with the fee of every market, or only the balances of
+/// the market currencies and its fee.
+fn balances(state: &MarketState, name: Option<&str>) -> Value {
+    let currencies: Vec<String> = match name.and_then(|name| state.markets.get(name)) {
+        Some(market) => vec![market.base.clone(), market.quote.clone()],
+        None => state.balances.keys().cloned().collect(),
+    };
+
+    let mut result = Map::new();
+    for currency in currencies {
+        let balance = state.balance(&currency);
+        result.insert(currency.clone() + "_balance", json!(format_amount(balance.total())));
+        result.insert(currency.clone() + "_available",
+                      json!(format_amount(balance.available)));
+        result.insert(currency + "_reserved", json!(format_amount(balance.on_hold)));
+    }
+    match name {
+        Some(_) => {
+            result.insert("fee".to_string(), json!("0.25"));
+        }
+        None => {
+            for name in state.markets.keys() {
+                result.insert(name.clone() + "_fee", json!("0.25"));
+            }
+        }
+    }
+    Value::Object(result)
+}
+
+fn open_orders(market: &Market) -> Vec<Value> {
+    let currency_pair = market.base.to_uppercase() + "/" + &market.quote.to_uppercase();
+    market.own_orders()
+        .iter()
+        .map(|order| {
+            json!({
+                "id": order.id.to_string(),
+                "datetime": format_date(order.timestamp),
+                "type": side_to_str(order.side),
+                "price": format_amount(order.price),
+                "amount": format_amount(order.remaining()),
+                "currency_pair": currency_pair,
+            })
+        })
+        .collect()
+}
+
+fn side_to_str(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "0",
+        OrderSide::Sell => "1",
+    }
+}
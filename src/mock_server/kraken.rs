@@ -0,0 +1,297 @@
+//! Emulation of the Kraken REST API: `/0/public/*` and `/0/private/*`.
+//!
+//! Served methods: Time, AssetPairs, Ticker, Depth and Trades (public), Balance, AddOrder,
+//! CancelOrder and OpenOrders (private). Any other method, like TradeBalance, QueryOrders or
+//! ClosedOrders, returns "EGeneral:Unknown method".
+
+use serde_json::Value;
+use serde_json::value::Map;
+
+use std::collections::HashMap;
+
+use kraken::utils;
+use transport::{HttpRequest, HttpResponse};
+use types::{OrderSide, OrderType};
+
+use super::{Credentials, parse_params, parse_amount, json_response, format_amount};
+use super::market::{MarketState, MarketError, BookOrder};
+
+/// Return the initial state of the Kraken mock: XXBTZEUR, XXBTZUSD, XETHXXBT and XETHZEUR
+/// markets, with an account holding XXBT, XETH, ZEUR and ZUSD.
+pub fn markets() -> MarketState {
+    MarketState::new(&[("XXBTZEUR", "XXBT", "ZEUR", 1100.0),
+                       ("XXBTZUSD", "XXBT", "ZUSD", 1200.0),
+                       ("XETHXXBT", "XETH", "XXBT", 0.04),
+                       ("XETHZEUR", "XETH", "ZEUR", 45.0)],
+                     &[("XXBT", 10.0), ("XETH", 100.0), ("ZEUR", 100000.0), ("ZUSD", 100000.0)])
+}
+
+pub fn handle(state: &mut MarketState,
+              credentials: &Credentials,
+              request: &HttpRequest,
+              path: &str,
+              query: &str)
+              -> HttpResponse {
+    let result = if path.starts_with("/0/public/") {
+        public(state, &path["/0/public/".len()..], &parse_params(query))
+    } else if path.starts_with("/0/private/") {
+        let params = parse_params(&request.body);
+        match authenticate(credentials, request, path, &params) {
+            Ok(()) => private(state, &path["/0/private/".len()..], &params),
+            Err(error) => Err(error),
+        }
+    } else {
+        Err("EGeneral:Unknown method")
+    };
+
+    match result {
+        Ok(result) => json_response(200, json!({"error": [], "result": result})),
+        Err(error) => json_response(200, json!({"error": [error]})),
+    }
+}
+
+fn authenticate(credentials: &Credentials,
+                request: &HttpRequest,
+                path: &str,
+                params: &HashMap<String, String>)
+                -> Result<(), &'static str> {
+    if request.header("API-Key") != Some(credentials.api_key.as_str()) {
+        return Err("EAPI:Invalid key");
+    }
+    let nonce = match params.get("nonce") {
+        Some(nonce) => nonce,
+        None => return Err("EAPI:Invalid nonce"),
+    };
+    let signature = utils::create_signature(&credentials.api_secret, path, &request.body, nonce)
+        .map_err(|_| "EAPI:Invalid key")?;
+    if request.header("API-Sign") != Some(signature.as_str()) {
+        return Err("EAPI:Invalid signature");
+    }
+    Ok(())
+}
+
+fn public(state: &MarketState,
+          method: &str,
+          params: &HashMap<String, String>)
+          -> Result<Value, &'static str> {
+    if method == "Time" {
+        let now = ::time::now_utc();
+        return Ok(json!({
+            "unixtime": now.to_timespec().sec,
+            "rfc1123": now.rfc822().to_string(),
+        }));
+    }
+
+    if method == "AssetPairs" {
+        let mut result = Map::new();
+        for (name, market) in state.markets.iter() {
+            // Fiat prices have 2 decimals, crypto-currency prices 5
+            let pair_decimals = if market.quote.starts_with('Z') { 2 } else { 5 };
+            result.insert(name.clone(),
+                          json!({
+                              "altname": utils::get_pair_altname(name),
+                              "aclass_base": "currency",
+                              "base": market.base,
+                              "aclass_quote": "currency",
+                              "quote": market.quote,
+                              "lot": "unit",
+                              "pair_decimals": pair_decimals,
+                              "lot_decimals": 8,
+                              "lot_multiplier": 1,
+                              "leverage_buy": [2, 3],
+                              "leverage_sell": [2, 3],
+                              "fees": [[0, 0.26], [50000, 0.24], [100000, 0.22]],
+                              "fees_maker": [[0, 0.16], [50000, 0.14], [100000, 0.12]],
+                              "fee_volume_currency": "ZUSD",
+                              "margin_call": 80,
+                              "margin_stop": 40,
+                              "ordermin": "0.002",
+                          }));
+        }
+        return Ok(Value::Object(result));
+    }
+
+    let pair = params.get("pair").map(|pair| pair.as_str()).unwrap_or("");
+    let mut result = Map::new();
+    for name in pair.split(',') {
+        let market = state.markets.get(name).ok_or("EQuery:Unknown asset pair")?;
+        let data = match method {
+            "Ticker" => {
+                let ask = format_amount(market.best_ask().unwrap_or(0.0));
+                let bid = format_amount(market.best_bid().unwrap_or(0.0));
+                let last = format_amount(market.last_price());
+                let volume = format_amount(market.volume());
+                let count = market.trades.len();
+                json!({
+                    "a": [ask, "1", "1.000"],
+                    "b": [bid, "1", "1.000"],
+                    "c": [last, "0.10000000"],
+                    "v": [volume, volume],
+                    "p": [last, last],
+                    "t": [count, count],
+                    "l": [last, last],
+                    "h": [last, last],
+                    "o": last,
+                })
+            }
+            "Depth" => {
+                let count = params.get("count").and_then(|count| count.parse::<usize>().ok());
+                let offers = |orders: &Vec<BookOrder>| -> Vec<Value> {
+                    orders.iter()
+                        .take(count.unwrap_or(100))
+                        .map(|order| {
+                            json!([format_amount(order.price),
+                                   format_amount(order.remaining()),
+                                   order.timestamp / 1000])
+                        })
+                        .collect()
+                };
+                json!({"asks": offers(&market.asks), "bids": offers(&market.bids)})
+            }
+            "Trades" => {
+                let since = params.get("since").and_then(|since| since.parse::<u64>().ok());
+                let trades = market.trades_since(since.unwrap_or(0));
+                let last = trades.last()
+                    .map(|trade| trade.id)
+                    .or(since)
+                    .unwrap_or(0);
+                let entries: Vec<Value> = trades.iter()
+                    .map(|trade| {
+                        json!([format_amount(trade.price),
+                               format_amount(trade.volume),
+                               trade.timestamp as f64 / 1000.0,
+                               match trade.side {
+                                   OrderSide::Buy => "b",
+                                   OrderSide::Sell => "s",
+                               },
+                               match trade.order_type {
+                                   OrderType::Limit => "l",
+                                   OrderType::Market => "m",
+                               },
+                               ""])
+                    })
+                    .collect();
+                result.insert("last".to_string(), json!(last.to_string()));
+                Value::Array(entries)
+            }
+            _ => return Err("EGeneral:Unknown method"),
+        };
+        result.insert(name.to_string(), data);
+    }
+    Ok(Value::Object(result))
+}
+
+fn private(state: &mut MarketState,
+           method: &str,
+           params: &HashMap<String, String>)
+           -> Result<Value, &'static str> {
+    match method {
+        "Balance" => {
+            let mut result = Map::new();
+            for (asset, balance) in state.balances.iter() {
+                result.insert(asset.clone(), json!(format!("{:.10}", balance.total())));
+            }
+            Ok(Value::Object(result))
+        }
+        "AddOrder" => {
+            let pair = params.get("pair").ok_or("EGeneral:Invalid arguments")?;
+            let side = match params.get("type").map(|side| side.as_str()) {
+                Some("buy") => OrderSide::Buy,
+                Some("sell") => OrderSide::Sell,
+                _ => return Err("EGeneral:Invalid arguments:type"),
+            };
+            let order_type = match params.get("ordertype").map(|order_type| order_type.as_str()) {
+                Some("limit") => OrderType::Limit,
+                Some("market") => OrderType::Market,
+                _ => return Err("EGeneral:Invalid arguments:ordertype"),
+            };
+            let volume = parse_amount(params, "volume")
+                .ok_or("EGeneral:Invalid arguments:volume")?;
+            let price = parse_amount(params, "price");
+
+            let (id, _) = state.place_order(pair, side, order_type, price, volume)
+                .map_err(market_error)?;
+
+            let description = format!("{} {} {} @ {} {}",
+                                      params["type"],
+                                      format_amount(volume),
+                                      utils::get_pair_altname(pair),
+                                      params["ordertype"],
+                                      price.map(format_amount).unwrap_or_default());
+            Ok(json!({"descr": {"order": description}, "txid": [txid(id)]}))
+        }
+        "CancelOrder" => {
+            let id = params.get("txid")
+                .and_then(|txid| parse_txid(txid))
+                .ok_or("EOrder:Unknown order")?;
+            state.cancel_order(id).map_err(market_error)?;
+            Ok(json!({"count": 1, "pending": false}))
+        }
+        "OpenOrders" => {
+            let mut open = Map::new();
+            for (name, market) in state.markets.iter() {
+                for order in market.own_orders() {
+                    let side = match order.side {
+                        OrderSide::Buy => "buy",
+                        OrderSide::Sell => "sell",
+                    };
+                    let price = format_amount(order.price);
+                    let volume = format_amount(order.volume);
+                    let description = format!("{} {} {} @ limit {}",
+                                              side,
+                                              volume,
+                                              utils::get_pair_altname(name),
+                                              price);
+                    open.insert(txid(order.id),
+                                json!({
+                                    "refid": null,
+                                    "userref": null,
+                                    "status": "open",
+                                    "opentm": order.timestamp as f64 / 1000.0,
+                                    "starttm": 0,
+                                    "expiretm": 0,
+                                    "descr": {
+                                        "pair": utils::get_pair_altname(name),
+                                        "type": side,
+                                        "ordertype": "limit",
+                                        "price": price,
+                                        "price2": "0",
+                                        "leverage": "none",
+                                        "order": description,
+                                    },
+                                    "vol": volume,
+                                    "vol_exec": format_amount(order.filled),
+                                    "cost": format_amount(order.filled * order.price),
+                                    "fee": "0.00000",
+                                    "price": format_amount(order.price),
+                                    "misc": "",
+                                    "oflags": "",
+                                }));
+                }
+            }
+            Ok(json!({"open": open}))
+        }
+        _ => Err("EGeneral:Unknown method"),
+    }
+}
+
+fn market_error(error: MarketError) -> &'static str {
+    match error {
+        MarketError::UnknownMarket => "EQuery:Unknown asset pair",
+        MarketError::UnknownOrder => "EOrder:Unknown order",
+        MarketError::InvalidArguments => "EGeneral:Invalid arguments",
+        MarketError::InsufficientFunds => "EOrder:Insufficient funds",
+    }
+}
+
+/// Kraken transaction ids look like "OQCLML-BW3P3-BUCMWZ", the mock uses "OMOCK-000042".
+fn txid(id: u64) -> String {
+    format!("OMOCK-{:06}", id)
+}
+
+fn parse_txid(txid: &str) -> Option<u64> {
+    if !txid.starts_with("OMOCK-") {
+        return None;
+    }
+    txid["OMOCK-".len()..].parse::<u64>().ok()
+}
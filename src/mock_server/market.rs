@@ -0,0 +1,374 @@
+//! In-memory market shared by the mock exchanges: an order book, the public trades and the
+//! balances of the account trading on it.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use helpers;
+use types::{OrderSide, OrderType};
+
+// The simulated markets compute with floats: the amounts are formatted with 8 decimals in the
+// responses, which the clients parse exactly.
+type Price = f64;
+type Volume = f64;
+
+/// An order resting in the book. Orders which are not `own` are the liquidity seeded with the
+/// market, the account can not see nor cancel them.
+#[derive(Debug, Clone)]
+pub struct BookOrder {
+    pub id: u64,
+    pub own: bool,
+    pub side: OrderSide,
+    pub price: Price,
+    pub volume: Volume,
+    pub filled: Volume,
+    pub timestamp: i64,
+}
+
+impl BookOrder {
+    pub fn remaining(&self) -> Volume {
+        self.volume - self.filled
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MarketTrade {
+    pub id: u64,
+    pub timestamp: i64,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub price: Price,
+    pub volume: Volume,
+}
+
+#[derive(Debug, Clone)]
+pub struct Market {
+    pub base: String,
+    pub quote: String,
+    pub asks: Vec<BookOrder>,
+    pub bids: Vec<BookOrder>,
+    pub trades: Vec<MarketTrade>,
+}
+
+impl Market {
+    /// Create a market seeded with a few price levels on each side of the price given.
+    pub fn new(base: &str, quote: &str, price: Price, next_id: &mut u64) -> Market {
+        let timestamp = helpers::get_unix_timestamp_ms();
+        let mut asks = Vec::new();
+        let mut bids = Vec::new();
+        for level in 1..6 {
+            let spread = price * 0.001 * level as f64;
+            let volume = level as f64;
+            asks.push(seed_order(next_id, OrderSide::Sell, price + spread, volume, timestamp));
+            bids.push(seed_order(next_id, OrderSide::Buy, price - spread, volume, timestamp));
+        }
+
+        let mut trades = Vec::new();
+        for &(side, offset) in &[(OrderSide::Buy, 0.0005), (OrderSide::Sell, -0.0005)] {
+            *next_id += 1;
+            trades.push(MarketTrade {
+                id: *next_id,
+                timestamp: timestamp,
+                side: side,
+                order_type: OrderType::Limit,
+                price: price * (1.0 + offset),
+                volume: 0.5,
+            });
+        }
+
+        Market {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            asks: asks,
+            bids: bids,
+            trades: trades,
+        }
+    }
+
+    pub fn best_ask(&self) -> Option<Price> {
+        self.asks.first().map(|order| order.price)
+    }
+
+    pub fn best_bid(&self) -> Option<Price> {
+        self.bids.first().map(|order| order.price)
+    }
+
+    pub fn last_price(&self) -> Price {
+        match self.trades.last() {
+            Some(trade) => trade.price,
+            None => (self.best_ask().unwrap_or(0.0) + self.best_bid().unwrap_or(0.0)) / 2.0,
+        }
+    }
+
+    pub fn volume(&self) -> Volume {
+        self.trades.iter().map(|trade| trade.volume).sum()
+    }
+
+    /// Return the open orders of the account, from the oldest to the most recent.
+    pub fn own_orders(&self) -> Vec<&BookOrder> {
+        let mut orders: Vec<&BookOrder> = self.asks
+            .iter()
+            .chain(self.bids.iter())
+            .filter(|order| order.own)
+            .collect();
+        orders.sort_by_key(|order| order.id);
+        orders
+    }
+
+    /// Return the trades strictly more recent than the trade id given.
+    pub fn trades_since(&self, since: u64) -> Vec<&MarketTrade> {
+        self.trades.iter().filter(|trade| trade.id > since).collect()
+    }
+
+    fn sort_book(&mut self) {
+        // Best price first, then time priority
+        self.asks.sort_by(|a, b| {
+            a.price.partial_cmp(&b.price).unwrap_or(Ordering::Equal).then(a.id.cmp(&b.id))
+        });
+        self.bids.sort_by(|a, b| {
+            b.price.partial_cmp(&a.price).unwrap_or(Ordering::Equal).then(a.id.cmp(&b.id))
+        });
+    }
+}
+
+fn seed_order(next_id: &mut u64,
+              side: OrderSide,
+              price: Price,
+              volume: Volume,
+              timestamp: i64)
+              -> BookOrder {
+    *next_id += 1;
+    BookOrder {
+        id: *next_id,
+        own: false,
+        side: side,
+        price: price,
+        volume: volume,
+        filled: 0.0,
+        timestamp: timestamp,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccountBalance {
+    pub available: Volume,
+    pub on_hold: Volume,
+}
+
+impl AccountBalance {
+    pub fn total(&self) -> Volume {
+        self.available + self.on_hold
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketError {
+    UnknownMarket,
+    UnknownOrder,
+    InvalidArguments,
+    InsufficientFunds,
+}
+
+/// The state of a mock exchange: its markets, keyed by the pair name used by the exchange, and
+/// the account of the API user.
+#[derive(Debug, Clone)]
+pub struct MarketState {
+    pub markets: HashMap<String, Market>,
+    pub balances: HashMap<String, AccountBalance>,
+    next_id: u64,
+}
+
+impl MarketState {
+    /// Create a state with the markets given as (pair name, base, quote, price) and an account
+    /// holding the balances given.
+    pub fn new(markets: &[(&str, &str, &str, Price)], balances: &[(&str, Volume)]) -> MarketState {
+        let mut next_id = 0;
+        let mut state_markets = HashMap::new();
+        for &(name, base, quote, price) in markets {
+            state_markets.insert(name.to_string(), Market::new(base, quote, price, &mut next_id));
+        }
+
+        let mut state_balances = HashMap::new();
+        for &(currency, available) in balances {
+            state_balances.insert(currency.to_string(),
+                                  AccountBalance {
+                                      available: available,
+                                      on_hold: 0.0,
+                                  });
+        }
+
+        MarketState {
+            markets: state_markets,
+            balances: state_balances,
+            next_id: next_id,
+        }
+    }
+
+    pub fn balance(&self, currency: &str) -> AccountBalance {
+        self.balances.get(currency).cloned().unwrap_or_default()
+    }
+
+    /// Place an order of the account. The order is matched against the book, the remaining volume
+    /// of a limit order is added to the book and its funds are put on hold.
+    /// Return the id of the order and the trades it generated.
+    pub fn place_order(&mut self,
+                       pair: &str,
+                       side: OrderSide,
+                       order_type: OrderType,
+                       price: Option<Price>,
+                       volume: Volume)
+                       -> Result<(u64, Vec<MarketTrade>), MarketError> {
+        if volume <= 0.0 || price.map_or(false, |price| price <= 0.0) {
+            return Err(MarketError::InvalidArguments);
+        }
+        let limit = match (order_type, price) {
+            (OrderType::Limit, Some(price)) => Some(price),
+            (OrderType::Limit, None) => return Err(MarketError::InvalidArguments),
+            (OrderType::Market, _) => None,
+        };
+
+        let (base, quote, fills) = {
+            let market = self.markets.get(pair).ok_or(MarketError::UnknownMarket)?;
+            let book = match side {
+                OrderSide::Buy => &market.asks,
+                OrderSide::Sell => &market.bids,
+            };
+            let mut fills = Vec::new();
+            let mut remaining = volume;
+            for order in book.iter().filter(|order| !order.own) {
+                let crosses = match (side, limit) {
+                    (_, None) => true,
+                    (OrderSide::Buy, Some(limit)) => order.price <= limit,
+                    (OrderSide::Sell, Some(limit)) => order.price >= limit,
+                };
+                if remaining <= 0.0 || !crosses {
+                    break;
+                }
+                let traded = remaining.min(order.remaining());
+                fills.push((order.id, order.price, traded));
+                remaining -= traded;
+            }
+            (market.base.clone(), market.quote.clone(), fills)
+        };
+
+        let filled: Volume = fills.iter().map(|&(_, _, volume)| volume).sum();
+        let cost: f64 = fills.iter().map(|&(_, price, volume)| price * volume).sum();
+        let resting = match limit {
+            Some(_) => volume - filled,
+            None => 0.0,
+        };
+
+        // Funds needed: the cost of the fills plus the hold of the resting volume
+        match side {
+            OrderSide::Buy => {
+                let needed = cost + resting * limit.unwrap_or(0.0);
+                if self.balance(&quote).available < needed {
+                    return Err(MarketError::InsufficientFunds);
+                }
+            }
+            OrderSide::Sell => {
+                if self.balance(&base).available < filled + resting {
+                    return Err(MarketError::InsufficientFunds);
+                }
+            }
+        }
+
+        self.next_id += 1;
+        let order_id = self.next_id;
+        let timestamp = helpers::get_unix_timestamp_ms();
+        let mut trades = Vec::with_capacity(fills.len());
+        for &(_, price, traded) in &fills {
+            self.next_id += 1;
+            trades.push(MarketTrade {
+                id: self.next_id,
+                timestamp: timestamp,
+                side: side,
+                order_type: order_type,
+                price: price,
+                volume: traded,
+            });
+        }
+
+        let (base_delta, quote_delta) = match side {
+            OrderSide::Buy => (filled, -cost),
+            OrderSide::Sell => (-filled, cost),
+        };
+        self.balances.entry(base.clone()).or_insert_with(Default::default).available += base_delta;
+        self.balances.entry(quote.clone()).or_insert_with(Default::default).available +=
+            quote_delta;
+
+        let market = self.markets.get_mut(pair).ok_or(MarketError::UnknownMarket)?;
+        {
+            let book = match side {
+                OrderSide::Buy => &mut market.asks,
+                OrderSide::Sell => &mut market.bids,
+            };
+            for &(id, _, traded) in &fills {
+                if let Some(order) = book.iter_mut().find(|order| order.id == id) {
+                    order.filled += traded;
+                }
+            }
+            book.retain(|order| order.remaining() > 0.0);
+        }
+        market.trades.extend(trades.iter().cloned());
+
+        if resting > 0.0 {
+            let price = limit.unwrap_or(0.0);
+            let (currency, amount) = match side {
+                OrderSide::Buy => (quote, resting * price),
+                OrderSide::Sell => (base, resting),
+            };
+            let balance = self.balances.entry(currency).or_insert_with(Default::default);
+            balance.available -= amount;
+            balance.on_hold += amount;
+
+            let order = BookOrder {
+                id: order_id,
+                own: true,
+                side: side,
+                price: price,
+                volume: volume,
+                filled: filled,
+                timestamp: timestamp,
+            };
+            match side {
+                OrderSide::Buy => market.bids.push(order),
+                OrderSide::Sell => market.asks.push(order),
+            }
+            market.sort_book();
+        }
+
+        Ok((order_id, trades))
+    }
+
+    /// Cancel an open order of the account and release its funds.
+    /// Return the pair name of the order and the order.
+    pub fn cancel_order(&mut self, id: u64) -> Result<(String, BookOrder), MarketError> {
+        for (name, market) in self.markets.iter_mut() {
+            let position = market.asks
+                .iter()
+                .chain(market.bids.iter())
+                .position(|order| order.own && order.id == id);
+            let index = match position {
+                Some(index) => index,
+                None => continue,
+            };
+            let order = if index < market.asks.len() {
+                market.asks.remove(index)
+            } else {
+                market.bids.remove(index - market.asks.len())
+            };
+
+            let (currency, amount) = match order.side {
+                OrderSide::Buy => (market.quote.clone(), order.remaining() * order.price),
+                OrderSide::Sell => (market.base.clone(), order.remaining()),
+            };
+            let balance = self.balances.entry(currency).or_insert_with(Default::default);
+            balance.available += amount;
+            balance.on_hold -= amount;
+
+            return Ok((name.clone(), order));
+        }
+        Err(MarketError::UnknownOrder)
+    }
+}
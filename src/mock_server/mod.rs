@@ -0,0 +1,230 @@
+//! A mock exchange emulating the REST APIs of Kraken, Poloniex and Bitstamp, backed by in-memory
+//! order books and accounts. Use it to test trading flows end-to-end with no real money and no
+//! network: either in-process as a Transport, or over HTTP with the `coinnect-mock` binary.
+//!
+//! Private requests are authenticated exactly like the real exchanges do, so a client with wrong
+//! keys or a broken signature scheme is rejected. Every exchange starts with a few markets and an
+//! account funded in every currency:
+//!
+//! - Kraken: XXBTZEUR, XXBTZUSD, XETHXXBT and XETHZEUR
+//! - Poloniex: BTC_ETH and BTC_ZEC (the ETH/BTC and ZEC/BTC pairs)
+//! - Bitstamp: btcusd, btceur, ethbtc and etheur
+//!
+//! Only the endpoints the generic `ExchangeApi` relies on are emulated (markets, ticker, order
+//! book, trades, balances, order placement, cancellation and open orders): the other methods of
+//! the raw APIs are answered with the "unknown method" error of the exchange.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::decimal::Decimal;
+//! use coinnect::exchange::ExchangeApi;
+//! use coinnect::kraken::api::KrakenApi;
+//! use coinnect::mock_server::{MockServer, DEFAULT_API_KEY, DEFAULT_API_SECRET};
+//! use coinnect::pair::Pair;
+//! use coinnect::types::{OrderRequest, OrderSide};
+//!
+//! let server = MockServer::default();
+//! let mut api = KrakenApi::with_transport(DEFAULT_API_KEY, DEFAULT_API_SECRET, server.clone());
+//!
+//! let price = api.orderbook(Pair::BTC_EUR).unwrap().best_bid().unwrap().0 * Decimal::new(9, 1);
+//! let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, price, Decimal::ONE);
+//! let id = api.place_order(order).unwrap();
+//! assert_eq!(api.open_orders(Pair::BTC_EUR).unwrap()[0].id, id);
+//! ```
+
+use serde_json::Value;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use decimal::Decimal;
+use error::Error;
+use exchange::Exchange;
+use transport::{Transport, HttpRequest, HttpResponse};
+use types::Balance;
+
+mod market;
+mod bitstamp;
+mod kraken;
+mod poloniex;
+
+use self::market::MarketState;
+
+/// API key accepted by `MockServer::default()`.
+pub const DEFAULT_API_KEY: &'static str = "coinnect-mock-key";
+/// API secret accepted by `MockServer::default()`. It is valid base64, as required by Kraken.
+pub const DEFAULT_API_SECRET: &'static str = "Y29pbm5lY3QtbW9jay1zZWNyZXQ=";
+/// Bitstamp customer id accepted by `MockServer::default()`.
+pub const DEFAULT_CUSTOMER_ID: &'static str = "123456";
+
+#[derive(Debug, Clone)]
+struct Credentials {
+    api_key: String,
+    api_secret: String,
+    customer_id: String,
+}
+
+#[derive(Debug)]
+struct ServerState {
+    credentials: Credentials,
+    kraken: MarketState,
+    poloniex: MarketState,
+    bitstamp: MarketState,
+}
+
+/// The mock exchanges. Clones share the same state, so a clone can be kept to inspect the
+/// accounts while a client owns another one as its Transport.
+#[derive(Debug, Clone)]
+pub struct MockServer {
+    state: Arc<Mutex<ServerState>>,
+}
+
+impl MockServer {
+    /// Create a new MockServer accepting the credentials specified on every exchange. The
+    /// customer id is only used by Bitstamp.
+    pub fn new(api_key: &str, api_secret: &str, customer_id: &str) -> MockServer {
+        let state = ServerState {
+            credentials: Credentials {
+                api_key: api_key.to_string(),
+                api_secret: api_secret.to_string(),
+                customer_id: customer_id.to_string(),
+            },
+            kraken: kraken::markets(),
+            poloniex: poloniex::markets(),
+            bitstamp: bitstamp::markets(),
+        };
+        MockServer { state: Arc::new(Mutex::new(state)) }
+    }
+
+    /// Return the balance of the account on the exchange specified. The currency uses the name
+    /// of the exchange ("XXBT" for Kraken, "BTC" for Poloniex, "btc" for Bitstamp).
+    pub fn balance(&self, exchange: Exchange, currency: &str) -> Balance {
+        let state = self.state.lock().unwrap();
+        let balance = match exchange {
+            Exchange::Bitstamp => state.bitstamp.balance(currency),
+            Exchange::Kraken => state.kraken.balance(currency),
+            Exchange::Poloniex => state.poloniex.balance(currency),
+        };
+        Balance {
+            total: to_decimal(balance.total()),
+            available: Some(to_decimal(balance.available)),
+            on_hold: Some(to_decimal(balance.on_hold)),
+        }
+    }
+
+    /// Answer the request as the exchange targeted by its path would. The host of the URL is
+    /// ignored.
+    pub fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        // Remove the scheme and the host, then split the query
+        let url = match request.url.find("://") {
+            Some(index) => &request.url[index + 3..],
+            None => &request.url[..],
+        };
+        let url = match url.find('/') {
+            Some(index) => &url[index..],
+            None => "/",
+        };
+        let (path, query) = match url.find('?') {
+            Some(index) => (&url[..index], &url[index + 1..]),
+            None => (url, ""),
+        };
+
+        if path.starts_with("/0/") {
+            kraken::handle(&mut state.kraken, &state.credentials, request, path, query)
+        } else if path == "/public" || path == "/tradingApi" {
+            poloniex::handle(&mut state.poloniex, &state.credentials, request, path, query)
+        } else if path.starts_with("/api/") {
+            bitstamp::handle(&mut state.bitstamp, &state.credentials, request, path)
+        } else {
+            json_response(404, json!({"error": "Not found"}))
+        }
+    }
+}
+
+impl Default for MockServer {
+    /// Create a new MockServer accepting `DEFAULT_API_KEY`, `DEFAULT_API_SECRET` and
+    /// `DEFAULT_CUSTOMER_ID`.
+    fn default() -> MockServer {
+        MockServer::new(DEFAULT_API_KEY, DEFAULT_API_SECRET, DEFAULT_CUSTOMER_ID)
+    }
+}
+
+impl Transport for MockServer {
+    fn send(&mut self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        Ok(self.handle(request))
+    }
+}
+
+/// Parse URL encoded parameters ("a=1&b=2") into a HashMap.
+fn parse_params(params: &str) -> HashMap<String, String> {
+    params.split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let mut parts = param.splitn(2, '=');
+            let name = url_decode(parts.next().unwrap_or(""));
+            let value = url_decode(parts.next().unwrap_or(""));
+            (name, value)
+        })
+        .collect()
+}
+
+/// Decode a percent-encoded parameter name or value ("+" being a space). Invalid escapes are
+/// kept as is.
+fn url_decode(param: &str) -> String {
+    let bytes = param.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' => param.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(if bytes[i] == b'+' { b' ' } else { bytes[i] });
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse the parameter specified as a positive f64.
+fn parse_amount(params: &HashMap<String, String>, name: &str) -> Option<f64> {
+    params.get(name)
+        .and_then(|value| value.parse::<f64>().ok())
+        .and_then(|value| if value > 0.0 { Some(value) } else { None })
+}
+
+fn json_response(status: u16, body: Value) -> HttpResponse {
+    HttpResponse {
+        status: status,
+        body: body.to_string(),
+    }
+}
+
+fn format_amount(amount: f64) -> String {
+    format!("{:.8}", amount)
+}
+
+/// Convert an amount to a Decimal, as a client would parse it from a response.
+fn to_decimal(amount: f64) -> Decimal {
+    format_amount(amount).parse().unwrap_or_default()
+}
+
+/// Format a timestamp in ms as an UTC date ("2017-04-11 14:21:07").
+fn format_date(timestamp_ms: i64) -> String {
+    let tm = ::time::at_utc(::time::Timespec::new(timestamp_ms / 1000, 0));
+    match tm.strftime("%Y-%m-%d %H:%M:%S") {
+        Ok(date) => date.to_string(),
+        Err(_) => String::new(),
+    }
+}
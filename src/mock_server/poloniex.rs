@@ -0,0 +1,266 @@
+//! Emulation of the Poloniex REST API: `/public?command=` and `/tradingApi`.
+
+use serde_json::Value;
+use serde_json::value::Map;
+
+use std::collections::HashMap;
+
+use poloniex::utils;
+use transport::{HttpRequest, HttpResponse};
+use types::{OrderSide, OrderType};
+
+use super::{Credentials, parse_params, parse_amount, json_response, format_amount, format_date};
+use super::market::{MarketState, MarketError, Market, MarketTrade, BookOrder};
+
+/// Return the initial state of the Poloniex mock: BTC_ETH and BTC_ZEC markets, with an account
+/// holding BTC, ETH and ZEC. Poloniex names its markets <quote>_<base>.
+pub fn markets() -> MarketState {
+    MarketState::new(&[("BTC_ETH", "ETH", "BTC", 0.04), ("BTC_ZEC", "ZEC", "BTC", 0.05)],
+                     &[("BTC", 10.0), ("ETH", 100.0), ("ZEC", 100.0)])
+}
+
+pub fn handle(state: &mut MarketState,
+              credentials: &Credentials,
+              request: &HttpRequest,
+              path: &str,
+              query: &str)
+              -> HttpResponse {
+    let result = if path == "/public" {
+        public(state, &parse_params(query))
+    } else {
+        let params = parse_params(&request.body);
+        match authenticate(credentials, request, &params) {
+            Ok(()) => private(state, &params),
+            Err(error) => Err(error),
+        }
+    };
+
+    match result {
+        Ok(result) => json_response(200, result),
+        Err(error) => json_response(200, json!({"error": error})),
+    }
+}
+
+fn authenticate(credentials: &Credentials,
+                request: &HttpRequest,
+                params: &HashMap<String, String>)
+                -> Result<(), String> {
+    let signature = utils::create_signature(&credentials.api_secret, &request.body);
+    if request.header("Key") != Some(credentials.api_key.as_str()) ||
+       request.header("Sign") != Some(signature.as_str()) {
+        return Err("Invalid API key/secret pair.".to_string());
+    }
+    match params.get("nonce").and_then(|nonce| nonce.parse::<u64>().ok()) {
+        Some(nonce) if nonce > 0 => Ok(()),
+        _ => Err("Nonce must be greater than 0.".to_string()),
+    }
+}
+
+fn get_market<'a>(state: &'a MarketState,
+                  params: &HashMap<String, String>)
+                  -> Result<&'a Market, String> {
+    params.get("currencyPair")
+        .and_then(|name| state.markets.get(name))
+        .ok_or("Invalid currency pair.".to_string())
+}
+
+fn public(state: &MarketState, params: &HashMap<String, String>) -> Result<Value, String> {
+    match params.get("command").map(|command| command.as_str()) {
+        Some("returnTicker") => {
+            let mut result = Map::new();
+            for (id, (name, market)) in state.markets.iter().enumerate() {
+                let base_volume: f64 = market.trades
+                    .iter()
+                    .map(|trade| trade.price * trade.volume)
+                    .sum();
+                result.insert(name.clone(),
+                              json!({
+                                  "id": id + 1,
+                                  "last": format_amount(market.last_price()),
+                                  "lowestAsk": format_amount(market.best_ask().unwrap_or(0.0)),
+                                  "highestBid": format_amount(market.best_bid().unwrap_or(0.0)),
+                                  "percentChange": "0.00000000",
+                                  "baseVolume": format_amount(base_volume),
+                                  "quoteVolume": format_amount(market.volume()),
+                                  "isFrozen": "0",
+                                  "high24hr": format_amount(market.last_price()),
+                                  "low24hr": format_amount(market.last_price()),
+                              }));
+            }
+            Ok(Value::Object(result))
+        }
+        Some("returnCurrencies") => {
+            let mut currencies: Vec<&String> = state.balances.keys().collect();
+            currencies.sort();
+            let mut result = Map::new();
+            for (id, currency) in currencies.into_iter().enumerate() {
+                result.insert(currency.clone(),
+                              json!({
+                                  "id": id + 1,
+                                  "name": currency,
+                                  "txFee": "0.00010000",
+                                  "minConf": 1,
+                                  "depositAddress": null,
+                                  "disabled": 0,
+                                  "delisted": 0,
+                                  "frozen": 0,
+                              }));
+            }
+            Ok(Value::Object(result))
+        }
+        Some("returnOrderBook") => {
+            let market = get_market(state, params)?;
+            let depth = params.get("depth").and_then(|depth| depth.parse::<usize>().ok());
+            let offers = |orders: &Vec<BookOrder>| -> Vec<Value> {
+                orders.iter()
+                    .take(depth.unwrap_or(50))
+                    .map(|order| json!([format_amount(order.price), order.remaining()]))
+                    .collect()
+            };
+            Ok(json!({
+                "asks": offers(&market.asks),
+                "bids": offers(&market.bids),
+                "isFrozen": "0",
+                "seq": market.trades.len(),
+            }))
+        }
+        Some("returnTradeHistory") => {
+            let market = get_market(state, params)?;
+            // Most recent trades first
+            let trades: Vec<Value> = market.trades.iter().rev().map(trade_to_json).collect();
+            Ok(Value::Array(trades))
+        }
+        _ => Err("Invalid command.".to_string()),
+    }
+}
+
+fn private(state: &mut MarketState, params: &HashMap<String, String>) -> Result<Value, String> {
+    match params.get("command").map(|command| command.as_str()) {
+        Some("returnBalances") => {
+            let mut result = Map::new();
+            for (currency, balance) in state.balances.iter() {
+                result.insert(currency.clone(), json!(format_amount(balance.available)));
+            }
+            Ok(Value::Object(result))
+        }
+        Some("returnCompleteBalances") => {
+            let mut result = Map::new();
+            for (currency, balance) in state.balances.iter() {
+                let btc_value = if currency == "BTC" {
+                    balance.total()
+                } else {
+                    state.markets
+                        .get(&("BTC_".to_string() + currency))
+                        .map_or(0.0, |market| market.last_price() * balance.total())
+                };
+                result.insert(currency.clone(),
+                              json!({
+                                  "available": format_amount(balance.available),
+                                  "onOrders": format_amount(balance.on_hold),
+                                  "btcValue": format_amount(btc_value),
+                              }));
+            }
+            Ok(Value::Object(result))
+        }
+        Some(command @ "buy") |
+        Some(command @ "sell") => {
+            let name = params.get("currencyPair")
+                .cloned()
+                .ok_or("Invalid currency pair.".to_string())?;
+            let side = if command == "buy" {
+                OrderSide::Buy
+            } else {
+                OrderSide::Sell
+            };
+            let rate = parse_amount(params, "rate").ok_or("Invalid rate parameter.".to_string())?;
+            let amount = parse_amount(params, "amount")
+                .ok_or("Invalid amount parameter.".to_string())?;
+
+            let (id, trades) = state.place_order(&name, side, OrderType::Limit, Some(rate), amount)
+                .map_err(|error| market_error(state, &name, side, error))?;
+
+            let trades: Vec<Value> = trades.iter().map(trade_to_json).collect();
+            Ok(json!({"orderNumber": id.to_string(), "resultingTrades": trades}))
+        }
+        Some("cancelOrder") => {
+            let id = params.get("orderNumber").and_then(|id| id.parse::<u64>().ok());
+            match id.map(|id| state.cancel_order(id)) {
+                Some(Ok((_, order))) => {
+                    Ok(json!({
+                        "success": 1,
+                        "amount": format_amount(order.remaining()),
+                        "message": format!("Order #{} canceled.", order.id),
+                    }))
+                }
+                _ => {
+                    Err("Invalid order number, or you are not the person who placed the order."
+                        .to_string())
+                }
+            }
+        }
+        Some("returnOpenOrders") => {
+            if params.get("currencyPair").map(|name| name.as_str()) == Some("all") {
+                let mut result = Map::new();
+                for (name, market) in state.markets.iter() {
+                    result.insert(name.clone(), open_orders(market));
+                }
+                return Ok(Value::Object(result));
+            }
+            let market = get_market(state, params)?;
+            Ok(open_orders(market))
+        }
+        _ => Err("Invalid command.".to_string()),
+    }
+}
+
+fn market_error(state: &MarketState, name: &str, side: OrderSide, error: MarketError) -> String {
+    match error {
+        MarketError::UnknownMarket => "Invalid currency pair.".to_string(),
+        MarketError::UnknownOrder => "Invalid order number.".to_string(),
+        MarketError::InvalidArguments => "Invalid rate or amount.".to_string(),
+        MarketError::InsufficientFunds => {
+            let currency = state.markets.get(name).map_or("", |market| match side {
+                OrderSide::Buy => market.quote.as_str(),
+                OrderSide::Sell => market.base.as_str(),
+            });
+            format!("Not enough {}.", currency)
+        }
+    }
+}
+
+fn open_orders(market: &Market) -> Value {
+    let orders: Vec<Value> = market.own_orders()
+        .iter()
+        .map(|order| {
+            json!({
+                "orderNumber": order.id.to_string(),
+                "type": match order.side {
+                    OrderSide::Buy => "buy",
+                    OrderSide::Sell => "sell",
+                },
+                "rate": format_amount(order.price),
+                "startingAmount": format_amount(order.volume),
+                "amount": format_amount(order.remaining()),
+                "total": format_amount(order.remaining() * order.price),
+                "date": format_date(order.timestamp),
+                "margin": 0,
+            })
+        })
+        .collect();
+    Value::Array(orders)
+}
+
+fn trade_to_json(trade: &MarketTrade) -> Value {
+    json!({
+        "globalTradeID": trade.id,
+        "tradeID": trade.id.to_string(),
+        "date": format_date(trade.timestamp),
+        "type": match trade.side {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        },
+        "rate": format_amount(trade.price),
+        "amount": format_amount(trade.volume),
+        "total": format_amount(trade.price * trade.volume),
+    })
+}
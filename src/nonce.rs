@@ -0,0 +1,141 @@
+//! Use this module to generate the nonces of private requests.
+//!
+//! Exchanges reject a private request whose nonce is not greater than the nonce of the previous
+//! request sent with the same API key. A `NonceGenerator` returns strictly increasing nonces
+//! (the current time in milliseconds, or the previous nonce + 1 if the clock did not move), and
+//! clones share the same sequence, so it can be used by several clients and threads.
+//!
+//! Every client uses `NonceGenerator::shared()` by default, so the clients of a process do not
+//! need any setup. Processes sharing an API key must use a generator persisted to the same file
+//! (`NonceGenerator::with_file`): each nonce is then read from and written to the file under an
+//! advisory lock (`flock` on Unix, `LockFileEx` on Windows), which the OS releases if the process
+//! dies while holding it.
+//!
+//! The clients wait for their rate limiter before taking a nonce, not after: a request waiting
+//! with a nonce taken could otherwise be sent after a request holding a greater one, and be
+//! rejected.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::env;
+//!
+//! use coinnect::config::ClientConfig;
+//! use coinnect::nonce::NonceGenerator;
+//!
+//! let nonces = NonceGenerator::with_file(env::temp_dir().join("coinnect_doc_nonce"));
+//! let config = ClientConfig::new().nonce_generator(nonces);
+//! ```
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use error::{Error, ErrorKind};
+use helpers;
+
+lazy_static! {
+    static ref SHARED: NonceGenerator = NonceGenerator::new();
+}
+
+/// A generator of strictly increasing nonces. Clones share the same sequence.
+#[derive(Clone)]
+pub struct NonceGenerator {
+    last: Arc<Mutex<u64>>,
+    path: Option<PathBuf>,
+}
+
+impl NonceGenerator {
+    /// Create a new NonceGenerator kept in memory.
+    pub fn new() -> NonceGenerator {
+        NonceGenerator {
+            last: Arc::new(Mutex::new(0)),
+            path: None,
+        }
+    }
+
+    /// Create a new NonceGenerator persisting the last nonce to the file specified, so that every
+    /// process using the file gets nonces greater than the ones already used. The file is locked
+    /// while each nonce is read and written.
+    pub fn with_file(path: PathBuf) -> NonceGenerator {
+        NonceGenerator {
+            last: Arc::new(Mutex::new(0)),
+            path: Some(path),
+        }
+    }
+
+    /// Return the generator used by every client configured without one.
+    pub fn shared() -> NonceGenerator {
+        SHARED.clone()
+    }
+
+    /// Return a nonce greater than every nonce returned before. Fail with
+    /// `ErrorKind::NonceError` if the file of the generator cannot be read or written.
+    pub fn next(&self) -> Result<u64, Error> {
+        let mut last = self.last.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut nonce = (helpers::get_unix_timestamp_ms() as u64).max(*last + 1);
+        if let Some(ref path) = self.path {
+            // Unlocked when the file is closed
+            let mut file = lock_file(path)?;
+            nonce = nonce.max(read_nonce(&mut file, path)? + 1);
+            write_nonce(&mut file, path, nonce)?;
+        }
+
+        *last = nonce;
+        Ok(nonce)
+    }
+}
+
+impl fmt::Debug for NonceGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NonceGenerator")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Open the nonce file specified, creating it if needed, and wait until no other process holds
+/// its lock to take it. `File::lock` sets the minimum Rust version of the crate (1.89).
+fn lock_file(path: &Path) -> Result<File, Error> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| nonce_error("Cannot open", path, e))?;
+    file.lock().map_err(|e| nonce_error("Cannot lock", path, e))?;
+    Ok(file)
+}
+
+/// Return the nonce stored in the file specified, or 0 if the file is empty (just created).
+fn read_nonce(file: &mut File, path: &Path) -> Result<u64, Error> {
+    let mut content = String::new();
+    file.read_to_string(&mut content).map_err(|e| nonce_error("Cannot read", path, e))?;
+
+    match content.trim() {
+        "" => Ok(0),
+        content => {
+            content.parse::<u64>().map_err(|e| {
+                let reason = format!("{} does not hold a nonce", path.display());
+                Error::new(ErrorKind::NonceError(reason)).with_source(e)
+            })
+        }
+    }
+}
+
+/// Replace the content of the file specified with the nonce, through the handle holding the lock.
+fn write_nonce(file: &mut File, path: &Path, nonce: u64) -> Result<(), Error> {
+    file.seek(SeekFrom::Start(0))
+        .and_then(|_| file.set_len(0))
+        .and_then(|_| file.write_all(nonce.to_string().as_bytes()))
+        .map_err(|e| nonce_error("Cannot write", path, e))
+}
+
+fn nonce_error(action: &str, path: &Path, source: io::Error) -> Error {
+    let reason = format!("{} {}", action, path.display());
+    Error::new(ErrorKind::NonceError(reason)).with_source(source)
+}
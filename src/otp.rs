@@ -0,0 +1,174 @@
+//! Use this module to send the one-time password (two-factor authentication) of an API key.
+//!
+//! Kraken lets an API key be protected with a second factor: either a static password, or a
+//! time-based one-time password (TOTP, RFC 6238) generated from a secret shared with an
+//! authenticator application. Private requests made with such a key must carry the password
+//! currently valid.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::kraken::api::KrakenApi;
+//! use coinnect::otp::Otp;
+//! use coinnect::transport::MockTransport;
+//!
+//! // The secret is the base32 string shown by Kraken when the 2FA is set up
+//! let otp = Otp::totp("JBSWY3DPEHPK3PXP").unwrap();
+//! let api = KrakenApi::with_transport("api_key", "api_secret", MockTransport::new())
+//!     .with_otp(otp);
+//! ```
+
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha1::Sha1;
+
+use std::fmt;
+
+use credentials::Secret;
+use error::{Error, ErrorKind};
+use helpers;
+
+/// The second factor of an API key. Its Debug output does not show the password or the secret,
+/// which are zeroized when dropped.
+#[derive(Clone, PartialEq)]
+pub enum Otp {
+    /// A static password.
+    Password(Secret),
+    /// A time-based one-time password generator.
+    Totp(Totp),
+}
+
+impl Otp {
+    /// Create a new Otp sending the static password specified.
+    pub fn password(password: &str) -> Otp {
+        Otp::Password(Secret::new(password))
+    }
+
+    /// Create a new Otp generating 6-digit passwords valid for 30 seconds from the base32 secret
+    /// specified. Return an InvalidArguments error if the secret is not valid base32.
+    pub fn totp(secret: &str) -> Result<Otp, Error> {
+        Ok(Otp::Totp(Totp::new(secret)?))
+    }
+
+    /// Return the password to send now.
+    pub fn current(&self) -> String {
+        match *self {
+            Otp::Password(ref password) => password.expose().to_string(),
+            Otp::Totp(ref totp) => totp.now(),
+        }
+    }
+}
+
+impl fmt::Debug for Otp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Otp::Password(_) => write!(f, "Password(..)"),
+            Otp::Totp(ref totp) => write!(f, "Totp({:?})", totp),
+        }
+    }
+}
+
+/// A time-based one-time password generator (RFC 6238) using HMAC-SHA1.
+#[derive(Clone, PartialEq)]
+pub struct Totp {
+    secret: Vec<u8>,
+    digits: u32,
+    period: u64,
+}
+
+impl Totp {
+    /// Create a new Totp generating 6-digit passwords valid for 30 seconds from the base32 secret
+    /// specified (spaces and padding are ignored, case does not matter).
+    pub fn new(secret: &str) -> Result<Totp, Error> {
+        Ok(Totp::from_bytes(decode_base32(secret)?))
+    }
+
+    /// Create a new Totp generating 6-digit passwords valid for 30 seconds from the raw secret
+    /// specified.
+    pub fn from_bytes(secret: Vec<u8>) -> Totp {
+        Totp {
+            secret: secret,
+            digits: 6,
+            period: 30,
+        }
+    }
+
+    /// Set the number of digits of the passwords (from 6 to 9).
+    pub fn digits(mut self, digits: u32) -> Totp {
+        self.digits = digits.max(6).min(9);
+        self
+    }
+
+    /// Set how long each password is valid, in seconds.
+    pub fn period(mut self, period: u64) -> Totp {
+        self.period = period.max(1);
+        self
+    }
+
+    /// Return the password valid at the unix time specified, in seconds.
+    pub fn at(&self, unix_time: u64) -> String {
+        let counter = unix_time / self.period;
+        let mut counter_bytes = [0u8; 8];
+        for (i, byte) in counter_bytes.iter_mut().enumerate() {
+            *byte = (counter >> (8 * (7 - i))) as u8;
+        }
+
+        let mut hmac = Hmac::new(Sha1::new(), &self.secret);
+        hmac.input(&counter_bytes);
+        let hash = hmac.result();
+        let hash = hash.code();
+
+        // Dynamic truncation (RFC 4226, section 5.3)
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let code = ((hash[offset] as u32 & 0x7f) << 24) | ((hash[offset + 1] as u32) << 16) |
+                   ((hash[offset + 2] as u32) << 8) | (hash[offset + 3] as u32);
+        let code = code % 10u32.pow(self.digits);
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+
+    /// Return the password valid now.
+    pub fn now(&self) -> String {
+        self.at((helpers::get_unix_timestamp_ms() / 1000) as u64)
+    }
+}
+
+impl Drop for Totp {
+    fn drop(&mut self) {
+        helpers::zeroize(&mut self.secret);
+    }
+}
+
+impl fmt::Debug for Totp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Totp")
+            .field("digits", &self.digits)
+            .field("period", &self.period)
+            .finish()
+    }
+}
+
+const BASE32_ALPHABET: &'static str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Decode a base32 string (RFC 4648 alphabet), ignoring spaces, padding and case.
+fn decode_base32(encoded: &str) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in encoded.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE32_ALPHABET.find(c.to_ascii_uppercase())
+            .ok_or(ErrorKind::InvalidArguments)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if bytes.is_empty() {
+        return Err(ErrorKind::InvalidArguments.into());
+    }
+    Ok(bytes)
+}
@@ -1,171 +1,120 @@
-//! This module contains Pair enum.
+//! Use this module to name the markets traded on the exchanges.
+//!
+//! A `Pair` is a base currency priced in a quote currency: on the ETH/BTC market, ETH is bought
+//! and sold for BTC, and prices are in BTC per ETH. The pairs are independent of the exchanges:
+//! each exchange names its markets in its own way (`XETHXXBT` on Kraken, `BTC_ETH` on Poloniex,
+//! `ethbtc` on Bitstamp), and the translation is done by its `utils` module.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::currency::Currency;
+//! use coinnect::pair::Pair;
+//!
+//! let pair: Pair = "ETH/BTC".parse().unwrap();
+//! assert_eq!(pair, Pair::new(Currency::ETH, Currency::BTC));
+//! assert_eq!(pair, Pair::ETH_BTC);
+//! assert_eq!(pair.inverse().to_string(), "BTC/ETH");
+//! ```
 
-/// Pair lists all pairs that can be traded on supported exchanges.
-/// Update date : 11/04/2017.
-/// Note : Kraken uses XBT instead of BTC (so the XBT/EUR pair becomes BTC/EUR).
-/// Order of quote currency <-> base currency is important. For example, Kraken supports ZEC_BTC
-/// but Poloniex is doing the opposite :  BTC_ZEC, which equal to 1/ZEC_BTC. So: ZEC_BTC != BTC_ZEC
-#[derive(Debug, Copy, Clone, PartialEq)]
-#[allow(non_camel_case_types)]
-pub enum Pair {
-    BTC_AMP,
-    BTC_ARDR,
-    BTC_BBR,
-    BTC_BCN,
-    BTC_BCY,
-    BTC_BELA,
-    BTC_BITS,
-    BTC_BLK,
-    BTC_BTCD,
-    BTC_BTM,
-    BTC_BTS,
-    BTC_BURST,
-    BTC_C2,
-    BTC_CAD_d,
-    BTC_CAD,
-    BTC_CLAM,
-    BTC_CURE,
-    BTC_DASH,
-    BTC_DCR,
-    BTC_DGB,
-    BTC_DOGE,
-    BTC_EMC2,
-    BTC_ETC,
-    BTC_ETH,
-    BTC_EUR_d,
-    BTC_EUR,
-    BTC_EXP,
-    BTC_FCT,
-    BTC_FLDC,
-    BTC_FLO,
-    BTC_GAME,
-    BTC_GBP_d,
-    BTC_GBP,
-    BTC_GNT,
-    BTC_GRC,
-    BTC_HUC,
-    BTC_HZ,
-    BTC_IOC,
-    BTC_JPY_d,
-    BTC_JPY,
-    BTC_LBC,
-    BTC_LSK,
-    BTC_LTC,
-    BTC_MAID,
-    BTC_MYR,
-    BTC_NAUT,
-    BTC_NAV,
-    BTC_NEOS,
-    BTC_NMC,
-    BTC_NOBL,
-    BTC_NOTE,
-    BTC_NSR,
-    BTC_NXC,
-    BTC_NXT,
-    BTC_OMNI,
-    BTC_PASC,
-    BTC_PINK,
-    BTC_POT,
-    BTC_PPC,
-    BTC_QBK,
-    BTC_QORA,
-    BTC_QTL,
-    BTC_RADS,
-    BTC_RBY,
-    BTC_REP,
-    BTC_RIC,
-    BTC_SBD,
-    BTC_SC,
-    BTC_SDC,
-    BTC_SJCX,
-    BTC_STEEM,
-    BTC_STR,
-    BTC_STRAT,
-    BTC_SYS,
-    BTC_UNITY,
-    BTC_USD_d,
-    BTC_USD,
-    BTC_VIA,
-    BTC_VOX,
-    BTC_VRC,
-    BTC_VTC,
-    BTC_XBC,
-    BTC_XCP,
-    BTC_XEM,
-    BTC_XMG,
-    BTC_XMR,
-    BTC_XPM,
-    BTC_XRP,
-    BTC_XVC,
-    BTC_ZEC,
-    DASH_BTC,
-    ETC_BTC,
-    ETC_ETH,
-    ETC_EUR,
-    ETC_USD,
-    ETH_BTC,
-    ETH_CAD_d,
-    ETH_CAD,
-    ETH_ETC,
-    ETH_EUR_d,
-    ETH_EUR,
-    ETH_GBP_d,
-    ETH_GBP,
-    ETH_GNT,
-    ETH_JPY_d,
-    ETH_JPY,
-    ETH_LSK,
-    ETH_REP,
-    ETH_STEEM,
-    ETH_USD_d,
-    ETH_USD,
-    ETH_XBT_d,
-    ETH_ZEC,
-    EUR_USD,
-    ICN_BTC,
-    ICN_ETH,
-    LTC_BTC,
-    LTC_EUR,
-    LTC_USD,
-    MLN_BTC,
-    MLN_ETH,
-    REP_BTC,
-    REP_ETH,
-    REP_EUR,
-    REP_USD,
-    USDT_BTC,
-    USDT_DASH,
-    USDT_ETC,
-    USDT_ETH,
-    USDT_LTC,
-    USDT_NXT,
-    USDT_REP,
-    USDT_STR,
-    USDT_USD,
-    USDT_XMR,
-    USDT_XRP,
-    USDT_ZEC,
-    XDG_BTC,
-    XLM_BTC,
-    XLM_EUR,
-    XLM_USD,
-    XMR_BBR,
-    XMR_BCN,
-    XMR_BLK,
-    XMR_BTC,
-    XMR_BTCD,
-    XMR_DASH,
-    XMR_EUR,
-    XMR_LTC,
-    XMR_MAID,
-    XMR_NXT,
-    XMR_QORA,
-    XMR_USD,
-    XMR_ZEC,
-    XRP_BTC,
-    XRP_EUR,
-    XRP_USD,
-    ZEC_BTC,
-    ZEC_EUR,
-    ZEC_USD,
+use std::fmt;
+use std::str::FromStr;
+
+use currency::Currency;
+use error::{Error, ErrorKind};
+
+/// A market: the base currency, priced in the quote currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pair {
+    /// The currency bought and sold.
+    pub base: Currency,
+    /// The currency the prices are expressed in.
+    pub quote: Currency,
+}
+
+impl Pair {
+    pub const BTC_CAD: Pair = Pair::new(Currency::BTC, Currency::CAD);
+    pub const BTC_EUR: Pair = Pair::new(Currency::BTC, Currency::EUR);
+    pub const BTC_GBP: Pair = Pair::new(Currency::BTC, Currency::GBP);
+    pub const BTC_JPY: Pair = Pair::new(Currency::BTC, Currency::JPY);
+    pub const BTC_USD: Pair = Pair::new(Currency::BTC, Currency::USD);
+    pub const BTC_USDT: Pair = Pair::new(Currency::BTC, Currency::USDT);
+    pub const DASH_BTC: Pair = Pair::new(Currency::DASH, Currency::BTC);
+    pub const DOGE_BTC: Pair = Pair::new(Currency::DOGE, Currency::BTC);
+    pub const ETC_BTC: Pair = Pair::new(Currency::ETC, Currency::BTC);
+    pub const ETC_ETH: Pair = Pair::new(Currency::ETC, Currency::ETH);
+    pub const ETC_EUR: Pair = Pair::new(Currency::ETC, Currency::EUR);
+    pub const ETC_USD: Pair = Pair::new(Currency::ETC, Currency::USD);
+    pub const ETH_BTC: Pair = Pair::new(Currency::ETH, Currency::BTC);
+    pub const ETH_CAD: Pair = Pair::new(Currency::ETH, Currency::CAD);
+    pub const ETH_EUR: Pair = Pair::new(Currency::ETH, Currency::EUR);
+    pub const ETH_GBP: Pair = Pair::new(Currency::ETH, Currency::GBP);
+    pub const ETH_JPY: Pair = Pair::new(Currency::ETH, Currency::JPY);
+    pub const ETH_USD: Pair = Pair::new(Currency::ETH, Currency::USD);
+    pub const EUR_USD: Pair = Pair::new(Currency::EUR, Currency::USD);
+    pub const ICN_BTC: Pair = Pair::new(Currency::ICN, Currency::BTC);
+    pub const ICN_ETH: Pair = Pair::new(Currency::ICN, Currency::ETH);
+    pub const LTC_BTC: Pair = Pair::new(Currency::LTC, Currency::BTC);
+    pub const LTC_EUR: Pair = Pair::new(Currency::LTC, Currency::EUR);
+    pub const LTC_USD: Pair = Pair::new(Currency::LTC, Currency::USD);
+    pub const MLN_BTC: Pair = Pair::new(Currency::MLN, Currency::BTC);
+    pub const MLN_ETH: Pair = Pair::new(Currency::MLN, Currency::ETH);
+    pub const REP_BTC: Pair = Pair::new(Currency::REP, Currency::BTC);
+    pub const REP_ETH: Pair = Pair::new(Currency::REP, Currency::ETH);
+    pub const REP_EUR: Pair = Pair::new(Currency::REP, Currency::EUR);
+    pub const REP_USD: Pair = Pair::new(Currency::REP, Currency::USD);
+    pub const USDT_USD: Pair = Pair::new(Currency::USDT, Currency::USD);
+    pub const XLM_BTC: Pair = Pair::new(Currency::XLM, Currency::BTC);
+    pub const XLM_EUR: Pair = Pair::new(Currency::XLM, Currency::EUR);
+    pub const XLM_USD: Pair = Pair::new(Currency::XLM, Currency::USD);
+    pub const XMR_BTC: Pair = Pair::new(Currency::XMR, Currency::BTC);
+    pub const XMR_EUR: Pair = Pair::new(Currency::XMR, Currency::EUR);
+    pub const XMR_USD: Pair = Pair::new(Currency::XMR, Currency::USD);
+    pub const XRP_BTC: Pair = Pair::new(Currency::XRP, Currency::BTC);
+    pub const XRP_EUR: Pair = Pair::new(Currency::XRP, Currency::EUR);
+    pub const XRP_USD: Pair = Pair::new(Currency::XRP, Currency::USD);
+    pub const ZEC_BTC: Pair = Pair::new(Currency::ZEC, Currency::BTC);
+    pub const ZEC_EUR: Pair = Pair::new(Currency::ZEC, Currency::EUR);
+    pub const ZEC_USD: Pair = Pair::new(Currency::ZEC, Currency::USD);
+
+    /// Create a new Pair of the base currency specified, priced in the quote currency.
+    pub const fn new(base: Currency, quote: Currency) -> Pair {
+        Pair {
+            base: base,
+            quote: quote,
+        }
+    }
+
+    /// Return the pair with the base and quote currencies swapped: the inverse of BTC/EUR is
+    /// EUR/BTC, whose prices are the inverses of the prices of BTC/EUR.
+    pub fn inverse(&self) -> Pair {
+        Pair::new(self.quote, self.base)
+    }
+}
+
+impl fmt::Display for Pair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
+
+impl FromStr for Pair {
+    type Err = Error;
+
+    /// Parse a pair written "BASE/QUOTE" or "BASE_QUOTE" ("BTC/EUR", "btc_eur"). Return an
+    /// InvalidArguments error if it is not.
+    fn from_str(pair: &str) -> Result<Pair, Error> {
+        let invalid = || {
+            Error::new(ErrorKind::InvalidArguments)
+                .with_message(&format!("Invalid pair {}, expected BASE/QUOTE", pair))
+        };
+        let mut currencies = pair.split(&['/', '_'][..]);
+        match (currencies.next(), currencies.next(), currencies.next()) {
+            (Some(base), Some(quote), None) => {
+                Ok(Pair::new(base.parse().map_err(|_| invalid())?,
+                             quote.parse().map_err(|_| invalid())?))
+            }
+            _ => Err(invalid()),
+        }
+    }
 }
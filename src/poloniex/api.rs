@@ -2,73 +2,85 @@
 //! Use this module to interact with Poloniex exchange.
 //! See examples for more informations.
 
-use crypto::hmac::Hmac;
-use crypto::mac::Mac;
-use crypto::sha2::Sha512;
-
-use hyper_native_tls::NativeTlsClient;
-use hyper::Client;
-use hyper::header;
-use hyper::net::HttpsConnector;
-
-use rustc_serialize::hex::ToHex;
-
-use serde_json;
 use serde_json::Value;
 use serde_json::value::Map;
 
 use std::collections::HashMap;
-use std::io::Read;
-use std::thread;
-use std::time::Duration;
 use std::path::PathBuf;
-use std::fs::File;
 
+use config::ClientConfig;
+use credentials::{Account, Credentials};
 use error;
+use error::ResponseContext;
+use exchange::Exchange;
 use helpers;
+use market::MarketCache;
+use nonce::NonceGenerator;
+use rate_limiter::RateLimiter;
+use retry::RetryPolicy;
+use scope::Scope;
+use transport::{Transport, HttpsTransport, HttpRequest};
+use validator::OrderValidator;
 
 use poloniex::utils;
 
-header! {
-    #[doc(hidden)]
-    (KeyHeader, "Key") => [String]
-}
-
-header! {
-    #[doc(hidden)]
-    (SignHeader, "Sign") => [String]
-}
-
-header! {
-    #[doc(hidden)]
-    (ContentHeader, "Content-Type") => [String]
-}
-
 #[derive(Debug)]
 pub struct PoloniexApi {
-    last_request: i64, // unix timestamp in ms, to avoid ban
-    api_key: String,
-    api_secret: String,
-    http_client: Client,
+    credentials: Credentials,
+    base_url: String,
+    rate_limiter: RateLimiter,
+    private_rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
+    nonce_generator: NonceGenerator,
+    scope: Scope,
+    market_cache: MarketCache,
+    order_validator: OrderValidator,
+    last_response: Option<ResponseContext>,
+    transport: Box<Transport>,
 }
 
 
 impl PoloniexApi {
     /// Create a new PoloniexApi by providing an API key & API secret
-    pub fn new(api_key: &str, api_secret: &str) -> PoloniexApi {
-        let ssl = NativeTlsClient::new().unwrap();
-        let connector = HttpsConnector::new(ssl);
-
+    pub fn new(api_key: &str, api_secret: &str) -> Result<PoloniexApi, error::Error> {
+        Ok(PoloniexApi::with_transport(api_key, api_secret, HttpsTransport::new()?))
+    }
+
+    /// Create a new PoloniexApi sending its requests through the Transport specified instead of
+    /// HTTPS. This is mostly useful to test the client with a `MockTransport`.
+    pub fn with_transport<T>(api_key: &str, api_secret: &str, transport: T) -> PoloniexApi
+        where T: Transport + 'static
+    {
+        PoloniexApi::with_config(api_key, api_secret, ClientConfig::default(), transport)
+    }
+
+    /// Create a new PoloniexApi using the configuration specified (base URL, ...) and sending
+    /// its requests through the Transport specified.
+    pub fn with_config<T>(api_key: &str,
+                          api_secret: &str,
+                          config: ClientConfig,
+                          transport: T)
+                          -> PoloniexApi
+        where T: Transport + 'static
+    {
+        let rate_limiter = config.rate_limiter_or(utils::rate_limiter());
         PoloniexApi {
-            last_request: 0,
-            api_key: api_key.to_string(),
-            api_secret: api_secret.to_string(),
-            http_client: Client::with_connector(connector),
+            credentials: Credentials::new(api_key, api_secret),
+            base_url: config.base_url_or(utils::BASE_URL),
+            private_rate_limiter: config.private_rate_limiter_or(rate_limiter.clone()),
+            retry_policy: config.retry_policy_or(RetryPolicy::new()),
+            nonce_generator: config.nonce_generator_or(NonceGenerator::shared()),
+            scope: config.scope_or(Scope::default()),
+            market_cache: config.market_cache_or(MarketCache::default()),
+            order_validator: config.order_validator_or(OrderValidator::default()),
+            last_response: None,
+            rate_limiter: rate_limiter,
+            transport: Box::new(transport),
         }
     }
 
-    /// Create a new PoloniexApi from a json configuration file. This file must follow this
-    /// structure:
+    /// Create a new PoloniexApi from a JSON or TOML configuration file (see the `credentials`
+    /// module). A JSON file must follow this structure:
     ///
     /// ```ignore
     /// {
@@ -86,26 +98,46 @@ impl PoloniexApi {
     /// ```
     /// For this example, you could use load your Poloniex account with
     /// `new_from_file("account_poloniex", Path::new("/keys.json"))`
-    pub fn new_from_file(config_name: &str, path: PathBuf) -> PoloniexApi {
-        let mut f = File::open(&path).unwrap();
-        let mut buffer = String::new();
-        f.read_to_string(&mut buffer).unwrap();
+    pub fn new_from_file(config_name: &str, path: PathBuf) -> Result<PoloniexApi, error::Error> {
+        let account = Account::from_exchange_file(Exchange::Poloniex, config_name, &path)?;
+        let credentials = account.credentials();
 
-        let data: Value = serde_json::from_str(&buffer).unwrap();
-        let json_obj = data.as_object().unwrap().get(config_name).unwrap();
-        let api_key = json_obj.get("api_key").unwrap().as_str().unwrap();
-        let api_secret = json_obj.get("api_secret").unwrap().as_str().unwrap();
+        PoloniexApi::new(credentials.api_key(), credentials.api_secret())
+    }
 
-        PoloniexApi::new(api_key, api_secret)
+    /// Return the cache of the markets listed by Poloniex (see `ExchangeApi::markets`).
+    pub fn market_cache(&self) -> &MarketCache {
+        &self.market_cache
     }
 
-    fn block_or_continue(&self) {
-        let threshold = 167; // 6 requests/sec = 1/6*1000
-        let delay = helpers::get_unix_timestamp_ms() - self.last_request;
-        if delay < threshold {
-            let duration_ms = Duration::from_millis(delay as u64);
-            thread::sleep(duration_ms);
-        }
+    /// Return the validator of the orders placed through the generic API.
+    pub fn order_validator(&self) -> &OrderValidator {
+        &self.order_validator
+    }
+
+    /// Return the endpoint called by the last request and the response received, if any. The
+    /// generic API attaches them to the errors raised while reading the response.
+    pub fn last_response(&self) -> Option<&ResponseContext> {
+        self.last_response.as_ref()
+    }
+
+    /// Send a request to the endpoint specified and deserialize the response. Errors carry the
+    /// endpoint, and the status and body of the response when one was received.
+    fn send(&mut self,
+            endpoint: &str,
+            request: &HttpRequest)
+            -> Result<Map<String, Value>, error::Error> {
+        let response = self.transport
+            .send(request)
+            .map_err(|e| e.with_endpoint(Exchange::Poloniex, endpoint))?;
+        self.last_response = Some(ResponseContext::new(Exchange::Poloniex,
+                                                       endpoint,
+                                                       response.status,
+                                                       &response.body));
+        utils::deserialize_json(response.body.clone()).map_err(|e| {
+            e.with_endpoint(Exchange::Poloniex, endpoint)
+                .with_response(response.status, &response.body)
+        })
     }
 
     fn public_query(&mut self,
@@ -114,56 +146,51 @@ impl PoloniexApi {
                     -> Result<Map<String, Value>, error::Error> {
         let mut params = params.clone();
         helpers::strip_empties(&mut params);
-        let url = "https://poloniex.com/public?command=".to_string() + method + "&" +
+        let url = self.base_url.clone() + "/public?command=" + method + "&" +
                   &helpers::url_encode_hashmap(&params);
 
-        self.block_or_continue();
-        let mut response = match self.http_client.get(&url).send() {
-            Ok(response) => response,
-            Err(_) => return Err(error::Error::ServiceUnavailable),
-        };
-        self.last_request = helpers::get_unix_timestamp_ms();
-        let mut buffer = String::new();
-        response.read_to_string(&mut buffer).unwrap();
-        return utils::deserialize_json(buffer);
+        let retry_policy = self.retry_policy.clone();
+        retry_policy.run(|| {
+                             self.rate_limiter.acquire(1.0);
+                             self.send(method, &HttpRequest::get(&url))
+                         },
+                         utils::is_retryable)
     }
 
     fn private_query(&mut self,
                      method: &str,
                      params: &HashMap<&str, &str>)
                      -> Result<Map<String, Value>, error::Error> {
-        let unix_timestamp = helpers::get_unix_timestamp_ms().to_string();
+        self.scope
+            .check(utils::required_scope(method))
+            .map_err(|e| e.with_endpoint(Exchange::Poloniex, method))?;
+
+        let retry_policy = self.retry_policy.for_request(utils::is_read_only(method));
+        retry_policy.run(|| self.signed_query(method, params), utils::is_retryable)
+    }
+
+    /// Sign the private request specified with a new nonce and send it.
+    fn signed_query(&mut self,
+                    method: &str,
+                    params: &HashMap<&str, &str>)
+                    -> Result<Map<String, Value>, error::Error> {
+        self.private_rate_limiter.acquire(1.0);
+        let nonce = self.nonce_generator.next()?.to_string();
         let mut post_params = params.clone();
         post_params.insert("command", method);
-        post_params.insert("nonce", &unix_timestamp);
+        post_params.insert("nonce", &nonce);
         helpers::strip_empties(&mut post_params);
         let post_data = helpers::url_encode_hashmap(&post_params);
 
-        let mut hmac = Hmac::new(Sha512::new(), self.api_secret.as_bytes());
-        hmac.input(post_data.as_bytes());
-
-        let sign = hmac.result().code().to_hex();
+        let sign = utils::create_signature(self.credentials.api_secret(), &post_data);
 
-        let mut custom_header = header::Headers::new();
-        custom_header.set(KeyHeader(self.api_key.to_owned()));
-        custom_header.set(SignHeader(sign));
-        custom_header.set(ContentHeader("application/x-www-form-urlencoded".to_owned()));
+        let url = self.base_url.clone() + "/tradingApi";
+        let request = HttpRequest::post(&url, &post_data)
+            .with_header("Key", self.credentials.api_key())
+            .with_header("Sign", &sign)
+            .with_header("Content-Type", "application/x-www-form-urlencoded");
 
-        self.block_or_continue();
-
-        let mut response = match self.http_client
-            .post("https://poloniex.com/tradingApi")
-            .body(&post_data)
-            .headers(custom_header)
-            .send() {
-            Ok(response) => response,
-            Err(_) => return Err(error::Error::ServiceUnavailable),
-        };
-        self.last_request = helpers::get_unix_timestamp_ms();
-
-        let mut buffer = String::new();
-        response.read_to_string(&mut buffer).unwrap();
-        return utils::deserialize_json(buffer);
+        self.send(method, &request)
     }
 
     /// Sample output :
@@ -211,13 +238,15 @@ impl PoloniexApi {
         self.public_query("returnOrderBook", &params)
     }
 
+    /// Poloniex returns an array, which is wrapped in an object under the "data" key.
+    ///
     /// Sample output :
     ///
     /// ```ignore
-    /// [{"date":"2014-02-10 04:23:23","type":"buy","rate":"0.00007600","amount":"140",
-    /// "total":"0.01064"},
-    /// {"date":"2014-02-10 01:19:37","type":"buy","rate":"0.00007600","amount":"655",
-    /// "total":"0.04978"}, ... ]
+    /// {"data": [{"globalTradeID":25129732,"tradeID":6325758,"date":"2014-02-10 04:23:23",
+    /// "type":"buy","rate":"0.00007600","amount":"140","total":"0.01064"},
+    /// {"globalTradeID":25129731,"tradeID":6325757,"date":"2014-02-10 01:19:37",
+    /// "type":"buy","rate":"0.00007600","amount":"655","total":"0.04978"}, ... ]}
     /// ```
     pub fn return_trade_history(&mut self,
                                 currency_pair: &str,
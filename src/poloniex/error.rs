@@ -0,0 +1,88 @@
+//! Use this module to identify the errors returned by Poloniex.
+//!
+//! Poloniex errors are sentences, some of them holding values: "Not enough BTC.", "Nonce must be
+//! greater than 1491244592381. You provided 1491244592380.". The generic API maps them into the
+//! `ErrorKind` of the Error returned, which keeps the original sentence (see `Error::message`).
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::error::ErrorKind;
+//! use coinnect::poloniex::error::PoloniexError;
+//!
+//! let error = PoloniexError::from_message("Not enough BTC.");
+//! assert_eq!(error, PoloniexError::NotEnoughFunds);
+//! assert_eq!(error.kind(), ErrorKind::InsufficientFunds);
+//! ```
+
+use error::ErrorKind;
+
+/// An error of the Poloniex API.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoloniexError {
+    InvalidCommand,
+    InvalidApiKey,
+    InvalidNonce,
+    PermissionDenied,
+    TooManyRequests,
+    IpThrottled,
+    AccountFrozen,
+    InternalError,
+    InvalidCurrencyPair,
+    InvalidParameter,
+    NotEnoughFunds,
+    TotalTooLow,
+    AmountTooLow,
+    RateTooLow,
+    InvalidOrderNumber,
+    /// An unknown error, holding the message returned.
+    Other(String),
+}
+
+impl PoloniexError {
+    /// Return the PoloniexError of a message returned by Poloniex.
+    pub fn from_message(message: &str) -> PoloniexError {
+        match message {
+            "Invalid command." => PoloniexError::InvalidCommand,
+            "Invalid API key/secret pair." => PoloniexError::InvalidApiKey,
+            "Permission denied." => PoloniexError::PermissionDenied,
+            "Internal error. Please try again." => PoloniexError::InternalError,
+            "Invalid currency pair." => PoloniexError::InvalidCurrencyPair,
+            "Rate must be greater than zero." => PoloniexError::RateTooLow,
+            m if m.starts_with("Nonce must be greater than") => PoloniexError::InvalidNonce,
+            m if m.starts_with("Please do not make more than") => PoloniexError::TooManyRequests,
+            m if m.starts_with("This IP has been") => PoloniexError::IpThrottled,
+            m if m.starts_with("Your account is frozen") => PoloniexError::AccountFrozen,
+            m if m.starts_with("Invalid ") && m.ends_with(" parameter.") => {
+                PoloniexError::InvalidParameter
+            }
+            m if m.starts_with("Not enough ") => PoloniexError::NotEnoughFunds,
+            m if m.starts_with("Total must be at least") => PoloniexError::TotalTooLow,
+            m if m.starts_with("Amount must be at least") => PoloniexError::AmountTooLow,
+            m if m.starts_with("Invalid order number") => PoloniexError::InvalidOrderNumber,
+            _ => PoloniexError::Other(message.to_string()),
+        }
+    }
+
+    /// Return the generic category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            PoloniexError::InvalidCommand |
+            PoloniexError::InvalidParameter => ErrorKind::InvalidArguments,
+            PoloniexError::InvalidApiKey => ErrorKind::InvalidLogin,
+            PoloniexError::InvalidNonce => ErrorKind::InvalidNonce,
+            PoloniexError::PermissionDenied => ErrorKind::PermissionDenied,
+            PoloniexError::TooManyRequests |
+            PoloniexError::IpThrottled => ErrorKind::RateLimitExceeded,
+            PoloniexError::AccountFrozen => ErrorKind::AccountLocked,
+            PoloniexError::InternalError => ErrorKind::ServiceUnavailable,
+            PoloniexError::InvalidCurrencyPair => ErrorKind::PairUnsupported,
+            PoloniexError::NotEnoughFunds => ErrorKind::InsufficientFunds,
+            PoloniexError::TotalTooLow |
+            PoloniexError::AmountTooLow |
+            PoloniexError::RateTooLow => ErrorKind::InvalidOrder,
+            PoloniexError::InvalidOrderNumber => ErrorKind::UnknownOrder,
+            PoloniexError::Other(ref message) => ErrorKind::ExchangeSpecificError(message.clone()),
+        }
+    }
+}
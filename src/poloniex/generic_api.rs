@@ -6,47 +6,325 @@
 use serde_json::Value;
 use serde_json::value::Map;
 
+use std::collections::HashMap;
+
 use exchange::ExchangeApi;
 use poloniex::api::PoloniexApi;
 
-use error::Error;
+use error::{Error, ErrorKind, ResponseContext};
+use market::MarketCache;
 use pair::Pair;
-use types::Ticker;
+use types::{Ticker, Orderbook, OrderSide, Trade, TradeHistory, Balance, Balances};
+use types::{Market, MarketInfo, Order, OrderId, OrderRequest, OrderStatus, OrderType};
 use poloniex::utils;
+use validator::OrderValidator;
 use helpers;
+use helpers::{GenericApi, get_pair_string, response_error, validate_order};
+
+impl GenericApi for PoloniexApi {
+    fn market_cache(&self) -> &MarketCache {
+        PoloniexApi::market_cache(self)
+    }
+
+    fn order_validator(&self) -> &OrderValidator {
+        PoloniexApi::order_validator(self)
+    }
+
+    fn last_response(&self) -> Option<&ResponseContext> {
+        PoloniexApi::last_response(self)
+    }
+
+    fn fallback_pair_string(pair: &Pair) -> Option<String> {
+        utils::get_pair_string(pair)
+    }
+}
 
 impl ExchangeApi for PoloniexApi {
+    fn markets(&mut self) -> Result<Vec<Market>, Error> {
+        if let Some(markets) = self.market_cache().get() {
+            return Ok(markets);
+        }
+
+        let raw_response = self.return_currencies()?;
+        let currencies = utils::parse_result(raw_response).map_err(|e| response_error(self, e))?;
+
+        let raw_response = self.return_ticker()?;
+
+        let markets = parse_markets(raw_response, &currencies)
+            .map_err(|e| response_error(self, e))?;
+
+        self.market_cache().set(markets.clone());
+        Ok(markets)
+    }
+
     fn ticker(&mut self, pair: Pair) -> Result<Ticker, Error> {
-        let pair_name = match utils::get_pair_string(&pair) {
-            Some(name) => name,
-            None => return Err(Error::PairUnsupported),
-        };
+        let pair_name = get_pair_string(self, &pair)?;
         let raw_response = self.return_ticker()?;
 
-        let result = utils::parse_result(raw_response)?;
+        parse_ticker(raw_response, pair, &pair_name).map_err(|e| response_error(self, e))
+    }
+
+    fn orderbook(&mut self, pair: Pair) -> Result<Orderbook, Error> {
+        let pair_name = get_pair_string(self, &pair)?;
 
-        let price = result[*pair_name]["last"].as_str().unwrap().parse::<f64>().unwrap();
-        let ask = result[*pair_name]["lowestAsk"].as_str().unwrap().parse::<f64>().unwrap();
-        let bid = result[*pair_name]["highestBid"].as_str().unwrap().parse::<f64>().unwrap();
-        let vol = result[*pair_name]["quoteVolume"].as_str().unwrap().parse::<f64>().unwrap();
+        let raw_response = self.return_order_book(&pair_name, "")?;
 
-        Ok(Ticker {
-            timestamp: helpers::get_unix_timestamp_ms(),
-            pair: pair,
-            last_trade_price: price,
-            lowest_ask: ask,
-            highest_bid: bid,
-            volume: Some(vol),
-        })
+        parse_orderbook(raw_response, pair).map_err(|e| response_error(self, e))
+    }
+
+    fn trades(&mut self, pair: Pair, since: Option<&str>) -> Result<TradeHistory, Error> {
+        let pair_name = get_pair_string(self, &pair)?;
+
+        // Without a range, only the last 200 trades are returned: ask for the last day instead to
+        // reach back to `since`
+        let (start, end) = match since {
+            Some(_) => {
+                let now = helpers::get_unix_timestamp_ms() / 1000;
+                ((now - 24 * 60 * 60).to_string(), now.to_string())
+            }
+            None => (String::new(), String::new()),
+        };
+        let raw_response = self.return_trade_history(&pair_name, &start, &end)?;
+
+        parse_trades(raw_response, pair, since).map_err(|e| response_error(self, e))
+    }
+
+    fn balances(&mut self) -> Result<Balances, Error> {
+        let raw_response = self.return_complete_balances()?;
 
+        parse_balances(raw_response).map_err(|e| response_error(self, e))
     }
-    fn return_trade_history(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+
+    fn place_order(&mut self, order: OrderRequest) -> Result<OrderId, Error> {
+        // Poloniex only supports limit orders
+        if order.order_type == OrderType::Market {
+            return Err(ErrorKind::OrderTypeUnsupported.into());
+        }
+        let pair_name = get_pair_string(self, &order.pair)?;
+        let order = validate_order(self, order)?;
+        let price = order.price.ok_or(ErrorKind::InvalidArguments)?;
+
+        let raw_response = match order.side {
+            OrderSide::Buy => {
+                self.buy(&pair_name, &price.to_string(), &order.volume.to_string())?
+            }
+            OrderSide::Sell => {
+                self.sell(&pair_name, &price.to_string(), &order.volume.to_string())?
+            }
+        };
+
+        parse_order_id(raw_response).map_err(|e| response_error(self, e))
     }
-    fn return_order_book(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+
+    fn cancel_order(&mut self, id: OrderId) -> Result<(), Error> {
+        let raw_response = PoloniexApi::cancel_order(self, &id.0)?;
+
+        utils::parse_result(raw_response).map_err(|e| response_error(self, e))?;
+        Ok(())
     }
-    fn return_balances(&mut self, _: Pair) -> Option<Map<String, Value>> {
-        unimplemented!();
+
+    fn open_orders(&mut self, pair: Pair) -> Result<Vec<Order>, Error> {
+        let pair_name = get_pair_string(self, &pair)?;
+
+        let raw_response = self.return_open_orders(&pair_name)?;
+
+        parse_open_orders(raw_response, pair).map_err(|e| response_error(self, e))
     }
-}
\ No newline at end of file
+}
+
+/// Return the markets of a returnTicker response, without the frozen markets and the ones of the
+/// delisted currencies of a returnCurrencies response.
+fn parse_markets(raw_response: Map<String, Value>,
+                 currencies: &Map<String, Value>)
+                 -> Result<Vec<Market>, Error> {
+    let tickers = utils::parse_result(raw_response)?;
+
+    // Frozen markets and delisted currencies can not be traded
+    let is_set = |flag: &Value| flag.as_u64() == Some(1) || flag.as_str() == Some("1");
+    let is_tradable = |currency: &str| match currencies.get(currency) {
+        Some(info) => !is_set(&info["delisted"]) && !is_set(&info["frozen"]),
+        None => true,
+    };
+
+    let mut markets = Vec::with_capacity(tickers.len());
+    for (name, ticker) in tickers.iter() {
+        if is_set(&ticker["isFrozen"]) {
+            continue;
+        }
+        let pair = match utils::get_pair(name) {
+            Some(pair) => pair,
+            None => continue,
+        };
+        if !name.split('_').all(&is_tradable) {
+            continue;
+        }
+
+        // Poloniex applies the same rules to every market, and does not publish the
+        // leverages of its margin markets
+        markets.push(Market {
+            pair: pair,
+            symbol: name.clone(),
+            info: MarketInfo {
+                price_decimals: utils::DECIMALS,
+                volume_decimals: utils::DECIMALS,
+                min_volume: None,
+                min_value: Some(utils::MIN_ORDER_TOTAL),
+                leverage: Vec::new(),
+                maker_fee: utils::MAKER_FEE,
+                taker_fee: utils::TAKER_FEE,
+            },
+        });
+    }
+    Ok(markets)
+}
+
+/// Return the ticker of the pair specified from a returnTicker response.
+fn parse_ticker(raw_response: Map<String, Value>,
+                pair: Pair,
+                pair_name: &str)
+                -> Result<Ticker, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let ticker = result.get(pair_name).ok_or(ErrorKind::BadParse)?;
+    let price = helpers::parse_json_decimal(&ticker["last"])?;
+    let ask = helpers::parse_json_decimal(&ticker["lowestAsk"])?;
+    let bid = helpers::parse_json_decimal(&ticker["highestBid"])?;
+    let vol = helpers::parse_json_decimal(&ticker["quoteVolume"])?;
+
+    Ok(Ticker {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        last_trade_price: price,
+        lowest_ask: ask,
+        highest_bid: bid,
+        volume: Some(vol),
+    })
+}
+
+/// Return the order book of a returnOrderBook response.
+fn parse_orderbook(raw_response: Map<String, Value>, pair: Pair) -> Result<Orderbook, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let mut asks = helpers::parse_offers(result.get("asks").ok_or(ErrorKind::BadParse)?)?;
+    let mut bids = helpers::parse_offers(result.get("bids").ok_or(ErrorKind::BadParse)?)?;
+    helpers::sort_offers(&mut asks, &mut bids);
+
+    Ok(Orderbook {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        asks: asks,
+        bids: bids,
+    })
+}
+
+/// Return the trades following the one specified from a returnTradeHistory response.
+fn parse_trades(raw_response: Map<String, Value>,
+                pair: Pair,
+                since: Option<&str>)
+                -> Result<TradeHistory, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let entries = result.get("data")
+        .and_then(|data| data.as_array())
+        .ok_or(ErrorKind::BadParse)?;
+    let mut trades = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let side = match entry["type"].as_str() {
+            Some("buy") => OrderSide::Buy,
+            Some("sell") => OrderSide::Sell,
+            _ => return Err(ErrorKind::BadParse.into()),
+        };
+        let date = entry["date"].as_str().ok_or(ErrorKind::BadParse)?;
+
+        trades.push(Trade {
+            id: Some(helpers::json_to_string(&entry["tradeID"])?),
+            timestamp: helpers::parse_utc_date_ms(date, "%Y-%m-%d %H:%M:%S")?,
+            price: helpers::parse_json_decimal(&entry["rate"])?,
+            volume: helpers::parse_json_decimal(&entry["amount"])?,
+            side: side,
+            order_type: None,
+        });
+    }
+
+    // Poloniex has no cursor, the trades are filtered by id
+    let (trades, last, gap) = helpers::filter_trades_since(trades, since)?;
+
+    Ok(TradeHistory {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        pair: pair,
+        trades: trades,
+        last: last,
+        gap: gap,
+    })
+}
+
+/// Return the balances of a returnCompleteBalances response.
+fn parse_balances(raw_response: Map<String, Value>) -> Result<Balances, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let mut balances = HashMap::new();
+    for (currency, balance) in result.iter() {
+        let available = helpers::parse_json_decimal(&balance["available"])?;
+        let on_hold = helpers::parse_json_decimal(&balance["onOrders"])?;
+        let total = available.checked_add(on_hold).map_err(|_| ErrorKind::BadParse)?;
+
+        balances.insert(utils::normalize_currency(currency),
+                        Balance {
+                            total: total,
+                            available: Some(available),
+                            on_hold: Some(on_hold),
+                        });
+    }
+
+    Ok(Balances {
+        timestamp: helpers::get_unix_timestamp_ms(),
+        balances: balances,
+    })
+}
+
+/// Return the id of the order placed from a buy or sell response.
+fn parse_order_id(raw_response: Map<String, Value>) -> Result<OrderId, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let order_number = result.get("orderNumber").ok_or(ErrorKind::BadParse)?;
+    Ok(OrderId(helpers::json_to_string(order_number)?))
+}
+
+/// Return the open orders of a returnOpenOrders response.
+fn parse_open_orders(raw_response: Map<String, Value>, pair: Pair) -> Result<Vec<Order>, Error> {
+    let result = utils::parse_result(raw_response)?;
+
+    let entries = result.get("data")
+        .and_then(|data| data.as_array())
+        .ok_or(ErrorKind::BadParse)?;
+    let mut orders = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let side = match entry["type"].as_str() {
+            Some("buy") => OrderSide::Buy,
+            Some("sell") => OrderSide::Sell,
+            _ => return Err(ErrorKind::BadParse.into()),
+        };
+        // "amount" is the remaining amount, "startingAmount" is not always given
+        let remaining = helpers::parse_json_decimal(&entry["amount"])?;
+        let (volume, filled) = match helpers::parse_json_decimal(&entry["startingAmount"]) {
+            Ok(starting) => {
+                let filled = starting.checked_sub(remaining).map_err(|_| ErrorKind::BadParse)?;
+                (starting, Some(filled))
+            }
+            Err(_) => (remaining, None),
+        };
+
+        orders.push(Order {
+            id: OrderId(helpers::json_to_string(&entry["orderNumber"])?),
+            pair: pair,
+            side: side,
+            order_type: Some(OrderType::Limit),
+            price: Some(helpers::parse_json_decimal(&entry["rate"])?),
+            volume: volume,
+            filled: filled,
+            status: OrderStatus::Open,
+        });
+    }
+
+    Ok(orders)
+}
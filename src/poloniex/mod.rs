@@ -1,5 +1,6 @@
 //! Use this module to interact with Poloniex exchange.
 
 pub mod api;
+pub mod error;
 pub mod generic_api;
 pub mod utils;
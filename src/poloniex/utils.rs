@@ -1,42 +1,158 @@
-use bidir_map::BidirMap;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha512;
+
+use rustc_serialize::hex::ToHex;
+
 use serde_json;
 use serde_json::Value;
 use serde_json::value::Map;
 
+use currency::Currency;
+use decimal::Decimal;
 use error;
+use exchange::Exchange;
 use pair::Pair;
-use pair::Pair::*;
-
-lazy_static! {
-    static ref PAIRS_STRING: BidirMap<Pair, &'static str> = {
-        let mut m = BidirMap::new();
-        m.insert(BTC_ETH, "BTC_ETH");
-        m.insert(BTC_ZEC, "BTC_ZEC");
-        m
-    };
+use rate_limiter::RateLimiter;
+use scope::Scope;
+use poloniex::error::PoloniexError;
+
+/// Root of the official REST API, used unless `ClientConfig::base_url` is set.
+pub const BASE_URL: &'static str = "https://poloniex.com";
+
+/// Number of decimals of the prices and amounts of every market.
+pub const DECIMALS: u32 = 8;
+
+/// Minimum total (price * amount) of an order, in quote currency.
+pub const MIN_ORDER_TOTAL: Decimal = Decimal::new(1, 4);
+
+/// Fee of the orders adding liquidity to the book for the lowest volume tier: 0.15 %.
+pub const MAKER_FEE: f64 = 0.0015;
+
+/// Fee of the orders taking liquidity from the book for the lowest volume tier: 0.25 %.
+pub const TAKER_FEE: f64 = 0.0025;
+
+/// The currencies Poloniex prices its markets in.
+const QUOTE_CURRENCIES: &'static [Currency] = &[Currency::BTC,
+                                                Currency::ETH,
+                                                Currency::XMR,
+                                                Currency::USDT];
+
+/// Return the name of the pair used by Poloniex: the quote currency comes first ("BTC_ETH" for
+/// ETH/BTC).
+/// If the quote currency is not one Poloniex prices its markets in, None is returned.
+pub fn get_pair_string(pair: &Pair) -> Option<String> {
+    if !QUOTE_CURRENCIES.contains(&pair.quote) {
+        return None;
+    }
+    Some(get_currency_string(&pair.quote) + "_" + &get_currency_string(&pair.base))
+}
+
+/// Return the Pair of a pair name used by Poloniex ("BTC_ETH" is ETH/BTC).
+/// If the name is not made of two valid currency codes, None is returned.
+pub fn get_pair(pair_name: &str) -> Option<Pair> {
+    let mut currencies = pair_name.split('_');
+    match (currencies.next(), currencies.next(), currencies.next()) {
+        (Some(quote), Some(base), None) => {
+            Some(Pair::new(normalize_currency(base).parse().ok()?,
+                           normalize_currency(quote).parse().ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Return the currency name used by Poloniex for the currency specified ("XLM" becomes "STR").
+/// This is the inverse of `normalize_currency`.
+pub fn get_currency_string(currency: &Currency) -> String {
+    match *currency {
+        Currency::XLM => "STR".to_string(),
+        other => other.code().to_string(),
+    }
+}
+
+/// Return the normalized currency code of a currency name used by Poloniex.
+/// Poloniex lists Stellar as STR instead of XLM.
+pub fn normalize_currency(currency: &str) -> String {
+    match currency {
+        "STR" => "XLM".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Return the Sign header of a private request: the hex HMAC-SHA512 of the POST data, keyed with
+/// the API secret.
+pub fn create_signature(api_secret: &str, post_data: &str) -> String {
+    let mut hmac = Hmac::new(Sha512::new(), api_secret.as_bytes());
+    hmac.input(post_data.as_bytes());
+    hmac.result().code().to_hex()
+}
+
+/// Return the default rate limiter of a client: 6 requests per second.
+pub fn rate_limiter() -> RateLimiter {
+    RateLimiter::new(6.0, 6.0)
 }
 
-/// Return the name associated to pair used by Poloniex
-/// If the Pair is not supported, None is returned.
-pub fn get_pair_string(pair: &Pair) -> Option<&&str> {
-    PAIRS_STRING.get_by_first(pair)
+/// Return true if the private command specified only reads the account, so it can be sent again
+/// safely.
+pub fn is_read_only(command: &str) -> bool {
+    match command {
+        "returnBalances" |
+        "returnCompleteBalances" |
+        "returnDepositAddresses" |
+        "returnDepositsWithdrawals" |
+        "returnOpenOrders" |
+        "returnTradeHistory" |
+        "returnOrderTrades" |
+        "returnFeeInfo" |
+        "returnAvailableAccountBalances" |
+        "returnTradableBalances" |
+        "returnMarginAccountSummary" |
+        "getMarginPosition" |
+        "returnOpenLoanOffers" |
+        "returnActiveLoans" |
+        "returnLendingHistory" => true,
+        _ => false,
+    }
+}
+
+/// Return the scope a client needs to send the private command specified: withdrawals and
+/// transfers between accounts need `Scope::Withdraw`, the commands which do not change anything
+/// `Scope::ReadOnly` and the other ones (orders, loans, margin positions) `Scope::Trade`.
+pub fn required_scope(command: &str) -> Scope {
+    match command {
+        "withdraw" | "transferBalance" => Scope::Withdraw,
+        command if is_read_only(command) => Scope::ReadOnly,
+        _ => Scope::Trade,
+    }
 }
 
-/// Return the Pair enum associated to the string used by Poloniex
-/// If the Pair is not supported, None is returned.
-pub fn get_pair_enum(pair: &str) -> Option<&Pair> {
-    PAIRS_STRING.get_by_second(&pair)
+/// Return true if a request which returned the result specified may succeed if sent again: the
+/// host could not be reached, answered with a server error, or returned an error meaning that it
+/// is overloaded or rate limiting the client.
+pub fn is_retryable(result: &Result<Map<String, Value>, error::Error>) -> bool {
+    match *result {
+        Ok(ref response) => {
+            parse_result(response.clone()).err().map_or(false, |e| e.is_retryable())
+        }
+        Err(ref e) => e.is_retryable(),
+    }
 }
 
 pub fn deserialize_json(json_string: String) -> Result<Map<String, Value>, error::Error> {
     let data: Value = match serde_json::from_str(&json_string) {
         Ok(data) => data,
-        Err(_) => return Err(error::Error::BadParse),
+        Err(e) => return Err(error::Error::new(error::ErrorKind::BadParse).with_source(e)),
     };
 
-    match data.as_object() {
-        Some(value) => Ok(value.clone()),
-        None => Err(error::Error::BadParse),
+    match data {
+        Value::Object(map) => Ok(map),
+        // Some methods return an array, it is wrapped in an object under the "data" key
+        Value::Array(_) => {
+            let mut map = Map::new();
+            map.insert("data".to_string(), data);
+            Ok(map)
+        }
+        _ => Err(error::ErrorKind::BadParse.into()),
     }
 }
 
@@ -45,12 +161,16 @@ pub fn deserialize_json(json_string: String) -> Result<Map<String, Value>, error
 /// else return the error string found in array
 pub fn parse_result(response: Map<String, Value>) -> Result<Map<String, Value>, error::Error> {
     let error_msg = match response.get("error") {
-        Some(error) => error.as_str().unwrap(),
-        None => return Ok(response.clone()),
+        Some(&Value::String(ref error)) => error.clone(),
+        Some(error) => error.to_string(),
+        None => return Ok(response),
     };
 
-    match error_msg.as_ref() {
-        "Invalid command." => Err(error::Error::InvalidArguments),
-        other => Err(error::Error::ExchangeSpecificError(other.to_string())),
-    }
+    let kind = PoloniexError::from_message(&error_msg).kind();
+    Err(exchange_error(kind).with_message(&error_msg))
+}
+
+/// Return an Error of the kind specified, raised by Poloniex.
+fn exchange_error(kind: error::ErrorKind) -> error::Error {
+    error::Error::new(kind).with_exchange(Exchange::Poloniex)
 }
@@ -0,0 +1,108 @@
+//! Use this module to throttle the requests sent to an exchange.
+//!
+//! A `RateLimiter` is a token bucket: it holds up to `capacity` tokens, refilled continuously at
+//! `per_second` tokens per second. Each request consumes tokens, and waits for the bucket to refill
+//! when it is empty. Clones share the same bucket, so a single limiter can be given to several
+//! clients (through `ClientConfig::rate_limiter`), possibly used from different threads, to stay
+//! under a limit enforced per IP address or per API key.
+//!
+//! Kraken's call counter for private methods is the same model seen the other way around: the
+//! counter is increased by each call (by 0, 1 or 2 depending on the method), decreased by
+//! 1 every 3 seconds, and the key is throttled when it reaches 15.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::config::ClientConfig;
+//! use coinnect::rate_limiter::RateLimiter;
+//!
+//! // Share the Poloniex limit (6 requests per second) between two clients
+//! let limiter = RateLimiter::new(6.0, 6.0);
+//! let config_1 = ClientConfig::new().rate_limiter(limiter.clone());
+//! let config_2 = ClientConfig::new().rate_limiter(limiter);
+//! ```
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// A token bucket rate limiter. Clones share the same bucket.
+#[derive(Clone)]
+pub struct RateLimiter {
+    capacity: f64,
+    per_second: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    /// Create a new full RateLimiter holding up to `capacity` tokens, refilled at `per_second`
+    /// tokens per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_second` is not positive: the bucket would never be refilled, and `acquire`
+    /// would block forever once it is empty.
+    pub fn new(capacity: f64, per_second: f64) -> RateLimiter {
+        assert!(per_second > 0.0, "The refill rate of a RateLimiter must be positive");
+        RateLimiter {
+            capacity: capacity,
+            per_second: per_second,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// Consume `cost` tokens, blocking the current thread until the bucket holds enough of them.
+    /// A cost greater than the capacity waits for a full bucket.
+    pub fn acquire(&self, cost: f64) {
+        while let Err(wait) = self.try_acquire(cost) {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Consume `cost` tokens if the bucket holds enough of them, else return how long to wait
+    /// before retrying.
+    pub fn try_acquire(&self, cost: f64) -> Result<(), Duration> {
+        let cost = cost.min(self.capacity);
+        let mut bucket = self.bucket.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill);
+        let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            return Ok(());
+        }
+        let wait = (cost - bucket.tokens) / self.per_second;
+        Err(Duration::new(wait as u64, (wait.fract() * 1e9) as u32 + 1))
+    }
+
+    /// Return the number of tokens currently available.
+    pub fn available(&self) -> f64 {
+        let bucket = self.bucket.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let elapsed = bucket.last_refill.elapsed();
+        let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        (bucket.tokens + elapsed * self.per_second).min(self.capacity)
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("capacity", &self.capacity)
+            .field("per_second", &self.per_second)
+            .finish()
+    }
+}
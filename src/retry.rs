@@ -0,0 +1,156 @@
+//! Use this module to retry the requests which failed temporarily.
+//!
+//! A `RetryPolicy` re-sends a request which failed because the exchange was unreachable,
+//! overloaded (HTTP 5xx, `EService:Unavailable`, ...) or rate limiting the client, waiting longer
+//! after each attempt: the delay doubles from `initial_delay` up to `max_delay`, and is randomized
+//! between half and all of it so that several clients do not retry in lockstep.
+//!
+//! The clients only retry public requests and private requests which do not change anything
+//! (balances, open orders, ...). Orders, cancellations, withdrawals and transfers are sent once:
+//! if the connection is lost, there is no way to know if the exchange processed them.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use coinnect::config::ClientConfig;
+//! use coinnect::retry::RetryPolicy;
+//!
+//! // Try up to 5 times, waiting up to 1, 2, 4 then 8 seconds between attempts
+//! let policy = RetryPolicy::new().max_attempts(5).initial_delay(Duration::from_secs(1));
+//! let config = ClientConfig::new().retry_policy(policy);
+//! ```
+
+use std::fmt;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use error::Error;
+
+/// How many times and how often a failed request is retried.
+#[derive(Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    /// Create a new RetryPolicy trying each request up to 3 times, waiting up to 0.5 then 1 second
+    /// between attempts.
+    pub fn new() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+
+    /// Create a new RetryPolicy sending each request once.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new().max_attempts(1)
+    }
+
+    /// Set the number of times a request is sent, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> RetryPolicy {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn initial_delay(mut self, delay: Duration) -> RetryPolicy {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Set the longest delay between two attempts.
+    pub fn max_delay(mut self, delay: Duration) -> RetryPolicy {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Randomize the delays between half and all of their value (the default), or not.
+    pub fn jitter(mut self, jitter: bool) -> RetryPolicy {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Return this policy for a request which only reads the account, or a policy sending the
+    /// request once for a request changing it (see the module documentation).
+    pub fn for_request(&self, read_only: bool) -> RetryPolicy {
+        if read_only {
+            self.clone()
+        } else {
+            RetryPolicy::none()
+        }
+    }
+
+    /// Return the delay to wait after the failed attempt specified (0 for the first attempt).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.min(31);
+        let delay = match self.initial_delay.checked_mul(factor) {
+            Some(delay) if delay < self.max_delay => delay,
+            _ => self.max_delay,
+        };
+        if !self.jitter {
+            return delay;
+        }
+
+        let millis = as_millis(delay);
+        let half = millis / 2;
+        Duration::from_millis(half + random() % (millis - half).saturating_add(1))
+    }
+
+    /// Call `call` until it succeeds, `is_retryable` returns false for its result or the maximum
+    /// number of attempts is reached, and return its last result.
+    pub fn run<T, F, R>(&self, mut call: F, is_retryable: R) -> Result<T, Error>
+        where F: FnMut() -> Result<T, Error>,
+              R: Fn(&Result<T, Error>) -> bool
+    {
+        let mut attempt = 0;
+        loop {
+            let result = call();
+            attempt += 1;
+            if attempt >= self.max_attempts || !is_retryable(&result) {
+                return result;
+            }
+            thread::sleep(self.delay(attempt - 1));
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::new()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_delay_ms", &as_millis(self.initial_delay))
+            .field("max_delay_ms", &as_millis(self.max_delay))
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+fn as_millis(duration: Duration) -> u64 {
+    // Saturates, a delay of 584 million years is as good as forever
+    duration.as_secs()
+        .saturating_mul(1000)
+        .saturating_add(duration.subsec_nanos() as u64 / 1_000_000)
+}
+
+/// Return a pseudo-random number, good enough to spread retries (splitmix64 of the clock).
+fn random() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+    let mut z = (now.as_secs() ^ now.subsec_nanos() as u64).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,63 @@
+//! Use this module to restrict what a client may do with its API key.
+//!
+//! A client is given a `Scope` at construction (see `ClientConfig::scope`) and checks it before
+//! sending any private request: calls beyond it fail with `ErrorKind::ScopeExceeded` and never
+//! reach the exchange. A service which only needs to read balances can thus be given a trading key
+//! without any risk of placing orders, and a trading bot cannot withdraw funds even if its code is
+//! wrong. The scope is enforced by the client only: restrict the permissions of the API key on
+//! the exchange as well when possible.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::config::ClientConfig;
+//! use coinnect::kraken::api::KrakenApi;
+//! use coinnect::scope::Scope;
+//! use coinnect::transport::MockTransport;
+//!
+//! let config = ClientConfig::new().scope(Scope::Trade);
+//! let mut api = KrakenApi::with_config("api_key", "c2VjcmV0", config, MockTransport::new());
+//! assert!(api.withdraw_funds("currency", "XBT", "key", "1.0").is_err());
+//! ```
+
+use error::{Error, ErrorKind};
+
+/// What a client is allowed to do. Each scope allows everything the previous ones allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    /// Read the account: balances, orders, trades, deposits, withdrawals...
+    ReadOnly,
+    /// Read the account, place and cancel orders.
+    Trade,
+    /// Everything, withdrawals and transfers included. This is the default.
+    Withdraw,
+}
+
+impl Scope {
+    /// Return true if the scope allows the calls which require the scope specified.
+    pub fn allows(&self, required: Scope) -> bool {
+        required <= *self
+    }
+
+    /// Return a ScopeExceeded error if the scope does not allow the calls which require the scope
+    /// specified.
+    pub fn check(&self, required: Scope) -> Result<(), Error> {
+        if self.allows(required) {
+            return Ok(());
+        }
+        let message = format!("The call requires the {:?} scope, the client is limited to {:?}",
+                              required,
+                              self);
+        Err(Error::new(ErrorKind::ScopeExceeded {
+                required: required,
+                allowed: *self,
+            })
+            .with_message(&message))
+    }
+}
+
+impl Default for Scope {
+    fn default() -> Scope {
+        Scope::Withdraw
+    }
+}
@@ -0,0 +1,258 @@
+//! Use this module to record the HTTP traffic of a client into a cassette file, and to replay it
+//! later without network. Recording real sessions once gives deterministic regression tests for
+//! the parsing of the exchanges responses.
+//!
+//! Credentials are never written to the cassette: API keys, signatures and nonces are redacted
+//! from the headers and the bodies of the requests before they are saved. The same redaction is
+//! applied to the requests received while replaying, so they match the recorded ones even though
+//! their nonces and signatures differ.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use std::path::PathBuf;
+//! use coinnect::kraken::api::KrakenApi;
+//! use coinnect::transport::{CassetteTransport, HttpsTransport};
+//!
+//! // Record a session against the real exchange...
+//! let path = PathBuf::from("tests/cassettes/kraken_ticker.json");
+//! let transport = CassetteTransport::record(path.clone(), HttpsTransport::new().unwrap());
+//! let mut api = KrakenApi::with_transport("api_key", "api_secret", transport);
+//! api.get_ticker_information("XXBTZEUR").unwrap();
+//!
+//! // ...and replay it without network.
+//! let transport = CassetteTransport::replay(path).unwrap();
+//! let mut api = KrakenApi::with_transport("", "", transport);
+//! api.get_ticker_information("XXBTZEUR").unwrap();
+//! ```
+
+use serde_json;
+use serde_json::Value;
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use error::{Error, ErrorKind};
+use transport::{Transport, HttpRequest, HttpResponse, Method};
+
+/// Headers holding credentials, compared case insensitively.
+const REDACTED_HEADERS: &'static [&'static str] = &["API-Key", "API-Sign", "Key", "Sign"];
+
+/// Query or body parameters holding credentials or values changing at each request.
+const REDACTED_PARAMS: &'static [&'static str] = &["key", "signature", "nonce", "otp"];
+
+const REDACTED: &'static str = "REDACTED";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Interaction {
+    request: HttpRequest,
+    response: HttpResponse,
+    replayed: bool,
+}
+
+#[derive(Debug)]
+enum Mode {
+    Record(Box<Transport>),
+    Replay,
+}
+
+/// A Transport recording the traffic of another Transport to a cassette file, or replaying a
+/// cassette file previously recorded.
+#[derive(Debug)]
+pub struct CassetteTransport {
+    mode: Mode,
+    path: PathBuf,
+    interactions: Vec<Interaction>,
+}
+
+impl CassetteTransport {
+    /// Send the requests through the transport specified and save every request/response pair
+    /// to the cassette file. The file is rewritten after each request.
+    pub fn record<T>(path: PathBuf, transport: T) -> CassetteTransport
+        where T: Transport + 'static
+    {
+        CassetteTransport {
+            mode: Mode::Record(Box::new(transport)),
+            path: path,
+            interactions: Vec::new(),
+        }
+    }
+
+    /// Answer the requests with the responses of the cassette file. Each recorded response is
+    /// served once, to the first request matching its recorded request. Requests not found in
+    /// the cassette fail with `ErrorKind::CassetteError`.
+    pub fn replay(path: PathBuf) -> Result<CassetteTransport, Error> {
+        let mut buffer = String::new();
+        File::open(&path)
+            .and_then(|mut f| f.read_to_string(&mut buffer))
+            .map_err(|e| {
+                let reason = format!("Could not read the cassette {}", path.display());
+                Error::new(ErrorKind::CassetteError(reason)).with_source(e)
+            })?;
+        let data: Value = serde_json::from_str(&buffer)
+            .map_err(|e| Error::new(ErrorKind::BadParse).with_source(e))?;
+        let entries = data.as_array().ok_or(ErrorKind::BadParse)?;
+
+        let mut interactions = Vec::with_capacity(entries.len());
+        for entry in entries {
+            interactions.push(parse_interaction(entry)?);
+        }
+
+        Ok(CassetteTransport {
+            mode: Mode::Replay,
+            path: path,
+            interactions: interactions,
+        })
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let entries: Vec<Value> = self.interactions.iter().map(interaction_to_json).collect();
+        let content = serde_json::to_string_pretty(&Value::Array(entries))
+            .map_err(|e| Error::new(ErrorKind::BadParse).with_source(e))?;
+
+        File::create(&self.path)
+            .and_then(|mut f| f.write_all(content.as_bytes()))
+            .map_err(|e| {
+                let reason = format!("Could not write the cassette {}", self.path.display());
+                Error::new(ErrorKind::CassetteError(reason)).with_source(e)
+            })
+    }
+}
+
+impl Transport for CassetteTransport {
+    fn send(&mut self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        let redacted = redact(request);
+
+        let response = match self.mode {
+            Mode::Record(ref mut transport) => transport.send(request)?,
+            Mode::Replay => {
+                let interaction = self.interactions
+                    .iter_mut()
+                    .find(|interaction| !interaction.replayed && interaction.request == redacted);
+                return match interaction {
+                    Some(interaction) => {
+                        interaction.replayed = true;
+                        Ok(interaction.response.clone())
+                    }
+                    None => {
+                        Err(ErrorKind::CassetteError(format!("Unexpected request: {} {}",
+                                                         method_to_str(redacted.method),
+                                                         redacted.url)).into())
+                    }
+                };
+            }
+        };
+
+        self.interactions.push(Interaction {
+            request: redacted,
+            response: response.clone(),
+            replayed: true,
+        });
+        self.save()?;
+        Ok(response)
+    }
+}
+
+/// Return a copy of the request without credentials. Parameters are sorted since their order is
+/// not deterministic.
+fn redact(request: &HttpRequest) -> HttpRequest {
+    let url = match request.url.find('?') {
+        Some(index) => {
+            request.url[..index + 1].to_string() + &redact_params(&request.url[index + 1..])
+        }
+        None => request.url.clone(),
+    };
+    let headers = request.headers
+        .iter()
+        .map(|&(ref name, ref value)| {
+            if REDACTED_HEADERS.iter().any(|header| header.eq_ignore_ascii_case(name)) {
+                (name.clone(), REDACTED.to_string())
+            } else {
+                (name.clone(), value.clone())
+            }
+        })
+        .collect();
+
+    HttpRequest {
+        method: request.method,
+        url: url,
+        headers: headers,
+        body: redact_params(&request.body),
+    }
+}
+
+fn redact_params(params: &str) -> String {
+    let mut params: Vec<String> = params.split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let name = param.split('=').next().unwrap_or("");
+            if REDACTED_PARAMS.contains(&name) {
+                name.to_string() + "=" + REDACTED
+            } else {
+                param.to_string()
+            }
+        })
+        .collect();
+    params.sort();
+    params.join("&")
+}
+
+fn method_to_str(method: Method) -> &'static str {
+    match method {
+        Method::Get => "GET",
+        Method::Post => "POST",
+    }
+}
+
+fn interaction_to_json(interaction: &Interaction) -> Value {
+    let headers: Vec<Value> = interaction.request
+        .headers
+        .iter()
+        .map(|&(ref name, ref value)| json!([name, value]))
+        .collect();
+
+    json!({
+        "request": {
+            "method": method_to_str(interaction.request.method),
+            "url": interaction.request.url,
+            "headers": headers,
+            "body": interaction.request.body,
+        },
+        "response": {
+            "status": interaction.response.status,
+            "body": interaction.response.body,
+        },
+    })
+}
+
+fn parse_interaction(entry: &Value) -> Result<Interaction, Error> {
+    let request = &entry["request"];
+    let response = &entry["response"];
+
+    let method = match request["method"].as_str() {
+        Some("GET") => Method::Get,
+        Some("POST") => Method::Post,
+        _ => return Err(ErrorKind::BadParse.into()),
+    };
+    let mut headers = Vec::new();
+    for header in request["headers"].as_array().ok_or(ErrorKind::BadParse)? {
+        let name = header[0].as_str().ok_or(ErrorKind::BadParse)?;
+        let value = header[1].as_str().ok_or(ErrorKind::BadParse)?;
+        headers.push((name.to_string(), value.to_string()));
+    }
+
+    Ok(Interaction {
+        request: HttpRequest {
+            method: method,
+            url: request["url"].as_str().ok_or(ErrorKind::BadParse)?.to_string(),
+            headers: headers,
+            body: request["body"].as_str().ok_or(ErrorKind::BadParse)?.to_string(),
+        },
+        response: HttpResponse {
+            status: response["status"].as_u64().ok_or(ErrorKind::BadParse)? as u16,
+            body: response["body"].as_str().ok_or(ErrorKind::BadParse)?.to_string(),
+        },
+        replayed: false,
+    })
+}
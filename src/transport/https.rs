@@ -0,0 +1,58 @@
+//! Use this module to send requests over HTTPS with hyper. This is the default transport of every
+//! exchange client.
+
+use hyper_native_tls::NativeTlsClient;
+use hyper::Client;
+use hyper::header::Headers;
+use hyper::net::HttpsConnector;
+
+use std::io::Read;
+
+use error::{Error, ErrorKind};
+use transport::{Transport, HttpRequest, HttpResponse, Method};
+
+#[derive(Debug)]
+pub struct HttpsTransport {
+    client: Client,
+}
+
+impl HttpsTransport {
+    /// Create a new HttpsTransport using the native TLS implementation of the platform.
+    pub fn new() -> Result<HttpsTransport, Error> {
+        let ssl = match NativeTlsClient::new() {
+            Ok(ssl) => ssl,
+            Err(e) => return Err(Error::new(ErrorKind::TlsError).with_source(e)),
+        };
+        let connector = HttpsConnector::new(ssl);
+
+        Ok(HttpsTransport { client: Client::with_connector(connector) })
+    }
+}
+
+impl Transport for HttpsTransport {
+    fn send(&mut self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        let mut headers = Headers::new();
+        for &(ref name, ref value) in &request.headers {
+            headers.set_raw(name.clone(), vec![value.clone().into_bytes()]);
+        }
+
+        let builder = match request.method {
+            Method::Get => self.client.get(&request.url),
+            Method::Post => self.client.post(&request.url).body(&request.body),
+        };
+        let mut response = match builder.headers(headers).send() {
+            Ok(response) => response,
+            Err(e) => return Err(Error::new(ErrorKind::ServiceUnavailable).with_source(e)),
+        };
+
+        let mut body = String::new();
+        if let Err(e) = response.read_to_string(&mut body) {
+            return Err(Error::new(ErrorKind::ServiceUnavailable).with_source(e));
+        }
+
+        Ok(HttpResponse {
+            status: response.status.to_u16(),
+            body: body,
+        })
+    }
+}
@@ -0,0 +1,107 @@
+//! Use this module to test the exchange clients without network. A MockTransport answers each
+//! request with the response registered for it and records every request it received.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::kraken::api::KrakenApi;
+//! use coinnect::transport::MockTransport;
+//!
+//! let transport = MockTransport::new();
+//! transport.respond("/0/public/Time",
+//!                   r#"{"error":[],"result":{"unixtime":1491919199,"rfc1123":""}}"#);
+//!
+//! let mut api = KrakenApi::with_transport("", "", transport.clone());
+//! assert!(api.get_server_time().unwrap().contains_key("result"));
+//! assert_eq!(transport.requests().len(), 1);
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use error::{Error, ErrorKind};
+use transport::{Transport, HttpRequest, HttpResponse};
+
+#[derive(Debug)]
+struct Route {
+    pattern: String,
+    response: HttpResponse,
+    once: bool,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    routes: Vec<Route>,
+    requests: Vec<HttpRequest>,
+}
+
+/// A Transport returning canned responses. Clones share the same routes and recorded requests, so
+/// a clone can be kept to inspect the requests sent by the client owning the transport.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTransport {
+    /// Create a new MockTransport without any route. Requests without route fail with
+    /// `ErrorKind::ServiceUnavailable`.
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    /// Answer the requests matching the pattern with a `200 OK` and the body specified.
+    /// See `respond_with`.
+    pub fn respond(&self, pattern: &str, body: &str) {
+        self.respond_with(pattern, HttpResponse::ok(body));
+    }
+
+    /// Answer the requests matching the pattern with the response specified. A request matches
+    /// if its URL or its body contains the pattern (the body is needed for exchanges sending every
+    /// private request to the same URL). The most recently registered route wins.
+    pub fn respond_with(&self, pattern: &str, response: HttpResponse) {
+        self.add_route(pattern, response, false);
+    }
+
+    /// Answer the next request matching the pattern with the response specified, then forget
+    /// it. Use it on top of another route to simulate a temporary failure.
+    pub fn respond_once_with(&self, pattern: &str, response: HttpResponse) {
+        self.add_route(pattern, response, true);
+    }
+
+    fn add_route(&self, pattern: &str, response: HttpResponse, once: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.routes.push(Route {
+            pattern: pattern.to_string(),
+            response: response,
+            once: once,
+        });
+    }
+
+    /// Return every request received so far, from the oldest to the most recent.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Return the most recent request received, if any.
+    pub fn last_request(&self) -> Option<HttpRequest> {
+        self.state.lock().unwrap().requests.last().cloned()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&mut self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        let mut state = self.state.lock().unwrap();
+        state.requests.push(request.clone());
+
+        let index = state.routes
+            .iter()
+            .rposition(|route| {
+                request.url.contains(route.pattern.as_str()) ||
+                request.body.contains(route.pattern.as_str())
+            });
+        match index {
+            Some(index) if state.routes[index].once => Ok(state.routes.remove(index).response),
+            Some(index) => Ok(state.routes[index].response.clone()),
+            None => Err(ErrorKind::ServiceUnavailable.into()),
+        }
+    }
+}
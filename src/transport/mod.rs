@@ -0,0 +1,89 @@
+//! This module contains the Transport trait used by every exchange client to send its requests.
+//! By default, clients send their requests over HTTPS with `HttpsTransport`, but any Transport can
+//! be given to the `with_transport` constructors, for example a `MockTransport` to test the
+//! clients without network or a `CassetteTransport` to record and replay real sessions.
+
+use std::fmt::Debug;
+
+use error::Error;
+
+pub mod cassette;
+pub mod https;
+pub mod mock;
+
+pub use self::cassette::CassetteTransport;
+pub use self::https::HttpsTransport;
+pub use self::mock::MockTransport;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpRequest {
+    /// Create a GET request without headers nor body.
+    pub fn get(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: Method::Get,
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    /// Create a POST request with the body specified.
+    pub fn post(url: &str, body: &str) -> HttpRequest {
+        HttpRequest {
+            method: Method::Post,
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Add a header to the request.
+    pub fn with_header(mut self, name: &str, value: &str) -> HttpRequest {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Return the value of the header specified, if any. Header names are case insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|&&(ref header, _)| header.eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| value.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Create a `200 OK` response with the body specified.
+    pub fn ok(body: &str) -> HttpResponse {
+        HttpResponse {
+            status: 200,
+            body: body.to_string(),
+        }
+    }
+}
+
+pub trait Transport: Debug + Send {
+    /// Send the request and return the response of the server. An error is only returned if no
+    /// response could be obtained: HTTP error statuses are returned as a response, since
+    /// exchanges usually describe the error in the body.
+    fn send(&mut self, request: &HttpRequest) -> Result<HttpResponse, Error>;
+}
@@ -1,9 +1,13 @@
 //! Types definition used for handling returned data when generic API is used.
 
+use std::collections::HashMap;
+use std::fmt;
+
+use decimal::{Decimal, MAX_SCALE};
 use pair::Pair;
 
-type Price = f64;
-type Volume = f64;
+pub type Price = Decimal;
+pub type Volume = Decimal;
 
 #[derive(Debug)]
 pub struct Ticker {
@@ -22,25 +26,218 @@ pub struct Ticker {
     pub volume: Option<Volume>,
 }
 
+#[derive(Debug)]
+pub struct Balance {
+    /// Total amount of the currency held on the account
+    pub total: Volume,
+    /// Amount which can be used to trade or withdraw, None if the exchange does not report it
+    pub available: Option<Volume>,
+    /// Amount reserved by open orders or pending withdrawals, None if the exchange does not
+    /// report it
+    pub on_hold: Option<Volume>,
+}
+
+#[derive(Debug)]
+pub struct Balances {
+    /// UNIX timestamp in ms (when the response was received)
+    pub timestamp: i64,
+    /// Balances keyed by normalized currency code ("BTC", "EUR", "DOGE", ...), whatever the
+    /// exchange naming is (Kraken uses "XXBT" and "ZEUR" for example)
+    pub balances: HashMap<String, Balance>,
+}
+
+impl Balances {
+    /// Return the Balance of the currency specified (normalized code, such as "BTC").
+    pub fn get(&self, currency: &str) -> Option<&Balance> {
+        self.balances.get(currency)
+    }
+}
+
+/// Side of an order or of a trade (from the taker point of view)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OrderType {
+    Limit,
+    Market,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OrderStatus {
+    /// The order is not in the orderbook yet
+    Pending,
+    /// The order is in the orderbook (it may be partially filled)
+    Open,
+    /// The order has been completely filled
+    Filled,
+    Canceled,
+    Expired,
+}
+
+/// Identifier of an order, as given by the exchange
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OrderId(pub String);
+
+impl fmt::Display for OrderId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// An order to be placed through the generic API
+#[derive(Debug, Clone)]
+pub struct OrderRequest {
+    pub pair: Pair,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    /// Limit price, ignored for market orders
+    pub price: Option<Price>,
+    /// Volume in base currency
+    pub volume: Volume,
+}
+
+impl OrderRequest {
+    /// Create a limit order request.
+    pub fn limit(pair: Pair, side: OrderSide, price: Price, volume: Volume) -> OrderRequest {
+        OrderRequest {
+            pair: pair,
+            side: side,
+            order_type: OrderType::Limit,
+            price: Some(price),
+            volume: volume,
+        }
+    }
+
+    /// Create a market order request.
+    pub fn market(pair: Pair, side: OrderSide, volume: Volume) -> OrderRequest {
+        OrderRequest {
+            pair: pair,
+            side: side,
+            order_type: OrderType::Market,
+            price: None,
+            volume: volume,
+        }
+    }
+}
+
+/// An order returned by the exchange
+#[derive(Debug)]
+pub struct Order {
+    pub id: OrderId,
+    pub pair: Pair,
+    pub side: OrderSide,
+    /// None if the order type has no generic equivalent (Kraken stop-loss orders for example)
+    pub order_type: Option<OrderType>,
+    /// Limit price, None for market orders
+    pub price: Option<Price>,
+    /// Volume of the order in base currency (the remaining volume if the exchange does not report
+    /// the filled volume)
+    pub volume: Volume,
+    /// Volume already executed, None if the exchange does not report it
+    pub filled: Option<Volume>,
+    pub status: OrderStatus,
+}
+
+#[derive(Debug)]
+pub struct Trade {
+    /// Trade identifier given by the exchange (Kraken does not provide one)
+    pub id: Option<String>,
+    /// UNIX timestamp in ms of the execution
+    pub timestamp: i64,
+    pub price: Price,
+    pub volume: Volume,
+    pub side: OrderSide,
+    /// Type of the order which triggered the trade, if the exchange provides it
+    pub order_type: Option<OrderType>,
+}
+
+#[derive(Debug)]
+pub struct TradeHistory {
+    /// UNIX timestamp in ms (when the response was received)
+    pub timestamp: i64,
+    /// The Pair corresponding to the trades returned
+    pub pair: Pair,
+    /// Trades sorted from the oldest to the most recent
+    pub trades: Vec<Trade>,
+    /// Cursor to give as `since` to retrieve only the trades that happened after this history.
+    /// None if no trade was returned and no cursor was given.
+    pub last: Option<String>,
+    /// True if `since` is older than the oldest trade returned by an exchange which only returns
+    /// its recent trades: the trades in between may have been missed.
+    pub gap: bool,
+}
+
 
-/*
+#[derive(Debug)]
 pub struct Orderbook {
-    timestamp: i64,
-    ask_offers: Vec<Price, Volume>,
-    bid_offers: Vec<Price, Volume>,
+    /// UNIX timestamp in ms (when the response was received)
+    pub timestamp: i64,
+    /// The Pair corresponding to the Orderbook returned (maybe useful later for asynchronous APIs)
+    pub pair: Pair,
+    /// Ask offers as (price, volume), sorted by ascending price (best ask first)
+    pub asks: Vec<(Price, Volume)>,
+    /// Bid offers as (price, volume), sorted by descending price (best bid first)
+    pub bids: Vec<(Price, Volume)>,
 }
 
 impl Orderbook {
-    pub fn get_timestamp(&self) -> i64 {
-        self.timestamp
+    /// Return the lowest ask offer, if any.
+    pub fn best_ask(&self) -> Option<&(Price, Volume)> {
+        self.asks.first()
+    }
+
+    /// Return the highest bid offer, if any.
+    pub fn best_bid(&self) -> Option<&(Price, Volume)> {
+        self.bids.first()
     }
+}
+
+/// A market listed by the exchange
+#[derive(Debug, Clone, PartialEq)]
+pub struct Market {
+    pub pair: Pair,
+    /// Name of the market used by the exchange ("XXBTZEUR", "BTC_ETH", "btceur", ...)
+    pub symbol: String,
+    /// Trading rules and fees of the market
+    pub info: MarketInfo,
+}
+
+/// Trading rules and fees of a market
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketInfo {
+    /// Number of decimals of the prices, in quote currency
+    pub price_decimals: u32,
+    /// Number of decimals of the volumes, in base currency
+    pub volume_decimals: u32,
+    /// Minimum volume of an order in base currency, None if the exchange does not report it
+    pub min_volume: Option<Volume>,
+    /// Minimum value (price * volume) of an order in quote currency, None if the exchange does
+    /// not report it
+    pub min_value: Option<Price>,
+    /// Leverages allowed for margin orders (2 for 2:1), empty if the market has no margin trading
+    pub leverage: Vec<u32>,
+    /// Fee of the orders adding liquidity to the book, as a fraction of their value (0.0016 for
+    /// 0.16 %), for the lowest volume tier
+    pub maker_fee: f64,
+    /// Fee of the orders taking liquidity from the book, as a fraction of their value, for the
+    /// lowest volume tier
+    pub taker_fee: f64,
+}
 
-    pub fn get_ask_offers(&self) -> Vec<Price, Volume> {
-        self.ask_offers
+impl MarketInfo {
+    /// Return the smallest price increment (0.01 for 2 price decimals), with at most
+    /// `MAX_SCALE` decimals.
+    pub fn tick_size(&self) -> Price {
+        Decimal::new(1, self.price_decimals.min(MAX_SCALE))
     }
 
-    pub fn get_bid_offers(&self) -> Vec<Price, Volume> {
-        self.bid_offers
+    /// Return the smallest volume increment (0.00000001 for 8 volume decimals), with at most
+    /// `MAX_SCALE` decimals.
+    pub fn lot_size(&self) -> Volume {
+        Decimal::new(1, self.volume_decimals.min(MAX_SCALE))
     }
 }
-*/
@@ -0,0 +1,249 @@
+//! Use this module to check the orders before they are sent to the exchange.
+//!
+//! Every order placed through the generic API goes through the `OrderValidator` of the client
+//! (see `ClientConfig::order_validator`) before any request is sent:
+//!
+//! - the volume must be positive, and so must the price of a limit order;
+//! - the pair must be listed in the markets of the exchange, which are retrieved if they are not
+//!   cached (see the `market` module): the price is rounded to the tick size of the market and
+//!   the volume to its lot size, and orders below the minimum volume or value of the market are
+//!   rejected;
+//! - if a price band is set, the price of a limit order must be within this fraction of the last
+//!   trade price of the `Ticker`, which is then retrieved before placing the order. It is also
+//!   retrieved to estimate the value of a market order if the market has a minimum value.
+//!
+//! Invalid orders fail with `ErrorKind::OrderRejected`, whose `Rejection` tells why.
+//!
+//! # Examples
+//!
+//! ```
+//! use coinnect::config::ClientConfig;
+//! use coinnect::validator::{OrderValidator, Rounding};
+//!
+//! // Round the volumes to the nearest lot, and reject the prices more than 5% away from the
+//! // last trade price
+//! let validator = OrderValidator::new().volume_rounding(Rounding::Nearest).price_band(0.05);
+//! let config = ClientConfig::new().order_validator(validator);
+//! ```
+
+use decimal::Decimal;
+use error::{Error, ErrorKind};
+use exchange::ExchangeApi;
+use types::{MarketInfo, OrderRequest, OrderSide, OrderType, Price, Ticker, Volume};
+
+/// How a price or a volume is rounded to the precision of the market.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    /// To the nearest multiple, half away from zero.
+    Nearest,
+    /// To the multiple below.
+    Down,
+    /// To the multiple above.
+    Up,
+    /// Prices of buy orders down and prices of sell orders up, so that the order never gets a
+    /// worse price than the one requested. Volumes down.
+    Passive,
+}
+
+impl Rounding {
+    /// Round the number specified to the number of decimals specified.
+    pub fn round(&self, number: Decimal, decimals: u32, side: OrderSide) -> Decimal {
+        match (*self, side) {
+            (Rounding::Nearest, _) => number.round_dp(decimals),
+            (Rounding::Down, _) |
+            (Rounding::Passive, OrderSide::Buy) => number.floor_dp(decimals),
+            (Rounding::Up, _) |
+            (Rounding::Passive, OrderSide::Sell) => number.ceil_dp(decimals),
+        }
+    }
+}
+
+/// Why an order was rejected before being sent to the exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    /// The limit order has no price.
+    MissingPrice,
+    /// The price is not positive, or is zero once rounded.
+    InvalidPrice(Price),
+    /// The volume is not positive.
+    InvalidVolume(Volume),
+    /// The volume, once rounded, is below the minimum volume of the market.
+    VolumeBelowMinimum { volume: Volume, minimum: Volume },
+    /// The value of the order (price * volume), once rounded, is below the minimum value of the
+    /// market.
+    ValueBelowMinimum { value: Price, minimum: Price },
+    /// The price is further from the last trade price than the price band allows.
+    PriceOutsideBand {
+        price: Price,
+        last_trade_price: Price,
+        band: f64,
+    },
+}
+
+impl Rejection {
+    fn message(&self) -> String {
+        match *self {
+            Rejection::MissingPrice => "A limit order requires a price".to_string(),
+            Rejection::InvalidPrice(price) => format!("Invalid price {}", price),
+            Rejection::InvalidVolume(volume) => format!("Invalid volume {}", volume),
+            Rejection::VolumeBelowMinimum { volume, minimum } => {
+                format!("The volume {} is below the minimum volume {}", volume, minimum)
+            }
+            Rejection::ValueBelowMinimum { value, minimum } => {
+                format!("The order value {} is below the minimum value {}", value, minimum)
+            }
+            Rejection::PriceOutsideBand { price, last_trade_price, band } => {
+                format!("The price {} is more than {}% away from the last trade price {}",
+                        price,
+                        band * 100.0,
+                        last_trade_price)
+            }
+        }
+    }
+}
+
+impl From<Rejection> for Error {
+    fn from(rejection: Rejection) -> Error {
+        let message = rejection.message();
+        Error::new(ErrorKind::OrderRejected(rejection)).with_message(&message)
+    }
+}
+
+/// Checks and rounds the orders of a client. By default, prices are rounded passively, volumes
+/// down, and no price band is enforced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderValidator {
+    price_rounding: Rounding,
+    volume_rounding: Rounding,
+    price_band: Option<f64>,
+}
+
+impl OrderValidator {
+    /// Create a new OrderValidator with the default settings.
+    pub fn new() -> OrderValidator {
+        OrderValidator {
+            price_rounding: Rounding::Passive,
+            volume_rounding: Rounding::Down,
+            price_band: None,
+        }
+    }
+
+    /// Round the prices to the tick size of the market as specified.
+    pub fn price_rounding(mut self, rounding: Rounding) -> OrderValidator {
+        self.price_rounding = rounding;
+        self
+    }
+
+    /// Round the volumes to the lot size of the market as specified.
+    pub fn volume_rounding(mut self, rounding: Rounding) -> OrderValidator {
+        self.volume_rounding = rounding;
+        self
+    }
+
+    /// Reject the limit orders whose price differs from the last trade price by more than the
+    /// fraction specified (0.1 for 10%). The Ticker is then retrieved before each limit order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `band` is negative, infinite or NaN: it would not bound the prices.
+    pub fn price_band(mut self, band: f64) -> OrderValidator {
+        assert!(band.is_finite() && band >= 0.0,
+                "The price band of an OrderValidator must be finite and not negative");
+        self.price_band = Some(band);
+        self
+    }
+
+    /// Check the order against the rules of its market, if they are known, and against the
+    /// Ticker retrieved from the exchange if a price band is set or if the market has a minimum
+    /// value and the order no price. Return the order rounded to the precision of the market.
+    pub fn check<A>(&self,
+                    api: &mut A,
+                    info: Option<&MarketInfo>,
+                    order: OrderRequest)
+                    -> Result<OrderRequest, Error>
+        where A: ExchangeApi + ?Sized
+    {
+        // The value of a market order is estimated from the last trade price
+        let min_value = info.and_then(|info| info.min_value).is_some();
+        let ticker = match (self.price_band, order.order_type) {
+            (Some(_), OrderType::Limit) => Some(api.ticker(order.pair)?),
+            _ if min_value && order.price.is_none() => Some(api.ticker(order.pair)?),
+            _ => None,
+        };
+        self.validate(order, info, ticker.as_ref())
+    }
+
+    /// Check the order against the market rules and the Ticker specified, without sending any
+    /// request. Return the order rounded to the precision of the market.
+    pub fn validate(&self,
+                    mut order: OrderRequest,
+                    info: Option<&MarketInfo>,
+                    ticker: Option<&Ticker>)
+                    -> Result<OrderRequest, Error> {
+        if order.volume <= Decimal::ZERO {
+            return Err(Rejection::InvalidVolume(order.volume).into());
+        }
+        if order.order_type == OrderType::Limit {
+            let price = order.price.ok_or(Rejection::MissingPrice)?;
+            if price <= Decimal::ZERO {
+                return Err(Rejection::InvalidPrice(price).into());
+            }
+        }
+
+        if let Some(info) = info {
+            if let Some(price) = order.price {
+                let rounded = self.price_rounding.round(price, info.price_decimals, order.side);
+                if rounded <= Decimal::ZERO {
+                    return Err(Rejection::InvalidPrice(price).into());
+                }
+                order.price = Some(rounded);
+            }
+            order.volume = self.volume_rounding
+                .round(order.volume, info.volume_decimals, order.side);
+
+            let min_volume = info.min_volume.unwrap_or(Decimal::ZERO).max(info.lot_size());
+            if order.volume < min_volume {
+                return Err(Rejection::VolumeBelowMinimum {
+                        volume: order.volume,
+                        minimum: min_volume,
+                    }
+                    .into());
+            }
+            // The value of a market order is estimated from the last trade price
+            let price = order.price.or_else(|| ticker.map(|ticker| ticker.last_trade_price));
+            if let (Some(minimum), Some(price)) = (info.min_value, price) {
+                let value = price.checked_mul(order.volume)?;
+                if value < minimum {
+                    return Err(Rejection::ValueBelowMinimum {
+                            value: value,
+                            minimum: minimum,
+                        }
+                        .into());
+                }
+            }
+        }
+
+        if let (Some(band), Some(price), Some(ticker)) = (self.price_band, order.price, ticker) {
+            let last_trade_price = ticker.last_trade_price;
+            let max_distance = Decimal::from_f64(band)
+                .unwrap_or(Decimal::ZERO)
+                .checked_mul(last_trade_price)?;
+            if price.checked_sub(last_trade_price)?.abs() > max_distance {
+                return Err(Rejection::PriceOutsideBand {
+                        price: price,
+                        last_trade_price: last_trade_price,
+                        band: band,
+                    }
+                    .into());
+            }
+        }
+
+        Ok(order)
+    }
+}
+
+impl Default for OrderValidator {
+    fn default() -> OrderValidator {
+        OrderValidator::new()
+    }
+}
@@ -3,101 +3,147 @@ mod bitstamp_tests {
     extern crate coinnect;
     use self::coinnect::bitstamp::utils;
     use self::coinnect::bitstamp::api::BitstampApi;
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::error::ErrorKind;
 
     use self::coinnect::exchange::ExchangeApi;
     use self::coinnect::pair::Pair;
+    use self::coinnect::types::{OrderRequest, OrderSide};
+    use self::coinnect::transport::{MockTransport, Method};
 
     use std::collections::HashMap;
 
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    fn mock_api() -> BitstampApi {
+        let transport = MockTransport::new();
+        transport.respond("/ticker/btcusd/",
+                          r#"{"high": "1186.00", "last": "1177.57", "timestamp": "1491919199",
+                          "bid": "1177.57", "vwap": "1169.89", "volume": "7127.60468436",
+                          "low": "1156.10", "ask": "1179.69", "open": "1165.24"}"#);
+        transport.respond("/order_book/",
+                          r#"{"timestamp": "1491919199",
+                          "bids": [["1177.57", "0.10000000"], ["1177.00", "2.00000000"]],
+                          "asks": [["1179.69", "1.20000000"], ["1180.00", "0.56300000"]]}"#);
+        transport.respond("/transactions/btcusd/",
+                          r#"[{"date": "1491919199", "tid": "13526279", "price": "1170.00",
+                          "type": "0", "amount": "0.10000000"},
+                          {"date": "1491919186", "tid": "13526278", "price": "1169.99",
+                          "type": "1", "amount": "1.20000000"}]"#);
+        transport.respond("/api/eur_usd/", r#"{"sell": "1.0548", "buy": "1.0624"}"#);
+
+        let params = HashMap::new();
+        BitstampApi::with_transport(&params, transport)
+    }
+
     #[test]
     fn build_url_should_return_the_a_url() {
-        assert_eq!(utils::build_url("ticker", "btcusd"),
+        assert_eq!(utils::build_url(utils::BASE_URL, "ticker", "btcusd"),
                    "https://www.bitstamp.net/api/v2/ticker/btcusd/");
     }
     #[test]
     fn build_url_should_return_the_url_for_transactions_for_btc_usd() {
-        assert_eq!(utils::build_url("transactions", "btcusd"),
+        assert_eq!(utils::build_url(utils::BASE_URL, "transactions", "btcusd"),
                    "https://www.bitstamp.net/api/v2/transactions/btcusd/");
     }
 
+    #[test]
+    fn build_url_v1_should_return_the_legacy_url() {
+        assert_eq!(utils::build_url_v1(utils::BASE_URL, "bitcoin_deposit_address"),
+                   "https://www.bitstamp.net/api/bitcoin_deposit_address/");
+    }
+
+    #[test]
+    fn get_pair_string_should_map_both_ways() {
+        assert_eq!(utils::get_pair_string(&Pair::ETH_EUR), Some("etheur".to_string()));
+        assert_eq!(utils::get_pair("xrpbtc"), Some(Pair::XRP_BTC));
+        assert_eq!(utils::get_pair("usdtusd"), Some(Pair::USDT_USD));
+        assert_eq!(utils::get_pair("x-rpbtc"), None);
+        assert_eq!(utils::get_pair_string(&Pair::DOGE_BTC.inverse()), None);
+    }
+
+    #[test]
+    fn unsupported_pair_should_return_an_error() {
+        let mut api = mock_api();
+        assert!(api.return_ticker(Pair::DOGE_BTC.inverse()).is_err());
+    }
+
     #[test]
     fn can_get_real_bitstamp_tick() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.ticker(Pair::BTC_USD);
         assert_eq!(result.is_ok(), true);
     }
 
     #[test]
     fn ticker_should_have_the_correct_last() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.ticker(Pair::BTC_USD);
-        assert!(result.unwrap().last_trade_price != 0.0);
+        assert_eq!(result.unwrap().last_trade_price, dec("1177.57"));
     }
     #[test]
     fn ticker_should_have_the_correct_high() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.ticker(Pair::BTC_USD);
-        assert!(result.unwrap().highest_bid != 0.0);
+        assert_eq!(result.unwrap().highest_bid, dec("1177.57"));
     }
     #[test]
     fn ticker_should_have_the_correct_low() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.ticker(Pair::BTC_USD);
-        assert!(result.unwrap().lowest_ask != 0.0);
+        assert_eq!(result.unwrap().lowest_ask, dec("1179.69"));
     }
     #[test]
     fn ticker_should_have_the_correct_volume() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.ticker(Pair::BTC_USD);
-        assert!(result.unwrap().volume.unwrap() != 0.0);
+        assert_eq!(result.unwrap().volume, Some(dec("7127.60468436")));
     }
 
     #[test]
     fn should_return_an_order_book() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.return_order_book(Pair::BTC_USD);
         assert_eq!(result.is_ok(), true);
     }
 
     #[test]
     fn order_book_should_have_a_timestamp() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.return_order_book(Pair::BTC_USD);
         assert!(result.unwrap().contains_key("timestamp"));
     }
     #[test]
     fn order_book_should_have_bids() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.return_order_book(Pair::BTC_USD);
         assert!(result.unwrap().contains_key("bids"));
     }
     #[test]
     fn order_book_should_have_asks() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         let result = api.return_order_book(Pair::BTC_USD);
         assert!(result.unwrap().contains_key("bids"));
     }
 
     #[test]
     fn order_book_should_have_asks_for_btcusd() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
+        let mut api = mock_api();
         assert!(api.return_order_book(Pair::BTC_USD).unwrap().contains_key("asks"));
     }
     #[test]
     fn order_book_should_have_asks_for_btceur() {
-        let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
-        assert!(api.return_order_book(Pair::BTC_USD).unwrap().contains_key("asks"));
+        let mut api = mock_api();
+        assert!(api.return_order_book(Pair::BTC_EUR).unwrap().contains_key("asks"));
+    }
+
+    #[test]
+    fn should_return_the_eur_usd_conversion_rate() {
+        let mut api = mock_api();
+        assert!(api.return_eur_usd_conversion_rate().unwrap().contains_key("sell"));
     }
 
     #[test]
@@ -110,23 +156,150 @@ mod bitstamp_tests {
     }
     #[test]
     fn should_create_a_correct_signature() {
-        let nonce = "1483228800".to_string();
-        let customer_id = "123456".to_string();
-        let api_key = "1234567890ABCDEF1234567890ABCDEF".to_string();
-        let api_secret = "1234567890ABCDEF1234567890ABCDEF".to_string();
+        let nonce = "1483228800";
+        let customer_id = "123456";
+        let api_key = "1234567890ABCDEF1234567890ABCDEF";
+        let api_secret = "1234567890ABCDEF1234567890ABCDEF";
         let expected_signature = "7D7C4168D49CBC2620A45EF00EAA228C1287561F1C1F94172272E1231A8ADF6B"
             .to_string();
         assert_eq!(utils::build_signature(nonce, customer_id, api_key, api_secret),
                    expected_signature);
     }
 
+    #[test]
+    fn parse_result_should_return_the_error_reason() {
+        let v1 = utils::deserialize_json(r#"{"error": "Invalid nonce"}"#.to_string()).unwrap();
+        let v2 = utils::deserialize_json(r#"{"status": "error", "reason": "Invalid order"}"#
+                .to_string())
+            .unwrap();
+        let ok = utils::deserialize_json(r#"{"id": "2253465"}"#.to_string()).unwrap();
+
+        let err = utils::parse_result(v1).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidNonce);
+        assert_eq!(err.message(), Some("Invalid nonce"));
+        assert_eq!(format!("{}", utils::parse_result(v2).unwrap_err()), "Invalid order (Bitstamp)");
+        assert!(utils::parse_result(ok).is_ok());
+    }
+
     #[test]
     fn should_return_the_trade_history_for_btc_usd() {
+        let mut api = mock_api();
+        let result = api.return_trade_history(Pair::BTC_USD, "");
+
+        assert!(result.unwrap()["data"].is_array());
+    }
+
+    #[test]
+    fn trades_should_be_sorted_and_return_a_cursor() {
+        let mut api = mock_api();
+        let history = api.trades(Pair::BTC_USD, None).unwrap();
+
+        assert_eq!(history.trades.len(), 2);
+        assert!(history.trades.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        assert_eq!(history.last, Some("13526279".to_string()));
+
+        let next = api.trades(Pair::BTC_USD, Some("13526278")).unwrap();
+        assert_eq!(next.trades.len(), 1);
+        assert!(!next.gap);
+    }
+
+    #[test]
+    fn trades_should_flag_a_since_older_than_the_trades_returned() {
+        let transport = MockTransport::new();
+        transport.respond("/transactions/btcusd/",
+                          r#"[{"date": "1491919199", "tid": "13526279", "price": "1170.00",
+                          "type": "0", "amount": "0.10000000"}]"#);
+        let mut api = BitstampApi::with_transport(&HashMap::new(), transport.clone());
+
+        let history = api.trades(Pair::BTC_USD, Some("13526000")).unwrap();
+        assert_eq!(history.trades.len(), 1);
+        assert!(history.gap);
+        // The trades of the last day are asked for to reach back to `since`
+        let requests = transport.requests();
+        assert!(requests.iter().any(|r| r.url.ends_with("/transactions/btcusd/?time=day")));
+
+        assert!(!api.trades(Pair::BTC_USD, None).unwrap().gap);
+    }
+
+    #[test]
+    fn private_requests_should_be_signed_and_posted() {
+        let transport = MockTransport::new();
+        transport.respond("/api/v2/buy/btcusd/", r#"{"id": "2253465", "price": "1170.00"}"#);
+        transport.respond("/api/v2/trading-pairs-info/",
+                          r#"[{"name": "BTC/USD", "url_symbol": "btcusd", "base_decimals": 8,
+                          "counter_decimals": 2, "minimum_order": "5.0 USD",
+                          "trading": "Enabled"}]"#);
+
+        let mut params = HashMap::new();
+        params.insert("api_key", "1234567890ABCDEF1234567890ABCDEF");
+        params.insert("api_secret", "1234567890ABCDEF1234567890ABCDEF");
+        params.insert("customer_id", "123456");
+        let mut api = BitstampApi::with_transport(&params, transport.clone());
+        let order = OrderRequest::limit(Pair::BTC_USD, OrderSide::Buy, dec("1170.0"), dec("0.1"));
+        let id = api.place_order(order);
+
+        assert_eq!(id.unwrap().0, "2253465");
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.method, Method::Post);
+        assert!(request.body.contains("key=1234567890ABCDEF1234567890ABCDEF"));
+        assert!(request.body.contains("signature="));
+        assert!(request.body.contains("price=1170"));
+        assert!(!request.body.contains("method="));
+    }
+
+    #[test]
+    fn requests_should_be_sent_to_the_configured_base_url() {
+        let transport = MockTransport::new();
+        transport.respond("/api/", "{}");
+        let config = ClientConfig::new().base_url("https://sandbox.example.com");
+        let mut api = BitstampApi::with_config(&HashMap::new(), config, transport.clone());
+        api.return_ticker(Pair::BTC_USD).unwrap();
+        api.return_eur_usd_conversion_rate().unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].url, "https://sandbox.example.com/api/v2/trading-pairs-info/");
+        assert_eq!(requests[1].url, "https://sandbox.example.com/api/v2/ticker/btcusd/");
+        assert_eq!(requests[2].url, "https://sandbox.example.com/api/eur_usd/");
+    }
+
+    #[test]
+    fn malformed_responses_should_return_an_error() {
+        let transport = MockTransport::new();
+        transport.respond("/ticker/btcusd/", r#"{"last": "1177.57"}"#);
+        transport.respond("/ticker/btceur/", r#"{"status": "error", "reason": "Maintenance"}"#);
+        let mut api = BitstampApi::with_transport(&HashMap::new(), transport);
+
+        assert_eq!(api.ticker(Pair::BTC_USD).unwrap_err().kind(), &ErrorKind::BadParse);
+        assert_eq!(api.ticker(Pair::BTC_EUR).unwrap_err().kind(),
+                   &ErrorKind::ExchangeSpecificError("Maintenance".to_string()));
+
+        let transport = MockTransport::new();
+        transport.respond("/order_book/", r#"{"status": "error", "reason": "Maintenance"}"#);
+        transport.respond("/transactions/", r#"{"error": "Maintenance"}"#);
+        transport.respond("/balance/", r#"{"btc_balance": "1.0", "btc_available": "abc"}"#);
+        let mut api = BitstampApi::with_transport(&HashMap::new(), transport);
+        assert_eq!(api.orderbook(Pair::BTC_USD).unwrap_err().kind(),
+                   &ErrorKind::ExchangeSpecificError("Maintenance".to_string()));
+        assert_eq!(api.trades(Pair::BTC_USD, None).unwrap_err().kind(),
+                   &ErrorKind::ExchangeSpecificError("Maintenance".to_string()));
+        assert_eq!(api.balances().unwrap_err().kind(), &ErrorKind::BadParse);
+    }
+
+    #[test]
+    fn generic_balances_should_parse_every_currency() {
+        let transport = MockTransport::new();
+        transport.respond("/api/v2/balance/",
+                          r#"{"btc_available": "0.50000000", "btc_balance": "1.00000000",
+                          "btc_reserved": "0.50000000", "usd_available": "100.00",
+                          "usd_balance": "100.00", "usd_reserved": "0.00", "btcusd_fee": "0.25"}"#);
+
         let params = HashMap::new();
-        let mut api = BitstampApi::new(&params);
-        let result = api.return_trade_history(Pair::BTC_USD);
+        let mut api = BitstampApi::with_transport(&params, transport);
+        let balances = api.balances().unwrap();
 
-        assert_eq!(result.is_ok(), false);
+        assert_eq!(balances.get("BTC").unwrap().total, dec("1.0"));
+        assert_eq!(balances.get("BTC").unwrap().on_hold, Some(dec("0.5")));
+        assert_eq!(balances.get("USD").unwrap().available, Some(dec("100.0")));
     }
 
     // IMPORTANT: Real keys are needed in order to retrieve the balance
@@ -135,7 +308,7 @@ mod bitstamp_tests {
     fn balance_should_have_usd_btc_fee() {
         use std::path::PathBuf;
         let path = PathBuf::from("./keys_real.json");
-        let mut api = BitstampApi::new_from_file("account_bitstamp", path);
+        let mut api = BitstampApi::new_from_file("account_bitstamp", path).unwrap();
         let result = api.return_balances(Pair::BTC_USD).unwrap();
         let result_looking_for_usd = result.clone();
         let result_looking_for_btc = result.clone();
@@ -145,4 +318,16 @@ mod bitstamp_tests {
         assert!(result_looking_for_btc.contains_key("btc_balance"));
         assert!(result_looking_for_fee.contains_key("fee"));
     }
+
+    // IMPORTANT: Real keys are needed in order to retrieve the open orders
+    #[test]
+    #[cfg_attr(not(feature = "bitstamp_private_tests"), ignore)]
+    fn all_open_orders_should_return_an_array() {
+        use std::path::PathBuf;
+        let path = PathBuf::from("./keys_real.json");
+        let mut api = BitstampApi::new_from_file("account_bitstamp", path).unwrap();
+        let result = api.return_all_open_orders().unwrap();
+
+        assert!(result["data"].is_array());
+    }
 }
@@ -4,21 +4,39 @@ mod coinnect_tests {
     extern crate coinnect;
 
     use self::coinnect::coinnect::Coinnect;
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::decimal::Decimal;
     use self::coinnect::exchange::{Exchange, ExchangeApi};
     use self::coinnect::pair::Pair;
+    use self::coinnect::transport::MockTransport;
+    use self::coinnect::types::OrderSide;
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
 
     #[test]
     fn can_create_new_api_connection_to_bitstamp() {
         let api: Box<ExchangeApi> = Coinnect::new(Exchange::Bitstamp,
                                                   "bs_api_key",
                                                   "bs_api_secret",
-                                                  Some("bs_cust_id"));
+                                                  Some("bs_cust_id"))
+            .unwrap();
 
         assert_eq!(format!("{:?}", api),
-                   "BitstampApi { last_request: 0, api_key: \"bs_api_key\", api_secret: \
-                    \"bs_api_secret\", customer_id: \"bs_cust_id\", http_client: Client { \
-                    redirect_policy: FollowAll, read_timeout: None, write_timeout: None, proxy: \
-                    None } }");
+                   "BitstampApi { credentials: Credentials { api_key: Secret(***), \
+                    api_secret: Secret(***) }, customer_id: \"bs_cust_id\", \
+                    base_url: \"https://www.bitstamp.net\", \
+                    rate_limiter: RateLimiter { capacity: 10.0, per_second: 1.0 }, \
+                    private_rate_limiter: RateLimiter { capacity: 10.0, per_second: 1.0 }, \
+                    retry_policy: RetryPolicy { max_attempts: 3, initial_delay_ms: 500, \
+                    max_delay_ms: 30000, jitter: true }, \
+                    nonce_generator: NonceGenerator { path: None }, scope: Withdraw, \
+                    market_cache: MarketCache { ttl: 3600s }, \
+                    order_validator: OrderValidator { price_rounding: Passive, \
+                    volume_rounding: Down, price_band: None }, last_response: None, \
+                    transport: HttpsTransport { client: Client { redirect_policy: FollowAll, \
+                    read_timeout: None, write_timeout: None, proxy: None } } }");
     }
     #[test]
     fn can_create_new_api_connection_to_kraken() {
@@ -31,47 +49,193 @@ mod coinnect_tests {
         //        assert_eq!(api, Exchange::Poloniex);
     }
 
+    fn mock_bitstamp() -> MockTransport {
+        let transport = MockTransport::new();
+        transport.respond("/ticker/btcusd/",
+                          r#"{"high": "1186.00", "last": "1177.57", "timestamp": "1491919199",
+                          "bid": "1177.57", "vwap": "1169.89", "volume": "7127.60468436",
+                          "low": "1156.10", "ask": "1179.69", "open": "1165.24"}"#);
+        transport.respond("/order_book/btcusd/",
+                          r#"{"timestamp": "1491919199",
+                          "bids": [["1177.00", "2.00000000"], ["1177.57", "0.10000000"]],
+                          "asks": [["1180.00", "0.56300000"], ["1179.69", "1.20000000"]]}"#);
+        transport.respond("/transactions/btcusd/",
+                          r#"[{"date": "1491919199", "tid": "13526279", "price": "1170.00",
+                          "type": "0", "amount": "0.10000000"},
+                          {"date": "1491919186", "tid": "13526278", "price": "1169.99",
+                          "type": "1", "amount": "1.20000000"}]"#);
+        transport
+    }
+
+    fn mock_kraken() -> MockTransport {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Ticker",
+                          r#"{"error": [], "result": {"XXBTZEUR": {
+                          "a": ["1120.00000", "1", "1.000"], "b": ["1119.00000", "2", "2.000"],
+                          "c": ["1119.50000", "0.10000000"], "v": ["100.5", "2500.25"]}}}"#);
+        transport.respond("/0/public/Depth",
+                          r#"{"error": [], "result": {"XXBTZEUR": {
+                          "asks": [["1120.00000", "1.000", 1491919199]],
+                          "bids": [["1119.00000", "2.000", 1491919199]]}}}"#);
+        transport.respond("/0/public/Trades",
+                          r#"{"error": [], "result": {"XXBTZEUR": [
+                          ["1119.50000", "0.10000000", 1491919186.1234, "b", "l", ""],
+                          ["1119.00000", "1.20000000", 1491919199.5678, "s", "m", ""]],
+                          "last": "1491919199567812345"}}"#);
+        transport
+    }
+
+    fn mock_poloniex() -> MockTransport {
+        let transport = MockTransport::new();
+        transport.respond("command=returnTicker",
+                          r#"{"BTC_ETH": {"last": "0.0251", "lowestAsk": "0.02589999",
+                          "highestBid": "0.0251", "percentChange": "0.02390438",
+                          "baseVolume": "6.16485315", "quoteVolume": "245.82513926"}}"#);
+        transport.respond("command=returnOrderBook",
+                          r#"{"asks": [["0.02589999", 1.5], ["0.026", 10]],
+                          "bids": [["0.0251", 2], ["0.025", 0.5]], "isFrozen": 0, "seq": 1}"#);
+        transport.respond("command=returnTradeHistory",
+                          r#"[{"date": "2017-04-11 14:21:07", "type": "buy", "rate": "0.0251",
+                          "amount": "0.5", "total": "0.01255", "tradeID": "2500"},
+                          {"date": "2017-04-11 14:20:52", "type": "sell", "rate": "0.025",
+                          "amount": "1.2", "total": "0.03", "tradeID": "2499"}]"#);
+        transport
+    }
+
     #[test]
     fn coinnect_can_get_a_ticker_from_bitstamp() {
-        let mut api = Coinnect::new(Exchange::Bitstamp,
-                                    "bs_api_key",
-                                    "bs_api_secret",
-                                    Some("bs_cust_id"));
+        let mut api = Coinnect::with_transport(Exchange::Bitstamp,
+                                               "bs_api_key",
+                                               "bs_api_secret",
+                                               Some("bs_cust_id"),
+                                               mock_bitstamp());
         let ticker = api.ticker(Pair::BTC_USD);
 
-        assert!(ticker.unwrap().last_trade_price != 0.0);
+        assert_eq!(ticker.unwrap().last_trade_price, dec("1177.57"));
     }
 
     #[test]
     fn coinnect_can_get_a_ticker_from_kraken() {
-        let mut api = Coinnect::new(Exchange::Kraken, "api_key", "api_secret", None);
-        let ticker = api.ticker(Pair::BTC_EUR);
+        let mut api = Coinnect::with_transport(Exchange::Kraken,
+                                               "api_key",
+                                               "api_secret",
+                                               None,
+                                               mock_kraken());
+        let ticker = api.ticker(Pair::BTC_EUR).unwrap();
 
-        assert!(ticker.unwrap().last_trade_price != 0.0);
+        assert_eq!(ticker.last_trade_price, dec("1119.5"));
+        assert_eq!(ticker.volume, Some(dec("2500.25")));
     }
 
     #[test]
     fn coinnect_can_get_a_ticker_from_poloniex() {
-        let mut api = Coinnect::new(Exchange::Poloniex, "api_key", "api_secret", None);
-        let ticker = api.ticker(Pair::BTC_ETH);
+        let mut api = Coinnect::with_transport(Exchange::Poloniex,
+                                               "api_key",
+                                               "api_secret",
+                                               None,
+                                               mock_poloniex());
+        let ticker = api.ticker(Pair::ETH_BTC).unwrap();
+
+        assert_eq!(ticker.last_trade_price, dec("0.0251"));
+        assert_eq!(ticker.lowest_ask, dec("0.02589999"));
+    }
+
+    #[test]
+    fn coinnect_can_get_an_orderbook_from_bitstamp() {
+        let mut api = Coinnect::with_transport(Exchange::Bitstamp, "", "", None, mock_bitstamp());
+        let orderbook = api.orderbook(Pair::BTC_USD).unwrap();
+
+        assert_eq!(orderbook.best_ask(), Some(&(dec("1179.69"), dec("1.2"))));
+        assert_eq!(orderbook.best_bid(), Some(&(dec("1177.57"), dec("0.1"))));
+    }
+
+    #[test]
+    fn coinnect_can_get_an_orderbook_from_kraken() {
+        let mut api = Coinnect::with_transport(Exchange::Kraken, "", "", None, mock_kraken());
+        let orderbook = api.orderbook(Pair::BTC_EUR).unwrap();
+
+        assert_eq!(orderbook.best_ask(), Some(&(dec("1120.0"), dec("1.0"))));
+        assert_eq!(orderbook.best_bid(), Some(&(dec("1119.0"), dec("2.0"))));
+    }
+
+    #[test]
+    fn coinnect_can_get_an_orderbook_from_poloniex() {
+        let mut api = Coinnect::with_transport(Exchange::Poloniex, "", "", None, mock_poloniex());
+        let orderbook = api.orderbook(Pair::ETH_BTC).unwrap();
+
+        assert_eq!(orderbook.best_ask(), Some(&(dec("0.02589999"), dec("1.5"))));
+        assert_eq!(orderbook.best_bid(), Some(&(dec("0.0251"), dec("2.0"))));
+    }
+
+    #[test]
+    fn coinnect_can_get_trades_from_bitstamp() {
+        let mut api = Coinnect::with_transport(Exchange::Bitstamp, "", "", None, mock_bitstamp());
+        let history = api.trades(Pair::BTC_USD, None).unwrap();
+
+        assert_eq!(history.trades.len(), 2);
+        assert_eq!(history.trades[0].timestamp, 1491919186000);
+        assert_eq!(history.last, Some("13526279".to_string()));
+
+        let next = api.trades(Pair::BTC_USD, history.last.as_ref().map(|s| s.as_ref())).unwrap();
+        assert!(next.trades.is_empty());
+    }
+
+    #[test]
+    fn coinnect_can_get_trades_from_kraken() {
+        let mut api = Coinnect::with_transport(Exchange::Kraken, "", "", None, mock_kraken());
+        let history = api.trades(Pair::BTC_EUR, None).unwrap();
+
+        assert_eq!(history.trades.len(), 2);
+        assert_eq!(history.trades[1].side, OrderSide::Sell);
+        assert_eq!(history.last, Some("1491919199567812345".to_string()));
+    }
+
+    #[test]
+    fn coinnect_can_get_trades_from_poloniex() {
+        let mut api = Coinnect::with_transport(Exchange::Poloniex, "", "", None, mock_poloniex());
+        let history = api.trades(Pair::ETH_BTC, None).unwrap();
+
+        assert_eq!(history.trades.len(), 2);
+        assert_eq!(history.trades[0].id, Some("2499".to_string()));
+        assert_eq!(history.trades[0].timestamp, 1491920452000);
+    }
+
+    #[test]
+    fn coinnect_should_return_an_error_when_the_exchange_is_unreachable() {
+        let transport = MockTransport::new();
+        let mut api = Coinnect::with_transport(Exchange::Kraken, "", "", None, transport);
+
+        assert!(api.ticker(Pair::BTC_EUR).is_err());
+    }
+
+    #[test]
+    fn coinnect_should_send_requests_to_the_configured_base_url() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Ticker", "{}");
+        let config = ClientConfig::new().base_url("http://localhost:8080");
+        let mut api = Coinnect::with_config(Exchange::Kraken,
+                                            "",
+                                            "",
+                                            None,
+                                            config,
+                                            transport.clone());
+        let _ = api.ticker(Pair::BTC_EUR);
 
-        assert!(ticker.unwrap().last_trade_price != 0.0);
+        let request = transport.last_request().unwrap();
+        assert!(request.url.starts_with("http://localhost:8080/0/public/Ticker?"));
     }
 
     // IMPORTANT: Real keys are needed in order to retrieve the balance
     #[test]
     #[cfg_attr(not(feature = "bitstamp_private_tests"), ignore)]
-    fn balance_should_have_usd_btc_fee() {
+    fn balances_should_have_usd_and_btc() {
         use std::path::PathBuf;
         let path = PathBuf::from("./keys_real.json");
-        let mut api = Coinnect::new_from_file(Exchange::Bitstamp, "account_bitstamp", path);
-        let result = api.return_balances(Pair::BTC_USD).unwrap();
-        let result_looking_for_usd = result.clone();
-        let result_looking_for_btc = result.clone();
-        let result_looking_for_fee = result.clone();
+        let mut api = Coinnect::new_from_file(Exchange::Bitstamp, "account_bitstamp", path)
+            .unwrap();
+        let result = api.balances().unwrap();
 
-        assert!(result_looking_for_usd.contains_key("usd_balance"));
-        assert!(result_looking_for_btc.contains_key("btc_balance"));
-        assert!(result_looking_for_fee.contains_key("fee"));
+        assert!(result.get("USD").is_some());
+        assert!(result.get("BTC").unwrap().available.is_some());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod credentials_tests {
+    extern crate coinnect;
+
+    use self::coinnect::coinnect::Coinnect;
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::credentials::{Account, Credentials, Secret};
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::exchange::Exchange;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::otp::Otp;
+    use self::coinnect::poloniex::api::PoloniexApi;
+    use self::coinnect::transport::MockTransport;
+
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn config_file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("coinnect_credentials_{}", name));
+        File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+        path
+    }
+
+    fn config_error(reason: &str) -> ErrorKind {
+        ErrorKind::ConfigError(reason.to_string())
+    }
+
+    #[test]
+    fn secrets_should_not_be_shown_by_debug_or_display() {
+        let secret = Secret::new("my_api_secret");
+
+        assert_eq!(format!("{:?}", secret), "Secret(***)");
+        assert_eq!(format!("{}", secret), "***");
+        assert_eq!(secret.expose(), "my_api_secret");
+    }
+
+    #[test]
+    fn credentials_should_return_their_keys() {
+        let credentials = Credentials::new("my_api_key", "my_api_secret");
+
+        assert_eq!(credentials.api_key(), "my_api_key");
+        assert_eq!(credentials.api_secret(), "my_api_secret");
+        assert!(!format!("{:?}", credentials).contains("my_api"));
+    }
+
+    #[test]
+    fn clients_should_not_show_their_keys() {
+        let kraken =
+            KrakenApi::with_transport("my_api_key", "bXlfYXBpX3NlY3JldA==", MockTransport::new());
+        let poloniex =
+            PoloniexApi::with_transport("my_api_key", "my_api_secret", MockTransport::new());
+
+        assert!(!format!("{:?}", kraken).contains("my_api"));
+        assert!(!format!("{:?}", kraken).contains("bXlfYXBpX3NlY3JldA=="));
+        assert!(!format!("{:?}", poloniex).contains("my_api"));
+    }
+
+    #[test]
+    fn accounts_should_be_loaded_from_json_files() {
+        let path = config_file("accounts.json",
+                               r#"{"account_bitstamp": {"exchange": "bitstamp",
+                               "api_key": "bs_key", "api_secret": "bs_secret",
+                               "customer_id": "123456"}}"#);
+
+        let account = Account::from_file("account_bitstamp", &path).unwrap();
+        assert_eq!(account.exchange(), Exchange::Bitstamp);
+        assert_eq!(account.credentials(), &Credentials::new("bs_key", "bs_secret"));
+        assert_eq!(account.customer_id(), Some("123456"));
+    }
+
+    #[test]
+    fn accounts_should_be_loaded_from_toml_files() {
+        let path = config_file("accounts.toml",
+                               "[account_kraken]\nexchange = \"Kraken\"\napi_key = \"key\"\n\
+                                api_secret = \"c2VjcmV0\"\notp = \"hunter2\"\n");
+
+        let account = Account::from_file("account_kraken", &path).unwrap();
+        assert_eq!(account.exchange(), Exchange::Kraken);
+        assert_eq!(account.credentials(), &Credentials::new("key", "c2VjcmV0"));
+        assert_eq!(account.otp(), Some(&Otp::password("hunter2")));
+    }
+
+    #[test]
+    fn loading_an_account_should_report_what_is_wrong() {
+        let path = config_file("invalid.json",
+                               r#"{"no_secret": {"exchange": "kraken", "api_key": "key"},
+                               "no_exchange": {"api_key": "key", "api_secret": "secret"},
+                               "no_customer_id": {"exchange": "bitstamp", "api_key": "key",
+                               "api_secret": "secret"}}"#);
+        let error = |name: &str| Account::from_file(name, &path).unwrap_err().kind().clone();
+        let location = |name: &str| format!("account {} of {}", name, path.display());
+
+        assert_eq!(error("no_secret"),
+                   config_error(&format!("Missing field api_secret in {}", location("no_secret"))));
+        assert_eq!(error("no_exchange"),
+                   config_error(&format!("Missing field exchange in {}", location("no_exchange"))));
+        assert_eq!(error("no_customer_id"),
+                   config_error(&format!("Missing field customer_id in {}",
+                                         location("no_customer_id"))));
+        assert_eq!(error("unknown"),
+                   config_error(&format!("No account unknown in {}", path.display())));
+    }
+
+    #[test]
+    fn the_exchange_of_an_account_should_match_the_client() {
+        let path = config_file("exchange.json",
+                               r#"{"account_poloniex": {"exchange": "poloniex",
+                               "api_key": "key", "api_secret": "secret"},
+                               "account_kraken": {"api_key": "key", "api_secret": "c2VjcmV0"}}"#);
+
+        assert!(KrakenApi::new_from_file("account_kraken", path.clone()).is_ok());
+        assert!(PoloniexApi::new_from_file("account_poloniex", path.clone()).is_ok());
+        assert_eq!(KrakenApi::new_from_file("account_poloniex", path.clone()).unwrap_err().kind(),
+                   &config_error(&format!("Expected a Kraken account, account account_poloniex \
+                                           of {} is a Poloniex account",
+                                          path.display())));
+    }
+
+    #[test]
+    fn accounts_should_be_loaded_from_the_environment() {
+        env::set_var("COINNECT_POLONIEX_API_KEY", "env_key");
+        env::set_var("COINNECT_POLONIEX_API_SECRET", "env_secret");
+
+        let account = Account::from_env(Exchange::Poloniex).unwrap();
+        assert_eq!(account.credentials(), &Credentials::new("env_key", "env_secret"));
+        assert_eq!(Account::from_env(Exchange::Bitstamp).unwrap_err().kind(),
+                   &config_error("Missing environment variable COINNECT_BITSTAMP_API_KEY"));
+    }
+
+    #[test]
+    fn clients_created_from_an_account_should_use_its_settings() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/Balance", r#"{"error": [], "result": {}}"#);
+        let account = Account::new(Exchange::Kraken, Credentials::new("key", "c2VjcmV0"))
+            .with_otp(Otp::password("hunter2"));
+        let mut api =
+            Coinnect::from_account_with_config(&account, ClientConfig::new(), transport.clone());
+
+        api.balances().unwrap();
+        let request = &transport.requests()[0];
+        assert_eq!(request.header("API-Key"), Some("key"));
+        assert!(request.body.split('&').any(|param| param == "otp=hunter2"));
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod decimal_tests {
+    extern crate coinnect;
+
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::error::ErrorKind;
+
+    use std::collections::HashSet;
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    #[test]
+    fn decimals_should_be_parsed_and_formatted_as_is() {
+        assert_eq!(dec("0.12345678").to_string(), "0.12345678");
+        assert_eq!(dec("1170.0").to_string(), "1170.0");
+        assert_eq!(dec("-0.001").to_string(), "-0.001");
+        assert_eq!(dec("+42").to_string(), "42");
+        assert_eq!(dec(".5").to_string(), "0.5");
+        assert_eq!(dec("1e-8").to_string(), "0.00000001");
+        assert_eq!(dec("1.5E3").to_string(), "1500");
+        assert_eq!(dec("123456789012345678.123456789").to_string(),
+                   "123456789012345678.123456789");
+        assert_eq!(format!("{:.2}", dec("1.005")), "1.01");
+        assert_eq!(format!("{:.3}", dec("1.5")), "1.500");
+        assert_eq!(format!("{:.2}", dec("170141183460469231731687303715884105727")),
+                   "170141183460469231731687303715884105727");
+    }
+
+    #[test]
+    fn invalid_decimals_should_be_rejected() {
+        for number in &["", ".", "-", "1.2.3", "abc", "1e", "0x10", "1 000", "NaN", "inf"] {
+            assert_eq!(number.parse::<Decimal>().unwrap_err().kind(),
+                       &ErrorKind::InvalidArguments);
+        }
+        assert!("1".repeat(40).parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn arithmetic_should_be_exact() {
+        assert_eq!(dec("0.1") + dec("0.2"), dec("0.3"));
+        assert_eq!(dec("1") - dec("0.00000001"), dec("0.99999999"));
+        assert_eq!(dec("1101.1") * dec("0.5"), dec("550.55"));
+        assert_eq!(dec("1") / dec("3"), dec("0.333333333333333333"));
+        assert_eq!(dec("2") / dec("3"), dec("0.666666666666666667"));
+        assert_eq!(dec("5") / dec("0.25"), dec("20"));
+        assert_eq!(-dec("1.5"), dec("-1.5"));
+        let amounts = vec![dec("0.1"); 10];
+        assert_eq!(amounts.iter().sum::<Decimal>(), Decimal::ONE);
+    }
+
+    #[test]
+    fn checked_operations_should_return_an_error_instead_of_overflowing() {
+        let max = dec("170141183460469231731687303715884105727");
+        assert_eq!(max.checked_add(dec("1")).unwrap_err().kind(), &ErrorKind::InvalidArguments);
+        assert!(max.checked_add(dec("0.1")).is_err());
+        assert!(max.checked_sub(dec("-1")).is_err());
+        assert!(max.checked_mul(dec("2")).is_err());
+        assert!(max.checked_div(dec("0.1")).is_err());
+        assert!(dec("1").checked_div(Decimal::ZERO).is_err());
+        assert_eq!(dec("1.5").checked_mul(dec("2")).unwrap(), dec("3"));
+        // The numerator is not scaled beyond the quotient
+        assert_eq!(Decimal::from(10_000_000_000) / dec("1.0000000001"),
+                   dec("9999999999.0000000001"));
+        assert_eq!(dec("0.0000000000000000000000000001") / dec("3"), Decimal::ZERO);
+    }
+
+    #[test]
+    fn decimals_should_compare_by_value() {
+        assert_eq!(dec("1.50"), dec("1.5"));
+        assert!(dec("0.02589999") < dec("0.0259"));
+        assert!(dec("-1") < Decimal::ZERO);
+        assert_eq!(dec("1.20").max(dec("1.3")), dec("1.3"));
+        let set: HashSet<Decimal> = vec![dec("1.0"), dec("1"), dec("1.000")].into_iter().collect();
+        assert_eq!(set.len(), 1);
+        // Whatever their scale, without overflowing
+        let max = dec("170141183460469231731687303715884105727");
+        assert!(max > dec("0.0000000000000000000000000001"));
+        assert!(-max < dec("-1.5"));
+        assert!(max != dec("0.1"));
+    }
+
+    #[test]
+    fn decimals_should_be_rounded_to_the_decimals_specified() {
+        assert_eq!(dec("1.25").round_dp(1), dec("1.3"));
+        assert_eq!(dec("-1.25").round_dp(1), dec("-1.3"));
+        assert_eq!(dec("1.29").floor_dp(1), dec("1.2"));
+        assert_eq!(dec("-1.21").floor_dp(1), dec("-1.3"));
+        assert_eq!(dec("1.21").ceil_dp(1), dec("1.3"));
+        assert_eq!(dec("1.2").round_dp(8).to_string(), "1.2");
+        assert_eq!(Decimal::new(123400, 4).normalize().to_string(), "12.34");
+    }
+
+    #[test]
+    fn decimals_should_convert_to_and_from_f64() {
+        assert_eq!(Decimal::from_f64(0.1), Some(dec("0.1")));
+        assert_eq!(Decimal::from_f64(1e-10), Some(dec("0.0000000001")));
+        assert_eq!(Decimal::from_f64(f64::NAN), None);
+        assert_eq!(dec("0.02589999").to_f64(), 0.02589999);
+    }
+}
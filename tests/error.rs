@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod error_tests {
+    extern crate coinnect;
+
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::error::{Error, ErrorKind};
+    use self::coinnect::exchange::{Exchange, ExchangeApi};
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::pair::Pair;
+    use self::coinnect::poloniex::api::PoloniexApi;
+    use self::coinnect::retry::RetryPolicy;
+    use self::coinnect::transport::{MockTransport, HttpResponse};
+
+    use std::error::Error as StdError;
+    use std::time::Duration;
+
+    #[test]
+    fn an_unparsable_response_should_carry_the_endpoint_status_and_body() {
+        let transport = MockTransport::new();
+        transport.respond_with("/0/public/Ticker",
+                               HttpResponse {
+                                   status: 502,
+                                   body: "<html>Bad gateway</html>".to_string(),
+                               });
+        let mut api = KrakenApi::with_transport("", "", transport);
+
+        let err = api.ticker(Pair::BTC_EUR).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::BadParse);
+        assert_eq!(err.exchange(), Some(Exchange::Kraken));
+        assert_eq!(err.endpoint(), Some("Ticker"));
+        assert_eq!(err.status(), Some(502));
+        assert_eq!(err.body(), Some("<html>Bad gateway</html>"));
+        assert!(err.source().is_some());
+        assert!(err.is_retryable());
+        assert!(format!("{}", err)
+            .starts_with("The response could not be parsed. (Kraken Ticker, HTTP 502): <html>"));
+    }
+
+    #[test]
+    fn an_unreachable_host_should_carry_the_endpoint() {
+        let transport = MockTransport::new();
+        let retry_policy = RetryPolicy::new().initial_delay(Duration::from_millis(1));
+        let config = ClientConfig::new().retry_policy(retry_policy);
+        let mut api = PoloniexApi::with_config("api_key", "api_secret", config, transport.clone());
+
+        let err = api.balances().unwrap_err();
+        assert_eq!(transport.requests().len(), 3);
+        assert_eq!(err.kind(), &ErrorKind::ServiceUnavailable);
+        assert_eq!(err.exchange(), Some(Exchange::Poloniex));
+        assert_eq!(err.endpoint(), Some("returnCompleteBalances"));
+        assert_eq!(err.status(), None);
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn an_error_returned_by_the_exchange_should_carry_the_response() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/Balance",
+                          r#"{"error": ["EGeneral:Something new"], "result": {}}"#);
+        let mut api = KrakenApi::with_transport("api_key", "c2VjcmV0", transport);
+
+        let err = api.balances().unwrap_err();
+        assert_eq!(err.kind(),
+                   &ErrorKind::ExchangeSpecificError("EGeneral:Something new".to_string()));
+        assert_eq!(err.exchange(), Some(Exchange::Kraken));
+        assert_eq!(err.endpoint(), Some("Balance"));
+        assert_eq!(err.status(), Some(200));
+        assert_eq!(err.message(), Some("EGeneral:Something new"));
+        assert_eq!(format!("{}", err),
+                   "EGeneral:Something new (Kraken Balance, HTTP 200): \
+                    {\"error\": [\"EGeneral:Something new\"], \"result\": {}}");
+    }
+
+    #[test]
+    fn a_response_missing_a_field_should_carry_the_endpoint_status_and_body() {
+        let transport = MockTransport::new();
+        transport.respond("returnTicker", r#"{"BTC_ZEC": {"last": "0.01"}}"#);
+        let mut api = PoloniexApi::with_transport("api_key", "api_secret", transport);
+
+        let err = api.ticker(Pair::ETH_BTC).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::BadParse);
+        assert_eq!(err.exchange(), Some(Exchange::Poloniex));
+        assert_eq!(err.endpoint(), Some("returnTicker"));
+        assert_eq!(err.status(), Some(200));
+        assert_eq!(err.body(), Some(r#"{"BTC_ZEC": {"last": "0.01"}}"#));
+    }
+
+    #[test]
+    fn long_bodies_should_be_truncated() {
+        let body: String = ::std::iter::repeat('x').take(1000).collect();
+        let err = Error::new(ErrorKind::BadParse).with_response(200, &body);
+
+        assert_eq!(err.body().unwrap().len(), 256);
+    }
+
+    #[test]
+    fn errors_should_be_classified() {
+        assert!(Error::new(ErrorKind::RateLimitExceeded).is_rate_limit());
+        assert!(Error::new(ErrorKind::BadParse).with_response(429, "").is_rate_limit());
+        assert!(Error::new(ErrorKind::BadParse).with_response(429, "").is_retryable());
+        assert!(!Error::new(ErrorKind::BadParse).with_response(400, "").is_retryable());
+        assert!(Error::new(ErrorKind::InvalidLogin).is_auth());
+        assert!(Error::new(ErrorKind::BadParse).with_response(403, "").is_auth());
+        assert!(!Error::new(ErrorKind::PairUnsupported).is_auth());
+        assert!(!Error::new(ErrorKind::PairUnsupported).is_retryable());
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod keystore_tests {
+    extern crate coinnect;
+
+    use self::coinnect::credentials::{Account, Credentials, Secret};
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::exchange::Exchange;
+    use self::coinnect::keystore::Keystore;
+
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{Read, Write};
+    use std::path::PathBuf;
+
+    fn keystore_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("coinnect_keystore_{}", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn fields(exchange: &str, api_key: &str, api_secret: &str) -> HashMap<String, Secret> {
+        let mut fields = HashMap::new();
+        fields.insert("exchange".to_string(), Secret::new(exchange));
+        fields.insert("api_key".to_string(), Secret::new(api_key));
+        fields.insert("api_secret".to_string(), Secret::new(api_secret));
+        fields
+    }
+
+    fn read(path: &PathBuf) -> String {
+        let mut content = String::new();
+        File::open(path).unwrap().read_to_string(&mut content).unwrap();
+        content
+    }
+
+    fn is_config_error(kind: &ErrorKind) -> bool {
+        match *kind {
+            ErrorKind::ConfigError(_) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn saved_accounts_should_be_encrypted_and_opened_with_the_passphrase() {
+        let path = keystore_path("saved");
+        let mut keystore = Keystore::create(path.clone(), "passphrase").cost(4);
+        keystore.add("account_poloniex", fields("poloniex", "my_api_key", "my_api_secret"))
+            .unwrap();
+        keystore.save().unwrap();
+
+        assert!(!read(&path).contains("my_api"));
+        let keystore = Keystore::open(path.clone(), "passphrase").unwrap();
+        assert_eq!(keystore.names(), vec!["account_poloniex"]);
+        let account = keystore.account("account_poloniex").unwrap();
+        assert_eq!(account.exchange(), Exchange::Poloniex);
+        assert_eq!(account.credentials(), &Credentials::new("my_api_key", "my_api_secret"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn secrets_with_special_characters_should_be_saved_as_is() {
+        let path = keystore_path("escaped");
+        let secret = "s\"e\\c\nr\u{1}é t";
+        let mut keystore = Keystore::create(path.clone(), "passphrase").cost(4);
+        keystore.add("a \"b\"", fields("poloniex", "my_api_key", secret)).unwrap();
+        keystore.save().unwrap();
+
+        let keystore = Keystore::open(path.clone(), "passphrase").unwrap();
+        let account = keystore.account("a \"b\"").unwrap();
+        assert_eq!(account.credentials(), &Credentials::new("my_api_key", secret));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_wrong_passphrase_or_a_modified_file_should_return_an_error() {
+        let path = keystore_path("modified");
+        let mut keystore = Keystore::create(path.clone(), "passphrase").cost(4);
+        keystore.add("account_kraken", fields("kraken", "key", "c2VjcmV0")).unwrap();
+        keystore.save().unwrap();
+
+        assert!(is_config_error(Keystore::open(path.clone(), "wrong").unwrap_err().kind()));
+
+        let original = read(&path);
+        let content = original.replace("\"log_n\":4", "\"log_n\":5");
+        File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+        assert!(is_config_error(Keystore::open(path.clone(), "passphrase").unwrap_err().kind()));
+
+        // Rejected before deriving any key, which would need 1 TiB of memory
+        let content = original.replace("\"log_n\":4", "\"log_n\":30");
+        File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+        assert!(is_config_error(Keystore::open(path.clone(), "passphrase").unwrap_err().kind()));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn accounts_should_be_added_and_removed() {
+        let path = keystore_path("accounts");
+        let mut keystore = Keystore::create(path.clone(), "passphrase").cost(4);
+        keystore.add("b", fields("kraken", "key", "c2VjcmV0")).unwrap();
+        keystore.add("a", fields("poloniex", "key", "secret")).unwrap();
+        assert_eq!(keystore.names(), vec!["a", "b"]);
+
+        assert!(keystore.remove("a"));
+        assert!(!keystore.remove("a"));
+        assert_eq!(keystore.names(), vec!["b"]);
+        assert!(is_config_error(keystore.account("a").unwrap_err().kind()));
+    }
+
+    #[test]
+    fn invalid_accounts_should_not_be_added() {
+        let mut keystore = Keystore::create(keystore_path("invalid"), "passphrase");
+
+        let error = keystore.add("account_bitstamp", fields("bitstamp", "key", "secret"))
+            .unwrap_err();
+        assert_eq!(error.kind(),
+                   &ErrorKind::ConfigError("Missing field customer_id in account \
+                                            account_bitstamp"
+                       .to_string()));
+        assert!(keystore.names().is_empty());
+    }
+
+    #[test]
+    fn accounts_should_be_loaded_from_a_keystore() {
+        let path = keystore_path("load");
+        let mut keystore = Keystore::create(path.clone(), "passphrase").cost(4);
+        let mut bitstamp = fields("bitstamp", "key", "secret");
+        bitstamp.insert("customer_id".to_string(), Secret::new("123456"));
+        keystore.add("account_bitstamp", bitstamp).unwrap();
+        keystore.save().unwrap();
+
+        let expected = Account::new(Exchange::Bitstamp, Credentials::new("key", "secret"))
+            .with_customer_id("123456");
+        assert_eq!(Account::from_keystore("account_bitstamp", &path, "passphrase").unwrap(),
+                   expected);
+        assert!(!format!("{:?}", keystore).contains("secret"));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -3,7 +3,241 @@
 mod kraken_tests {
     extern crate coinnect;
 
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::currency::Currency;
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::error::ErrorKind;
     use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::kraken::error::KrakenError;
+    use self::coinnect::kraken::utils;
+    use self::coinnect::exchange::ExchangeApi;
+    use self::coinnect::pair::Pair;
+    use self::coinnect::transport::{MockTransport, Method};
+    use self::coinnect::types::{OrderRequest, OrderSide, OrderStatus};
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    /// Answer the AssetPairs requests, so that orders on BTC/EUR can be validated.
+    fn respond_asset_pairs(transport: &MockTransport) {
+        transport.respond("/0/public/AssetPairs",
+                          r#"{"error": [], "result": {"XXBTZEUR": {"base": "XXBT",
+                          "quote": "ZEUR", "pair_decimals": 1, "lot_decimals": 8,
+                          "fees": [[0, 0.26]]}}}"#);
+    }
+
+    #[test]
+    fn normalize_asset_should_remove_kraken_prefixes() {
+        assert_eq!(utils::normalize_asset("XXBT"), "BTC");
+        assert_eq!(utils::normalize_asset("ZEUR"), "EUR");
+        assert_eq!(utils::normalize_asset("XXDG"), "DOGE");
+        assert_eq!(utils::normalize_asset("USDT"), "USDT");
+        assert_eq!(utils::normalize_asset("DASH"), "DASH");
+        assert_eq!(utils::normalize_asset("DOT.S"), "DOT");
+        assert_eq!(utils::normalize_asset("XBT.M"), "BTC");
+        assert_eq!(utils::normalize_asset("USD.HOLD"), "USD");
+    }
+
+    #[test]
+    fn get_pair_altname_should_remove_kraken_prefixes() {
+        assert_eq!(utils::get_pair_altname("XXBTZEUR"), "XBTEUR");
+        assert_eq!(utils::get_pair_altname("XETHXXBT.d"), "ETHXBT.d");
+        assert_eq!(utils::get_pair_altname("USDTZUSD"), "USDTUSD");
+    }
+
+    #[test]
+    fn get_pair_string_should_add_kraken_prefixes() {
+        assert_eq!(utils::get_pair_string(&Pair::BTC_EUR), Some("XXBTZEUR".to_string()));
+        assert_eq!(utils::get_pair_string(&Pair::DOGE_BTC), Some("XXDGXXBT".to_string()));
+        assert_eq!(utils::get_pair_string(&Pair::USDT_USD), Some("USDTZUSD".to_string()));
+        assert_eq!(utils::get_pair_string(&Pair::new(Currency::BCH, Currency::EUR)),
+                   Some("BCHEUR".to_string()));
+        assert_eq!(utils::get_pair_string(&Pair::new(Currency::DASH, Currency::EUR)),
+                   Some("DASHEUR".to_string()));
+        assert_eq!(utils::get_pair_string(&Pair::new(Currency::ETH, Currency::CHF)),
+                   Some("ETHCHF".to_string()));
+        assert_eq!(utils::get_dark_pool_string(&Pair::ETH_BTC),
+                   Some("XETHXXBT.d".to_string()));
+        assert_eq!(utils::get_pair_string(&Pair::DOGE_BTC.inverse()), None);
+    }
+
+    #[test]
+    fn get_pair_should_parse_names_and_altnames() {
+        assert_eq!(utils::get_pair("XXBTZEUR"), Some(Pair::BTC_EUR));
+        assert_eq!(utils::get_pair("XBTEUR"), Some(Pair::BTC_EUR));
+        assert_eq!(utils::get_pair("XETHXXBT.d"), Some(Pair::ETH_BTC));
+        assert!(utils::is_dark_pool("XETHXXBT.d"));
+        assert_eq!(utils::get_pair("USDTZUSD"), Some(Pair::USDT_USD));
+        assert_eq!(utils::get_pair("DASHEUR"), Some(Pair::new(Currency::DASH, Currency::EUR)));
+        assert_eq!(utils::get_pair("BCHXBT"), Some(Pair::new(Currency::BCH, Currency::BTC)));
+        assert_eq!(utils::get_pair("XBTUSDT"), Some(Pair::new(Currency::BTC, Currency::USDT)));
+        assert_eq!(utils::get_pair("XBT"), None);
+        assert_eq!(utils::get_pair("DA$HEUR"), None);
+    }
+
+    #[test]
+    fn public_requests_should_be_sent_with_get() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Time",
+                          r#"{"error": [], "result": {"unixtime": 1491919199,
+                          "rfc1123": "Tue, 11 Apr 17 14:39:59 +0000"}}"#);
+        let mut api = KrakenApi::with_transport("", "", transport.clone());
+        let result = api.get_server_time().unwrap();
+
+        assert_eq!(result["result"]["unixtime"], 1491919199);
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.method, Method::Get);
+        assert_eq!(request.url, "https://api.kraken.com/0/public/Time?");
+    }
+
+    #[test]
+    fn private_requests_should_be_signed() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/AddOrder",
+                          r#"{"error": [], "result": {
+                          "descr": {"order": "buy 1.00000000 XBTEUR @ limit 1000.0"},
+                          "txid": ["OAVY7T-MV5VK-KHDF5X"]}}"#);
+        respond_asset_pairs(&transport);
+        let mut api = KrakenApi::with_transport("api_key", "c2VjcmV0", transport.clone());
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("1000.0"), dec("1.0"));
+
+        assert_eq!(api.place_order(order).unwrap().0, "OAVY7T-MV5VK-KHDF5X");
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(request.header("API-Key"), Some("api_key"));
+        assert!(request.header("API-Sign").is_some());
+        assert!(request.body.contains("ordertype=limit"));
+        assert!(request.body.contains("nonce="));
+    }
+
+    #[test]
+    fn orders_on_unlisted_pairs_should_not_be_sent() {
+        let transport = MockTransport::new();
+        respond_asset_pairs(&transport);
+        let mut api = KrakenApi::with_transport("api_key", "c2VjcmV0", transport.clone());
+        let order = OrderRequest::limit(Pair::ETH_BTC, OrderSide::Buy, dec("0.05"), dec("1.0"));
+
+        assert_eq!(api.place_order(order).unwrap_err().kind(), &ErrorKind::PairUnsupported);
+        assert!(transport.requests().iter().all(|r| !r.url.contains("/private/")));
+    }
+
+    #[test]
+    fn requests_should_be_sent_to_the_configured_base_url() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/Balance", r#"{"error": [], "result": {}}"#);
+        let config = ClientConfig::new().base_url("https://gateway.example.com/kraken/");
+        let mut api = KrakenApi::with_config("api_key", "c2VjcmV0", config, transport.clone());
+        api.get_account_balance().unwrap();
+
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.url, "https://gateway.example.com/kraken/0/private/Balance");
+        // The signature still covers the path of the official API
+        let nonce = request.body.trim_left_matches("nonce=");
+        let signature = utils::create_signature("c2VjcmV0", "/0/private/Balance", &request.body,
+                                                nonce)
+            .unwrap();
+        assert_eq!(request.header("API-Sign"), Some(signature.as_str()));
+    }
+
+    #[test]
+    fn generic_open_orders_should_only_return_the_pair_orders() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/OpenOrders",
+                          r#"{"error": [], "result": {"open": {
+                          "OQCLML-BW3P3-BUCMWZ": {"status": "open", "vol": "1.00000000",
+                          "vol_exec": "0.25000000", "descr": {"pair": "XBTEUR", "type": "sell",
+                          "ordertype": "limit", "price": "1200.0"}},
+                          "OB5VMB-B4U2U-DK2WRW": {"status": "open", "vol": "10.00000000",
+                          "vol_exec": "0.00000000", "descr": {"pair": "ETHEUR", "type": "buy",
+                          "ordertype": "limit", "price": "40.0"}}}}}"#);
+        let mut api = KrakenApi::with_transport("api_key", "c2VjcmV0", transport);
+        let orders = api.open_orders(Pair::BTC_EUR).unwrap();
+
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].id.0, "OQCLML-BW3P3-BUCMWZ");
+        assert_eq!(orders[0].side, OrderSide::Sell);
+        assert_eq!(orders[0].filled, Some(dec("0.25")));
+        assert_eq!(orders[0].status, OrderStatus::Open);
+    }
+
+    #[test]
+    fn kraken_errors_should_be_returned() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Ticker",
+                          r#"{"error": ["EQuery:Unknown asset pair"]}"#);
+        let mut api = KrakenApi::with_transport("", "", transport);
+
+        assert!(api.ticker(Pair::BTC_EUR).is_err());
+    }
+
+    #[test]
+    fn malformed_responses_should_return_an_error() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Ticker",
+                          r#"{"error": [], "result": {"XXBTZEUR": {"c": [null]}}}"#);
+        transport.respond("/0/public/Depth", r#"{"error": []}"#);
+        transport.respond("/0/public/Trades", "<html>Bad gateway</html>");
+        let mut api = KrakenApi::with_transport("", "", transport);
+
+        assert_eq!(api.ticker(Pair::BTC_EUR).unwrap_err().kind(), &ErrorKind::BadParse);
+        assert!(api.orderbook(Pair::BTC_EUR).is_err());
+        assert!(api.trades(Pair::BTC_EUR, None).is_err());
+    }
+
+    #[test]
+    fn kraken_errors_should_be_mapped_to_their_kind() {
+        assert_eq!(KrakenError::from_message("EOrder:Insufficient funds"),
+                   KrakenError::InsufficientFunds);
+        assert_eq!(KrakenError::from_message("EGeneral:Invalid arguments:volume"),
+                   KrakenError::InvalidArguments);
+        assert_eq!(KrakenError::from_message("EAPI:Invalid nonce").kind(),
+                   ErrorKind::InvalidNonce);
+        assert_eq!(KrakenError::from_message("EGeneral:Temporary lockout").kind(),
+                   ErrorKind::AccountLocked);
+        assert_eq!(KrakenError::from_message("EOrder:Margin level too low").kind(),
+                   ErrorKind::InsufficientFunds);
+        assert_eq!(KrakenError::from_message("EService:Market in post_only mode").kind(),
+                   ErrorKind::InvalidOrder);
+        assert_eq!(KrakenError::from_message("EService:Busy").kind(),
+                   ErrorKind::ServiceUnavailable);
+        assert_eq!(KrakenError::from_message("EFoo:Bar").kind(),
+                   ErrorKind::ExchangeSpecificError("EFoo:Bar".to_string()));
+
+        let transport = MockTransport::new();
+        transport.respond("/0/private/AddOrder",
+                          r#"{"error": ["EOrder:Order minimum not met"]}"#);
+        respond_asset_pairs(&transport);
+        let mut api = KrakenApi::with_transport("api_key", "c2VjcmV0", transport);
+        let order = OrderRequest::limit(Pair::BTC_EUR,
+                                        OrderSide::Buy,
+                                        dec("1000.0"),
+                                        dec("0.0001"));
+
+        let err = api.place_order(order).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidOrder);
+        assert_eq!(err.message(), Some("EOrder:Order minimum not met"));
+    }
+
+    #[test]
+    fn a_secret_which_is_not_base64_should_return_an_error() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/Balance", r#"{"error": [], "result": {}}"#);
+        let mut api = KrakenApi::with_transport("api_key", "not base64!", transport);
+
+        assert_eq!(api.get_account_balance().unwrap_err().kind(), &ErrorKind::InvalidLogin);
+    }
+
+    #[test]
+    fn new_from_file_should_return_an_error_when_the_file_is_missing() {
+        use std::path::PathBuf;
+        let path = PathBuf::from("./does_not_exist.json");
+
+        match *KrakenApi::new_from_file("account_kraken", path).unwrap_err().kind() {
+            ErrorKind::ConfigError(_) => (),
+            ref other => panic!("unexpected error: {:?}", other),
+        }
+    }
 
     /// IMPORTANT: Real keys are needed in order to retrieve the balance
     #[test]
@@ -11,9 +245,21 @@ mod kraken_tests {
     fn balance_should_return_a_result() {
         use std::path::PathBuf;
         let path = PathBuf::from("./keys_real.json");
-        let mut api = KrakenApi::new_from_file("account_kraken", path);
+        let mut api = KrakenApi::new_from_file("account_kraken", path).unwrap();
         let result = api.get_account_balance();
 
         assert!(result.unwrap().contains_key("result"));
     }
-}
\ No newline at end of file
+
+    /// IMPORTANT: Real keys are needed in order to retrieve the balance
+    #[test]
+    #[cfg_attr(not(feature = "kraken_private_tests"), ignore)]
+    fn generic_balances_should_return_a_result() {
+        use std::path::PathBuf;
+        let path = PathBuf::from("./keys_real.json");
+        let mut api = KrakenApi::new_from_file("account_kraken", path).unwrap();
+        let result = api.balances();
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,240 @@
+#[cfg(test)]
+mod market_tests {
+    extern crate coinnect;
+
+    use self::coinnect::bitstamp::api::BitstampApi;
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::currency::Currency;
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::exchange::ExchangeApi;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::market::MarketCache;
+    use self::coinnect::mock_server::{MockServer, DEFAULT_API_KEY, DEFAULT_API_SECRET,
+                                      DEFAULT_CUSTOMER_ID};
+    use self::coinnect::pair::Pair;
+    use self::coinnect::poloniex::api::PoloniexApi;
+    use self::coinnect::retry::RetryPolicy;
+    use self::coinnect::transport::MockTransport;
+    use self::coinnect::types::{Market, MarketInfo};
+
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    fn symbols(markets: &[Market]) -> Vec<(Pair, String)> {
+        markets.iter().map(|market| (market.pair, market.symbol.clone())).collect()
+    }
+
+    fn symbol(pair: Pair, symbol: &str) -> (Pair, String) {
+        (pair, symbol.to_string())
+    }
+
+    fn kraken_asset_pairs() -> MockTransport {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/AssetPairs",
+                          r#"{"error": [], "result": {
+                          "XXBTZEUR": {"altname": "XBTEUR", "base": "XXBT", "quote": "ZEUR",
+                          "pair_decimals": 1, "lot_decimals": 8, "fees": [[0, 0.26]],
+                          "fees_maker": [[0, 0.16]], "leverage_buy": [2, 3, 4, 5],
+                          "ordermin": "0.002"},
+                          "XXBTZEUR.d": {"altname": "XBTEUR.d", "base": "XXBT", "quote": "ZEUR",
+                          "pair_decimals": 1, "lot_decimals": 8, "fees": [[0, 0.26]]},
+                          "DASHXBT": {"altname": "DASHXBT", "base": "DASH", "quote": "XXBT",
+                          "pair_decimals": 5, "lot_decimals": 8, "fees": [[0, 0.26]]}}}"#);
+        transport
+    }
+
+    #[test]
+    fn markets_should_be_retrieved_from_every_mock_exchange() {
+        let server = MockServer::default();
+        let mut kraken = KrakenApi::with_transport(DEFAULT_API_KEY,
+                                                   DEFAULT_API_SECRET,
+                                                   server.clone());
+        let mut poloniex = PoloniexApi::with_transport(DEFAULT_API_KEY,
+                                                       DEFAULT_API_SECRET,
+                                                       server.clone());
+        let mut params = HashMap::new();
+        params.insert("customer_id", DEFAULT_CUSTOMER_ID);
+        let mut bitstamp = BitstampApi::with_transport(&params, server);
+
+        let markets = kraken.markets().unwrap();
+        assert_eq!(markets.len(), 4);
+        assert!(symbols(&markets).contains(&symbol(Pair::ETH_BTC, "XETHXXBT")));
+        let markets = poloniex.markets().unwrap();
+        assert_eq!(markets.len(), 2);
+        assert!(symbols(&markets).contains(&symbol(Pair::ZEC_BTC, "BTC_ZEC")));
+        let markets = bitstamp.markets().unwrap();
+        assert_eq!(markets.len(), 4);
+        assert!(symbols(&markets).contains(&symbol(Pair::ETH_EUR, "etheur")));
+    }
+
+    #[test]
+    fn markets_should_be_cached_until_cleared() {
+        let transport = kraken_asset_pairs();
+        let mut api = KrakenApi::with_transport("", "", transport.clone());
+
+        let markets = api.markets().unwrap();
+        assert_eq!(markets.len(), 2);
+        assert!(symbols(&markets).contains(&symbol(Pair::DASH_BTC, "DASHXBT")));
+        assert_eq!(api.markets().unwrap(), markets);
+        assert_eq!(transport.requests().len(), 1);
+
+        api.market_cache().clear();
+        api.markets().unwrap();
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn markets_which_could_not_be_retrieved_should_not_be_requested_again_at_once() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/AssetPairs", r#"{"error": ["EService:Unavailable"]}"#);
+        transport.respond("/0/public/Ticker",
+                          r#"{"error": [], "result": {"XXBTZEUR": {"a": ["1001", "1", "1.000"],
+                          "b": ["999", "1", "1.000"], "c": ["1000", "0.1"],
+                          "v": ["10", "20"]}}}"#);
+        let config = ClientConfig::new().retry_policy(RetryPolicy::new().max_attempts(1));
+        let mut api = KrakenApi::with_config("", "", config, transport.clone());
+
+        // The pairs are named without the markets meanwhile
+        api.ticker(Pair::BTC_EUR).unwrap();
+        api.ticker(Pair::BTC_EUR).unwrap();
+        let requests = transport.requests();
+        assert_eq!(requests.iter().filter(|r| r.url.contains("AssetPairs")).count(), 1);
+    }
+
+    #[test]
+    fn expired_markets_should_be_retrieved_again() {
+        let transport = kraken_asset_pairs();
+        let config = ClientConfig::new().market_cache(MarketCache::new(Duration::from_secs(0)));
+        let mut api = KrakenApi::with_config("", "", config, transport.clone());
+
+        api.markets().unwrap();
+        api.markets().unwrap();
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn cached_markets_should_name_the_pairs() {
+        let transport = kraken_asset_pairs();
+        transport.respond("/0/public/Ticker",
+                          r#"{"error": [], "result": {"DASHXBT": {"a": ["0.05", "1", "1.000"],
+                          "b": ["0.04", "1", "1.000"], "c": ["0.045", "0.1"],
+                          "v": ["10", "20"]}}}"#);
+        let mut api = KrakenApi::with_transport("", "", transport.clone());
+        api.markets().unwrap();
+
+        assert_eq!(api.ticker(Pair::DASH_BTC).unwrap().last_trade_price, dec("0.045"));
+        assert!(transport.last_request().unwrap().url.contains("Ticker?pair=DASHXBT"));
+        // Unlisted pairs are rejected without sending a request
+        assert_eq!(api.ticker(Pair::ETH_BTC).unwrap_err().kind(),
+                   &ErrorKind::PairUnsupported);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn poloniex_markets_should_skip_frozen_markets_and_delisted_currencies() {
+        let transport = MockTransport::new();
+        transport.respond("command=returnTicker",
+                          r#"{"BTC_ETH": {"isFrozen": "0"}, "BTC_NXT": {"isFrozen": "1"},
+                          "BTC_BBR": {"isFrozen": "0"}, "USDT_STR": {"isFrozen": "0"}}"#);
+        transport.respond("command=returnCurrencies",
+                          r#"{"BTC": {"delisted": 0, "frozen": 0},
+                          "ETH": {"delisted": 0, "frozen": 0},
+                          "BBR": {"delisted": 1, "frozen": 0}}"#);
+        let mut api = PoloniexApi::with_transport("", "", transport);
+
+        let mut markets = api.markets().unwrap();
+        markets.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        assert_eq!(symbols(&markets),
+                   vec![symbol(Pair::ETH_BTC, "BTC_ETH"),
+                        symbol(Pair::new(Currency::XLM, Currency::USDT), "USDT_STR")]);
+    }
+
+    #[test]
+    fn clients_should_share_a_market_cache() {
+        let cache = MarketCache::default();
+        let config = ClientConfig::new().market_cache(cache.clone());
+        let mut first = KrakenApi::with_config("", "", config.clone(), kraken_asset_pairs());
+        first.markets().unwrap();
+
+        let transport = MockTransport::new();
+        let mut second = KrakenApi::with_config("", "", config, transport.clone());
+        assert_eq!(second.markets().unwrap().len(), 2);
+        assert!(transport.requests().is_empty());
+        assert!(cache.get().is_some());
+    }
+
+    #[test]
+    fn kraken_market_info_should_include_fees_and_leverage() {
+        let mut api = KrakenApi::with_transport("", "", kraken_asset_pairs());
+
+        let info = api.market_info(Pair::BTC_EUR).unwrap();
+        assert_eq!(info.price_decimals, 1);
+        assert_eq!(info.volume_decimals, 8);
+        assert_eq!(info.min_volume, Some(dec("0.002")));
+        assert_eq!(info.min_value, None);
+        assert_eq!(info.leverage, vec![2, 3, 4, 5]);
+        assert_eq!(info.maker_fee, 0.0016);
+        assert_eq!(info.taker_fee, 0.0026);
+        // Without a maker schedule, makers pay the taker fee
+        let info = api.market_info(Pair::DASH_BTC).unwrap();
+        assert_eq!(info.maker_fee, 0.0026);
+        assert_eq!(info.min_volume, None);
+        assert!(info.leverage.is_empty());
+    }
+
+    #[test]
+    fn market_info_should_be_retrieved_from_every_mock_exchange() {
+        let server = MockServer::default();
+        let mut poloniex = PoloniexApi::with_transport(DEFAULT_API_KEY,
+                                                       DEFAULT_API_SECRET,
+                                                       server.clone());
+        let mut params = HashMap::new();
+        params.insert("customer_id", DEFAULT_CUSTOMER_ID);
+        let mut bitstamp = BitstampApi::with_transport(&params, server);
+
+        let info = poloniex.market_info(Pair::ETH_BTC).unwrap();
+        assert_eq!(info.price_decimals, 8);
+        assert_eq!(info.min_value, Some(dec("0.0001")));
+        assert_eq!(info.maker_fee, 0.0015);
+        let info = bitstamp.market_info(Pair::ETH_EUR).unwrap();
+        assert_eq!(info.price_decimals, 2);
+        assert_eq!(info.tick_size(), dec("0.01"));
+        assert_eq!(info.lot_size(), dec("0.00000001"));
+        assert_eq!(info.min_value, Some(dec("5.0")));
+        assert_eq!(bitstamp.market_info(Pair::XMR_EUR).unwrap_err().kind(),
+                   &ErrorKind::PairUnsupported);
+    }
+
+    #[test]
+    fn markets_which_do_not_parse_should_be_skipped() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/AssetPairs",
+                          r#"{"error": [], "result": {
+                          "XXBTZEUR": {"base": "XXBT", "quote": "ZEUR", "pair_decimals": 40,
+                          "lot_decimals": 8, "fees": [[0, 0.26]]},
+                          "NEW-COINZEUR": {"base": "NEW-COIN", "quote": "ZEUR",
+                          "pair_decimals": 2, "lot_decimals": 8, "fees": [[0, 0.26]]},
+                          "DASHXBT": {"base": "DASH", "quote": "XXBT", "pair_decimals": 5,
+                          "lot_decimals": 8, "fees": [[0, 0.26]]}}}"#);
+        let mut api = KrakenApi::with_transport("", "", transport);
+
+        let markets = api.markets().unwrap();
+        assert_eq!(symbols(&markets), vec![symbol(Pair::DASH_BTC, "DASHXBT")]);
+        // Built by hand, the increments are clamped to the decimals of a Decimal
+        let info = MarketInfo {
+            price_decimals: 40,
+            volume_decimals: 8,
+            min_volume: None,
+            min_value: None,
+            leverage: Vec::new(),
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+        };
+        assert_eq!(info.tick_size(), dec("0.0000000000000000000000000001"));
+    }
+}
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod mock_server_tests {
+    extern crate coinnect;
+
+    use self::coinnect::bitstamp::api::BitstampApi;
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::exchange::{Exchange, ExchangeApi};
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::mock_server::{MockServer, DEFAULT_API_KEY, DEFAULT_API_SECRET,
+                                      DEFAULT_CUSTOMER_ID};
+    use self::coinnect::pair::Pair;
+    use self::coinnect::poloniex::api::PoloniexApi;
+    use self::coinnect::types::{OrderRequest, OrderSide};
+
+    use std::collections::HashMap;
+
+    fn bitstamp(server: &MockServer) -> BitstampApi {
+        let mut params = HashMap::new();
+        params.insert("api_key", DEFAULT_API_KEY);
+        params.insert("api_secret", DEFAULT_API_SECRET);
+        params.insert("customer_id", DEFAULT_CUSTOMER_ID);
+        BitstampApi::with_transport(&params, server.clone())
+    }
+
+    /// Place a limit order far from the market, check it rests in the book with its funds on hold
+    /// and cancel it.
+    fn resting_order_flow(api: &mut ExchangeApi, pair: Pair) {
+        let best_bid = api.orderbook(pair).unwrap().best_bid().unwrap().0;
+        let price = best_bid * Decimal::new(5, 1);
+        let order = OrderRequest::limit(pair, OrderSide::Buy, price, Decimal::from(2));
+        let id = api.place_order(order).unwrap();
+
+        let open_orders = api.open_orders(pair).unwrap();
+        assert_eq!(open_orders.len(), 1);
+        assert_eq!(open_orders[0].id, id);
+        assert_eq!(open_orders[0].volume, Decimal::from(2));
+        let orderbook = api.orderbook(pair).unwrap();
+        assert!(orderbook.bids.iter().any(|&(bid, _)| bid == price));
+
+        api.cancel_order(id.clone()).unwrap();
+        assert!(api.open_orders(pair).unwrap().is_empty());
+        assert!(api.cancel_order(id).is_err());
+    }
+
+    #[test]
+    fn kraken_orders_should_rest_in_the_book_until_canceled() {
+        let server = MockServer::default();
+        let mut api = KrakenApi::with_transport(DEFAULT_API_KEY, DEFAULT_API_SECRET, server);
+
+        resting_order_flow(&mut api, Pair::BTC_EUR);
+    }
+
+    #[test]
+    fn poloniex_orders_should_rest_in_the_book_until_canceled() {
+        let server = MockServer::default();
+        let mut api = PoloniexApi::with_transport(DEFAULT_API_KEY, DEFAULT_API_SECRET, server);
+
+        resting_order_flow(&mut api, Pair::ETH_BTC);
+    }
+
+    #[test]
+    fn bitstamp_orders_should_rest_in_the_book_until_canceled() {
+        let server = MockServer::default();
+        let mut api = bitstamp(&server);
+
+        resting_order_flow(&mut api, Pair::BTC_USD);
+    }
+
+    #[test]
+    fn resting_orders_should_put_funds_on_hold() {
+        let server = MockServer::default();
+        let mut api = PoloniexApi::with_transport(DEFAULT_API_KEY,
+                                                  DEFAULT_API_SECRET,
+                                                  server.clone());
+        let order = OrderRequest::limit(Pair::ETH_BTC,
+                                        OrderSide::Sell,
+                                        Decimal::ONE,
+                                        Decimal::from(10));
+        let id = api.place_order(order).unwrap();
+
+        let balances = api.balances().unwrap();
+        assert_eq!(balances.get("ETH").unwrap().on_hold, Some(Decimal::from(10)));
+        assert_eq!(balances.get("ETH").unwrap().available, Some(Decimal::from(90)));
+
+        ExchangeApi::cancel_order(&mut api, id).unwrap();
+        assert_eq!(server.balance(Exchange::Poloniex, "ETH").on_hold, Some(Decimal::ZERO));
+    }
+
+    #[test]
+    fn market_orders_should_fill_against_the_book() {
+        let server = MockServer::default();
+        let mut api = KrakenApi::with_transport(DEFAULT_API_KEY,
+                                                DEFAULT_API_SECRET,
+                                                server.clone());
+        let best_ask = api.orderbook(Pair::BTC_EUR).unwrap().best_ask().unwrap().clone();
+
+        let order = OrderRequest::market(Pair::BTC_EUR, OrderSide::Buy, best_ask.1);
+        api.place_order(order).unwrap();
+
+        assert_eq!(server.balance(Exchange::Kraken, "XXBT").total,
+                   Decimal::from(10) + best_ask.1);
+        assert_eq!(server.balance(Exchange::Kraken, "ZEUR").total,
+                   Decimal::from(100000) - best_ask.0 * best_ask.1);
+        let orderbook = api.orderbook(Pair::BTC_EUR).unwrap();
+        assert!(orderbook.best_ask().unwrap().0 > best_ask.0);
+        let history = api.trades(Pair::BTC_EUR, None).unwrap();
+        assert_eq!(history.trades.last().unwrap().price, best_ask.0);
+    }
+
+    #[test]
+    fn crossing_limit_orders_should_be_filled_on_bitstamp() {
+        let server = MockServer::default();
+        let mut api = bitstamp(&server);
+        let best_bid = api.orderbook(Pair::BTC_USD).unwrap().best_bid().unwrap().clone();
+
+        let order = OrderRequest::limit(Pair::BTC_USD, OrderSide::Sell, best_bid.0, best_bid.1);
+        api.place_order(order).unwrap();
+
+        assert!(api.open_orders(Pair::BTC_USD).unwrap().is_empty());
+        let balances = api.balances().unwrap();
+        assert_eq!(balances.get("BTC").unwrap().total,
+                   Decimal::from(10) - best_bid.1);
+        assert_eq!(balances.get("USD").unwrap().total,
+                   Decimal::from(100000) + best_bid.0 * best_bid.1);
+    }
+
+    #[test]
+    fn orders_without_enough_funds_should_be_rejected() {
+        let server = MockServer::default();
+        let mut kraken = KrakenApi::with_transport(DEFAULT_API_KEY,
+                                                   DEFAULT_API_SECRET,
+                                                   server.clone());
+        let mut poloniex = PoloniexApi::with_transport(DEFAULT_API_KEY,
+                                                       DEFAULT_API_SECRET,
+                                                       server.clone());
+        let mut bitstamp = bitstamp(&server);
+
+        let apis: Vec<(&mut ExchangeApi, Pair)> = vec![(&mut kraken, Pair::BTC_EUR),
+                                                        (&mut poloniex, Pair::ETH_BTC),
+                                                        (&mut bitstamp, Pair::BTC_USD)];
+        for (api, pair) in apis {
+            let order = OrderRequest::limit(pair,
+                                            OrderSide::Sell,
+                                            Decimal::from(100000),
+                                            Decimal::from(1000000));
+            let err = api.place_order(order).unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::InsufficientFunds);
+            assert!(err.message().is_some());
+        }
+    }
+
+    #[test]
+    fn requests_with_a_wrong_secret_should_be_rejected() {
+        let server = MockServer::default();
+        let mut kraken = KrakenApi::with_transport(DEFAULT_API_KEY, "d3Jvbmc=", server.clone());
+        let mut poloniex = PoloniexApi::with_transport(DEFAULT_API_KEY, "wrong", server.clone());
+        let mut params = HashMap::new();
+        params.insert("api_key", DEFAULT_API_KEY);
+        params.insert("api_secret", "wrong");
+        params.insert("customer_id", DEFAULT_CUSTOMER_ID);
+        let mut bitstamp = BitstampApi::with_transport(&params, server);
+
+        assert!(kraken.balances().unwrap_err().is_auth());
+        assert!(poloniex.balances().unwrap_err().is_auth());
+        assert!(bitstamp.balances().unwrap_err().is_auth());
+    }
+
+    #[test]
+    fn public_requests_should_not_need_credentials() {
+        let server = MockServer::default();
+        let mut kraken = KrakenApi::with_transport("", "", server.clone());
+        let mut poloniex = PoloniexApi::with_transport("", "", server.clone());
+        let mut bitstamp = BitstampApi::with_transport(&HashMap::new(), server);
+
+        assert!(kraken.ticker(Pair::BTC_EUR).unwrap().last_trade_price > Decimal::ZERO);
+        assert!(poloniex.ticker(Pair::ETH_BTC).unwrap().last_trade_price > Decimal::ZERO);
+        assert!(bitstamp.ticker(Pair::BTC_USD).unwrap().last_trade_price > Decimal::ZERO);
+        assert!(!kraken.trades(Pair::BTC_EUR, None).unwrap().trades.is_empty());
+        assert!(!poloniex.trades(Pair::ETH_BTC, None).unwrap().trades.is_empty());
+        assert!(!bitstamp.trades(Pair::BTC_USD, None).unwrap().trades.is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod nonce_tests {
+    extern crate coinnect;
+
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::exchange::ExchangeApi;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::nonce::NonceGenerator;
+    use self::coinnect::poloniex::api::PoloniexApi;
+    use self::coinnect::transport::MockTransport;
+
+    use std::collections::HashSet;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::thread;
+
+    fn nonce_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("coinnect_nonce_{}", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn nonce_of(body: &str) -> u64 {
+        body.split('&')
+            .find(|param| param.starts_with("nonce="))
+            .and_then(|param| param[6..].parse().ok())
+            .unwrap()
+    }
+
+    #[test]
+    fn nonces_should_be_strictly_increasing() {
+        let nonces = NonceGenerator::new();
+
+        let mut last = nonces.next().unwrap();
+        for _ in 0..1000 {
+            let nonce = nonces.next().unwrap();
+            assert!(nonce > last);
+            last = nonce;
+        }
+    }
+
+    #[test]
+    fn clones_should_share_the_sequence_across_threads() {
+        let nonces = NonceGenerator::new();
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let nonces = nonces.clone();
+                thread::spawn(move || {
+                    (0..250).map(|_| nonces.next().unwrap()).collect::<Vec<u64>>()
+                })
+            })
+            .collect();
+        let mut all = HashSet::new();
+        for thread in threads {
+            for nonce in thread.join().unwrap() {
+                assert!(all.insert(nonce));
+            }
+        }
+        assert_eq!(all.len(), 1000);
+    }
+
+    #[test]
+    fn generators_using_the_same_file_should_share_the_sequence() {
+        let path = nonce_path("shared");
+        File::create(&path).unwrap().write_all(b"99999999999999").unwrap();
+        let first = NonceGenerator::with_file(path.clone());
+        let second = NonceGenerator::with_file(path.clone());
+
+        assert_eq!(first.next().unwrap(), 100000000000000);
+        assert_eq!(second.next().unwrap(), 100000000000001);
+        assert_eq!(first.next().unwrap(), 100000000000002);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn generators_using_the_same_file_concurrently_should_never_repeat_a_nonce() {
+        let path = nonce_path("concurrent");
+
+        // Separate generators do not share their memory, only the locked file
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let nonces = NonceGenerator::with_file(path.clone());
+                thread::spawn(move || {
+                    (0..50).map(|_| nonces.next().unwrap()).collect::<Vec<u64>>()
+                })
+            })
+            .collect();
+        let mut all = HashSet::new();
+        for thread in threads {
+            for nonce in thread.join().unwrap() {
+                assert!(all.insert(nonce));
+            }
+        }
+        assert_eq!(all.len(), 200);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_file_which_does_not_hold_a_nonce_should_return_an_error() {
+        let path = nonce_path("invalid");
+        File::create(&path).unwrap().write_all(b"not a nonce").unwrap();
+        let nonces = NonceGenerator::with_file(path.clone());
+
+        match *nonces.next().unwrap_err().kind() {
+            ErrorKind::NonceError(_) => (),
+            ref other => panic!("unexpected error: {:?}", other),
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn clients_sharing_a_generator_should_send_increasing_nonces() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/Balance", r#"{"error": [], "result": {}}"#);
+        transport.respond("command=returnCompleteBalances", r#"{}"#);
+        let nonces = NonceGenerator::new();
+        let config = ClientConfig::new().nonce_generator(nonces.clone());
+        let mut kraken =
+            KrakenApi::with_config("api_key", "c2VjcmV0", config.clone(), transport.clone());
+        let mut poloniex =
+            PoloniexApi::with_config("api_key", "api_secret", config, transport.clone());
+
+        kraken.balances().unwrap();
+        poloniex.balances().unwrap();
+        kraken.balances().unwrap();
+
+        let sent: Vec<u64> =
+            transport.requests().iter().map(|request| nonce_of(&request.body)).collect();
+        assert!(sent[0] < sent[1] && sent[1] < sent[2]);
+        assert!(nonces.next().unwrap() > sent[2]);
+    }
+}
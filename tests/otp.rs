@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod otp_tests {
+    extern crate coinnect;
+
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::exchange::ExchangeApi;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::otp::{Otp, Totp};
+    use self::coinnect::transport::MockTransport;
+
+    const RFC_SECRET: &'static [u8] = b"12345678901234567890";
+
+    #[test]
+    fn totp_should_match_the_rfc_6238_test_vectors() {
+        let totp = Totp::from_bytes(RFC_SECRET.to_vec()).digits(8);
+
+        assert_eq!(totp.at(59), "94287082");
+        assert_eq!(totp.at(1111111109), "07081804");
+        assert_eq!(totp.at(1234567890), "89005924");
+        assert_eq!(totp.at(2000000000), "69279037");
+    }
+
+    #[test]
+    fn base32_secrets_should_be_decoded() {
+        let from_base32 = Totp::new("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap();
+        let from_bytes = Totp::from_bytes(RFC_SECRET.to_vec());
+
+        assert_eq!(from_base32, from_bytes);
+        assert_eq!(from_base32.at(59), "287082");
+    }
+
+    #[test]
+    fn invalid_base32_secrets_should_return_an_error() {
+        assert_eq!(Otp::totp("not base32!").unwrap_err().kind(), &ErrorKind::InvalidArguments);
+        assert_eq!(Otp::totp("").unwrap_err().kind(), &ErrorKind::InvalidArguments);
+    }
+
+    #[test]
+    fn debug_should_not_show_the_password_or_the_secret() {
+        assert_eq!(format!("{:?}", Otp::password("hunter2")), "Password(..)");
+        assert_eq!(format!("{:?}", Otp::totp("GEZDGNBVGY3TQOJQ").unwrap()),
+                   "Totp(Totp { digits: 6, period: 30 })");
+    }
+
+    #[test]
+    fn kraken_private_requests_should_send_the_password() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/Balance", r#"{"error": [], "result": {}}"#);
+        let mut api = KrakenApi::with_transport("api_key", "c2VjcmV0", transport.clone())
+            .with_otp(Otp::password("hunter2"));
+
+        api.balances().unwrap();
+        assert!(transport.requests()[0].body.split('&').any(|param| param == "otp=hunter2"));
+    }
+
+    #[test]
+    fn the_password_should_be_percent_encoded() {
+        let transport = MockTransport::new();
+        transport.respond("/0/private/Balance", r#"{"error": [], "result": {}}"#);
+        let mut api = KrakenApi::with_transport("api_key", "c2VjcmV0", transport.clone())
+            .with_otp(Otp::password("p&ss=w rd+"));
+
+        api.balances().unwrap();
+        assert!(transport.requests()[0]
+            .body
+            .split('&')
+            .any(|param| param == "otp=p%26ss%3Dw%20rd%2B"));
+    }
+}
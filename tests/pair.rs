@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod pair_tests {
+    extern crate coinnect;
+
+    use self::coinnect::currency::Currency;
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::pair::Pair;
+
+    #[test]
+    fn currency_should_be_parsed_in_any_case() {
+        assert_eq!("btc".parse::<Currency>().unwrap(), Currency::BTC);
+        assert_eq!("NewCoin".parse::<Currency>().unwrap().code(), "NEWCOIN");
+        assert_eq!(Currency::new("newcoin"), Currency::new("NEWCOIN"));
+        assert_eq!("".parse::<Currency>().unwrap_err().kind(),
+                   &ErrorKind::InvalidArguments);
+        assert!("BTC/EUR".parse::<Currency>().is_err());
+    }
+
+    #[test]
+    fn pair_should_be_displayed_and_parsed() {
+        assert_eq!(Pair::BTC_EUR.to_string(), "BTC/EUR");
+        assert_eq!("BTC/EUR".parse::<Pair>().unwrap(), Pair::BTC_EUR);
+        assert_eq!("btc_eur".parse::<Pair>().unwrap(), Pair::BTC_EUR);
+        assert_eq!("ABC/XYZ".parse::<Pair>().unwrap(),
+                   Pair::new(Currency::new("ABC"), Currency::new("XYZ")));
+        assert!("BTCEUR".parse::<Pair>().is_err());
+        assert!("BTC/EUR/USD".parse::<Pair>().is_err());
+        assert!("BTC/".parse::<Pair>().is_err());
+    }
+
+    #[test]
+    fn inverse_should_swap_base_and_quote() {
+        let inverse = Pair::BTC_EUR.inverse();
+
+        assert_eq!(inverse.base, Currency::EUR);
+        assert_eq!(inverse.quote, Currency::BTC);
+        assert_eq!(inverse.inverse(), Pair::BTC_EUR);
+    }
+}
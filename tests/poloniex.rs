@@ -3,7 +3,153 @@
 mod poloniex_tests {
     extern crate coinnect;
 
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::decimal::Decimal;
     use self::coinnect::poloniex::api::PoloniexApi;
+    use self::coinnect::poloniex::error::PoloniexError;
+    use self::coinnect::poloniex::utils;
+    use self::coinnect::exchange::ExchangeApi;
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::pair::Pair;
+    use self::coinnect::transport::{MockTransport, Method};
+    use self::coinnect::types::{OrderRequest, OrderSide};
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    #[test]
+    fn get_pair_string_should_put_the_quote_currency_first() {
+        assert_eq!(utils::get_pair_string(&Pair::ETH_BTC), Some("BTC_ETH".to_string()));
+        assert_eq!(utils::get_pair_string(&Pair::XLM_BTC), Some("BTC_STR".to_string()));
+        assert_eq!(utils::get_pair_string(&Pair::ZEC_BTC.inverse()), None);
+        assert_eq!(utils::get_pair("BTC_ZEC"), Some(Pair::ZEC_BTC));
+        assert_eq!(utils::get_pair("USDT_STR"), Some("XLM/USDT".parse().unwrap()));
+        assert_eq!(utils::get_pair("BTC"), None);
+        assert_eq!(utils::get_pair("BTC_E-TH"), None);
+    }
+
+    #[test]
+    fn private_requests_should_be_signed_and_posted() {
+        let transport = MockTransport::new();
+        transport.respond("command=returnCompleteBalances",
+                          r#"{"BTC": {"available": "0.5", "onOrders": "0.25",
+                          "btcValue": "0.75"},
+                          "STR": {"available": "100", "onOrders": "0", "btcValue": "0.003"}}"#);
+        let mut api = PoloniexApi::with_transport("api_key", "api_secret", transport.clone());
+        let balances = api.balances().unwrap();
+
+        assert_eq!(balances.get("BTC").unwrap().total, dec("0.75"));
+        assert_eq!(balances.get("XLM").unwrap().available, Some(dec("100.0")));
+
+        let request = transport.last_request().unwrap();
+        assert_eq!(request.method, Method::Post);
+        assert_eq!(request.url, "https://poloniex.com/tradingApi");
+        assert_eq!(request.header("Key"), Some("api_key"));
+        assert_eq!(request.header("Sign").map(|sign| sign.len()), Some(128));
+    }
+
+    #[test]
+    fn requests_should_be_sent_to_the_configured_base_url() {
+        let transport = MockTransport::new();
+        transport.respond("command=returnTicker", "{}");
+        transport.respond("command=returnBalances", "{}");
+        let config = ClientConfig::new().base_url("http://127.0.0.1:8080");
+        let mut api = PoloniexApi::with_config("api_key", "api_secret", config, transport.clone());
+        api.return_ticker().unwrap();
+        api.return_balances().unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].url, "http://127.0.0.1:8080/public?command=returnTicker&");
+        assert_eq!(requests[1].url, "http://127.0.0.1:8080/tradingApi");
+    }
+
+    #[test]
+    fn malformed_responses_should_return_an_error() {
+        let transport = MockTransport::new();
+        transport.respond("command=returnTicker", r#"{"BTC_ETH": {"last": "abc"}}"#);
+        transport.respond("command=returnCompleteBalances", r#"{"error": 42}"#);
+        let mut api = PoloniexApi::with_transport("api_key", "api_secret", transport);
+
+        assert_eq!(api.ticker(Pair::ETH_BTC).unwrap_err().kind(), &ErrorKind::BadParse);
+        assert_eq!(api.balances().unwrap_err().kind(),
+                   &ErrorKind::ExchangeSpecificError("42".to_string()));
+
+        // Amounts overflowing a Decimal are errors, not panics
+        let transport = MockTransport::new();
+        transport.respond("command=returnCompleteBalances",
+                          r#"{"BTC": {"available": "170141183460469231731687303715884105727",
+                          "onOrders": "1", "btcValue": "0"}}"#);
+        let mut api = PoloniexApi::with_transport("api_key", "api_secret", transport);
+        assert_eq!(api.balances().unwrap_err().kind(), &ErrorKind::BadParse);
+    }
+
+    #[test]
+    fn poloniex_errors_should_be_mapped_to_their_kind() {
+        assert_eq!(PoloniexError::from_message("Nonce must be greater than 2. You provided 1."),
+                   PoloniexError::InvalidNonce);
+        assert_eq!(PoloniexError::from_message("Total must be at least 0.0001.").kind(),
+                   ErrorKind::InvalidOrder);
+        assert_eq!(PoloniexError::from_message("Invalid rate parameter.").kind(),
+                   ErrorKind::InvalidArguments);
+
+        let transport = MockTransport::new();
+        transport.respond("command=returnCompleteBalances",
+                          r#"{"error": "Invalid API key/secret pair."}"#);
+        let mut api = PoloniexApi::with_transport("api_key", "api_secret", transport);
+
+        assert_eq!(api.balances().unwrap_err().kind(), &ErrorKind::InvalidLogin);
+    }
+
+    #[test]
+    fn generic_place_order_should_return_the_order_number() {
+        let transport = MockTransport::new();
+        transport.respond("command=buy",
+                          r#"{"orderNumber": 31226040, "resultingTrades": []}"#);
+        transport.respond("command=returnTicker", r#"{"BTC_ETH": {"isFrozen": "0"}}"#);
+        transport.respond("command=returnCurrencies", "{}");
+        let mut api = PoloniexApi::with_transport("api_key", "api_secret", transport);
+        let order = OrderRequest::limit(Pair::ETH_BTC, OrderSide::Buy, dec("0.025"), dec("1.5"));
+
+        assert_eq!(api.place_order(order).unwrap().0, "31226040");
+    }
+
+    #[test]
+    fn generic_place_order_should_reject_market_orders() {
+        let transport = MockTransport::new();
+        let mut api = PoloniexApi::with_transport("api_key", "api_secret", transport.clone());
+        let order = OrderRequest::market(Pair::ETH_BTC, OrderSide::Buy, dec("1.5"));
+
+        match api.place_order(order) {
+            Err(ref e) if *e.kind() == ErrorKind::OrderTypeUnsupported => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(transport.requests().is_empty());
+    }
+
+    #[test]
+    fn trades_since_should_ask_for_a_range_and_flag_a_gap() {
+        let transport = MockTransport::new();
+        transport.respond("command=returnTradeHistory",
+                          r#"[{"date": "2017-04-11 14:21:07", "type": "buy", "rate": "0.0251",
+                          "amount": "0.5", "total": "0.01255", "tradeID": "2500"},
+                          {"date": "2017-04-11 14:20:52", "type": "sell", "rate": "0.025",
+                          "amount": "1.2", "total": "0.03", "tradeID": "2499"}]"#);
+        transport.respond("command=returnTicker", r#"{"BTC_ETH": {"isFrozen": "0"}}"#);
+        transport.respond("command=returnCurrencies", "{}");
+        let mut api = PoloniexApi::with_transport("", "", transport.clone());
+
+        let history = api.trades(Pair::ETH_BTC, Some("2000")).unwrap();
+        assert_eq!(history.trades.len(), 2);
+        assert!(history.gap);
+        let requests = transport.requests();
+        let request = requests.iter().find(|r| r.url.contains("returnTradeHistory")).unwrap();
+        assert!(request.url.contains("start=") && request.url.contains("end="));
+
+        let history = api.trades(Pair::ETH_BTC, Some("2499")).unwrap();
+        assert_eq!(history.trades.len(), 1);
+        assert!(!history.gap);
+    }
 
     /// IMPORTANT: Real keys are needed in order to retrieve the balance
     #[test]
@@ -11,9 +157,21 @@ mod poloniex_tests {
     fn balance_has_btc_key() {
         use std::path::PathBuf;
         let path = PathBuf::from("./keys_real.json");
-        let mut api = PoloniexApi::new_from_file("account_poloniex", path);
+        let mut api = PoloniexApi::new_from_file("account_poloniex", path).unwrap();
         let result = api.return_balances();
 
         assert!(result.unwrap().contains_key("BTC"));
     }
-}
\ No newline at end of file
+
+    /// IMPORTANT: Real keys are needed in order to retrieve the balance
+    #[test]
+    #[cfg_attr(not(feature = "poloniex_private_tests"), ignore)]
+    fn generic_balances_has_btc_key() {
+        use std::path::PathBuf;
+        let path = PathBuf::from("./keys_real.json");
+        let mut api = PoloniexApi::new_from_file("account_poloniex", path).unwrap();
+        let result = api.balances().unwrap();
+
+        assert!(result.get("BTC").is_some());
+    }
+}
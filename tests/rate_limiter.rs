@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod rate_limiter_tests {
+    extern crate coinnect;
+
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::kraken::utils;
+    use self::coinnect::rate_limiter::RateLimiter;
+    use self::coinnect::transport::MockTransport;
+
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn a_full_bucket_should_allow_a_burst_of_requests() {
+        let limiter = RateLimiter::new(3.0, 1.0);
+
+        assert!(limiter.try_acquire(1.0).is_ok());
+        assert!(limiter.try_acquire(2.0).is_ok());
+        assert!(limiter.try_acquire(1.0).is_err());
+    }
+
+    #[test]
+    fn an_empty_bucket_should_return_the_remaining_wait() {
+        let limiter = RateLimiter::new(2.0, 0.5);
+        limiter.try_acquire(2.0).unwrap();
+
+        let wait = limiter.try_acquire(1.0).unwrap_err();
+        assert!(wait > Duration::from_millis(1900));
+        assert!(wait <= Duration::from_millis(2001));
+    }
+
+    #[test]
+    #[should_panic]
+    fn a_bucket_never_refilled_should_be_rejected() {
+        RateLimiter::new(1.0, 0.0);
+    }
+
+    #[test]
+    fn acquire_should_wait_for_the_bucket_to_refill() {
+        let limiter = RateLimiter::new(1.0, 20.0);
+        let start = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire(1.0);
+        }
+
+        // The first token is available at once, the 3 others take 50ms each
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(150));
+        assert!(elapsed < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn clones_should_share_the_same_bucket_across_threads() {
+        let limiter = RateLimiter::new(10.0, 0.001);
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let limiter = limiter.clone();
+                thread::spawn(move || limiter.try_acquire(2.0).is_ok())
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+
+        assert!(limiter.try_acquire(1.0).is_err());
+    }
+
+    #[test]
+    fn kraken_call_counter_should_depend_on_the_method() {
+        assert_eq!(utils::call_cost("Balance"), 1.0);
+        assert_eq!(utils::call_cost("TradesHistory"), 2.0);
+        assert_eq!(utils::call_cost("AddOrder"), 0.0);
+    }
+
+    #[test]
+    fn clients_should_share_the_configured_rate_limiters() {
+        let transport = MockTransport::new();
+        transport.respond("/0/", r#"{"error": [], "result": {}}"#);
+        let public = RateLimiter::new(5.0, 0.001);
+        let private = RateLimiter::new(15.0, 0.001);
+        let config = ClientConfig::new()
+            .rate_limiter(public.clone())
+            .private_rate_limiter(private.clone());
+        let mut api_1 = KrakenApi::with_config("", "c2VjcmV0", config.clone(), transport.clone());
+        let mut api_2 = KrakenApi::with_config("", "c2VjcmV0", config, transport);
+
+        api_1.get_server_time().unwrap();
+        api_2.get_server_time().unwrap();
+        api_1.get_account_balance().unwrap();
+        api_2.get_trades_history("", "", "", "", "").unwrap();
+
+        assert!(public.available() < 3.01);
+        assert!(private.available() < 12.01);
+    }
+}
@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod retry_tests {
+    extern crate coinnect;
+
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::error::{Error, ErrorKind};
+    use self::coinnect::exchange::ExchangeApi;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::pair::Pair;
+    use self::coinnect::poloniex::api::PoloniexApi;
+    use self::coinnect::retry::RetryPolicy;
+    use self::coinnect::transport::{MockTransport, HttpResponse};
+    use self::coinnect::types::{OrderRequest, OrderSide};
+
+    use std::time::Duration;
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    fn fast_retries() -> ClientConfig {
+        ClientConfig::new().retry_policy(RetryPolicy::new().initial_delay(Duration::from_millis(1)))
+    }
+
+    fn server_error() -> HttpResponse {
+        HttpResponse {
+            status: 502,
+            body: "<html>Bad gateway</html>".to_string(),
+        }
+    }
+
+    #[test]
+    fn delays_should_double_up_to_the_maximum() {
+        let policy = RetryPolicy::new()
+            .initial_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500))
+            .jitter(false);
+
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(1), Duration::from_millis(200));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(3), Duration::from_millis(500));
+        assert_eq!(policy.delay(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_should_keep_at_least_half_of_the_delay() {
+        let policy = RetryPolicy::new().initial_delay(Duration::from_millis(100));
+
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn huge_maximum_delays_should_not_overflow() {
+        let policy = RetryPolicy::new()
+            .initial_delay(Duration::from_secs(u64::max_value()))
+            .max_delay(Duration::from_secs(u64::max_value()));
+
+        assert!(policy.delay(3) >= Duration::from_millis(u64::max_value() / 2));
+        assert!(format!("{:?}", policy).contains("max_delay_ms: 18446744073709551615"));
+    }
+
+    #[test]
+    fn run_should_stop_at_the_first_result_which_is_not_retryable() {
+        let policy = RetryPolicy::new().max_attempts(5).initial_delay(Duration::from_millis(1));
+        let mut calls = 0;
+
+        let result = policy.run(|| {
+                                    calls += 1;
+                                    match calls {
+                                        1 => Err(Error::new(ErrorKind::ServiceUnavailable)),
+                                        2 => Err(Error::new(ErrorKind::RateLimitExceeded)),
+                                        _ => Err(Error::new(ErrorKind::InvalidArguments)),
+                                    }
+                                },
+                                |result: &Result<(), Error>| {
+                                    result.as_ref().err().map_or(false, |e| e.is_retryable())
+                                });
+
+        assert_eq!(result.unwrap_err().kind(), &ErrorKind::InvalidArguments);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn public_requests_should_be_retried_after_a_server_error() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Ticker",
+                          r#"{"error": [], "result": {"XXBTZEUR": {"c": ["1080.0", "1"],
+                              "a": ["1081.0", "1"], "b": ["1079.0", "1"],
+                              "v": ["10.0", "100.0"]}}}"#);
+        transport.respond_once_with("/0/public/Ticker", server_error());
+        transport.respond_once_with("/0/public/Ticker",
+                                    HttpResponse::ok(r#"{"error": ["EService:Unavailable"]}"#));
+        let mut api = KrakenApi::with_config("", "", fast_retries(), transport.clone());
+
+        assert_eq!(api.ticker(Pair::BTC_EUR).unwrap().last_trade_price, dec("1080.0"));
+        let tickers = transport.requests().iter().filter(|r| r.url.contains("Ticker")).count();
+        assert_eq!(tickers, 3);
+    }
+
+    #[test]
+    fn requests_should_be_sent_at_most_max_attempts_times() {
+        let transport = MockTransport::new();
+        transport.respond_with("/0/public/Time", server_error());
+        let mut api = KrakenApi::with_config("", "", fast_retries(), transport.clone());
+
+        assert_eq!(api.get_server_time().unwrap_err().status(), Some(502));
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[test]
+    fn read_only_private_requests_should_be_retried_with_a_new_nonce() {
+        let transport = MockTransport::new();
+        transport.respond("command=returnCompleteBalances", r#"{"BTC": {"available": "1.0",
+                          "onOrders": "0.0", "btcValue": "1.0"}}"#);
+        let throttled = r#"{"error": "Please do not make more than 6 API calls per second."}"#;
+        transport.respond_once_with("command=returnCompleteBalances", HttpResponse::ok(throttled));
+        let mut api = PoloniexApi::with_config("api_key",
+                                               "api_secret",
+                                               fast_retries(),
+                                               transport.clone());
+
+        assert!(api.balances().is_ok());
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].body != requests[1].body);
+    }
+
+    #[test]
+    fn orders_should_never_be_sent_twice() {
+        let transport = MockTransport::new();
+        transport.respond_with("/0/private/AddOrder", server_error());
+        transport.respond_with("command=buy", server_error());
+        transport.respond("/0/public/AssetPairs",
+                          r#"{"error": [], "result": {"XXBTZEUR": {"base": "XXBT",
+                          "quote": "ZEUR", "pair_decimals": 1, "lot_decimals": 8,
+                          "fees": [[0, 0.26]]}}}"#);
+        transport.respond("command=returnTicker", r#"{"BTC_ETH": {"isFrozen": "0"}}"#);
+        transport.respond("command=returnCurrencies", "{}");
+        let mut kraken = KrakenApi::with_config("api_key",
+                                                "c2VjcmV0",
+                                                fast_retries(),
+                                                transport.clone());
+        let mut poloniex = PoloniexApi::with_config("api_key",
+                                                    "api_secret",
+                                                    fast_retries(),
+                                                    transport.clone());
+
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("1000.0"), dec("1.0"));
+        assert!(kraken.place_order(order).unwrap_err().is_retryable());
+        let order = OrderRequest::limit(Pair::ETH_BTC, OrderSide::Buy, dec("0.05"), dec("1.0"));
+        assert!(poloniex.place_order(order).unwrap_err().is_retryable());
+        assert!(kraken.withdraw_funds("currency", "XBT", "key", "1.0").is_err());
+        // The markets retrieved to name the pairs are public requests, which are retried
+        let private = transport.requests()
+            .iter()
+            .filter(|r| r.url.contains("/private/") || r.body.contains("command="))
+            .count();
+        assert_eq!(private, 3);
+    }
+}
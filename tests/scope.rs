@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod scope_tests {
+    extern crate coinnect;
+
+    use self::coinnect::bitstamp::api::BitstampApi;
+    use self::coinnect::coinnect::Coinnect;
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::exchange::Exchange;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::pair::Pair;
+    use self::coinnect::poloniex::api::PoloniexApi;
+    use self::coinnect::scope::Scope;
+    use self::coinnect::transport::MockTransport;
+    use self::coinnect::types::{OrderRequest, OrderSide};
+
+    use std::collections::HashMap;
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    fn scoped(scope: Scope) -> ClientConfig {
+        ClientConfig::new().scope(scope)
+    }
+
+    fn exceeded(required: Scope, allowed: Scope) -> ErrorKind {
+        ErrorKind::ScopeExceeded {
+            required: required,
+            allowed: allowed,
+        }
+    }
+
+    #[test]
+    fn each_scope_should_allow_the_previous_ones() {
+        assert!(Scope::ReadOnly.allows(Scope::ReadOnly));
+        assert!(!Scope::ReadOnly.allows(Scope::Trade));
+        assert!(Scope::Trade.allows(Scope::ReadOnly));
+        assert!(!Scope::Trade.allows(Scope::Withdraw));
+        assert!(Scope::Withdraw.allows(Scope::Trade));
+        assert_eq!(Scope::default(), Scope::Withdraw);
+    }
+
+    #[test]
+    fn read_only_clients_should_not_send_orders() {
+        let transport = MockTransport::new();
+        transport.respond("command=returnCompleteBalances", r#"{}"#);
+        transport.respond("/0/private/Balance", r#"{"error": [], "result": {}}"#);
+        transport.respond("/0/public/AssetPairs",
+                          r#"{"error": [], "result": {"XXBTZEUR": {"base": "XXBT",
+                          "quote": "ZEUR", "pair_decimals": 1, "lot_decimals": 8,
+                          "fees": [[0, 0.26]]}}}"#);
+        transport.respond("command=returnTicker", r#"{"BTC_ETH": {"isFrozen": "0"}}"#);
+        transport.respond("command=returnCurrencies", "{}");
+        let mut poloniex = Coinnect::with_config(Exchange::Poloniex,
+                                                 "api_key",
+                                                 "api_secret",
+                                                 None,
+                                                 scoped(Scope::ReadOnly),
+                                                 transport.clone());
+        let mut kraken = Coinnect::with_config(Exchange::Kraken,
+                                               "api_key",
+                                               "c2VjcmV0",
+                                               None,
+                                               scoped(Scope::ReadOnly),
+                                               transport.clone());
+
+        assert!(poloniex.balances().is_ok());
+        assert!(kraken.balances().is_ok());
+        let order = OrderRequest::limit(Pair::ETH_BTC, OrderSide::Buy, dec("0.05"), dec("1.0"));
+        let error = poloniex.place_order(order).unwrap_err();
+        assert_eq!(error.kind(), &exceeded(Scope::Trade, Scope::ReadOnly));
+        assert_eq!(error.endpoint(), Some("buy"));
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Sell, dec("1000.0"), dec("1.0"));
+        assert_eq!(kraken.place_order(order).unwrap_err().kind(),
+                   &exceeded(Scope::Trade, Scope::ReadOnly));
+        let private = transport.requests()
+            .iter()
+            .filter(|r| r.url.contains("/private/") || r.body.contains("command="))
+            .count();
+        assert_eq!(private, 2);
+    }
+
+    #[test]
+    fn trading_clients_should_not_withdraw() {
+        let transport = MockTransport::new();
+        let mut kraken =
+            KrakenApi::with_config("api_key", "c2VjcmV0", scoped(Scope::Trade), transport.clone());
+        let mut poloniex = PoloniexApi::with_config("api_key",
+                                                    "api_secret",
+                                                    scoped(Scope::Trade),
+                                                    transport.clone());
+        let mut params = HashMap::new();
+        params.insert("api_key", "api_key");
+        params.insert("api_secret", "api_secret");
+        params.insert("customer_id", "123456");
+        let mut bitstamp =
+            BitstampApi::with_config(&params, scoped(Scope::Trade), transport.clone());
+
+        let withdraw = exceeded(Scope::Withdraw, Scope::Trade);
+        assert_eq!(kraken.withdraw_funds("currency", "XBT", "key", "1.0").unwrap_err().kind(),
+                   &withdraw);
+        assert_eq!(poloniex.withdraw("BTC", "1.0", "address").unwrap_err().kind(),
+                   &withdraw);
+        assert_eq!(poloniex.transfer_balance("BTC", "1.0", "exchange", "margin")
+                       .unwrap_err()
+                       .kind(),
+                   &withdraw);
+        assert_eq!(bitstamp.bitcoin_withdrawal("1.0", "address", "0").unwrap_err().kind(),
+                   &withdraw);
+        assert!(transport.requests().is_empty());
+    }
+
+    #[test]
+    fn scope_errors_should_tell_what_was_refused() {
+        let mut api = KrakenApi::with_config("api_key",
+                                             "c2VjcmV0",
+                                             scoped(Scope::ReadOnly),
+                                             MockTransport::new());
+
+        assert_eq!(api.cancel_open_order("OQCLML-BW3P3-BUCMWZ").unwrap_err().to_string(),
+                   "The client scope does not allow this call. (Kraken CancelOrder): \
+                    The call requires the Trade scope, the client is limited to ReadOnly");
+    }
+}
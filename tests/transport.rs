@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod transport_tests {
+    extern crate coinnect;
+
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::exchange::ExchangeApi;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::pair::Pair;
+    use self::coinnect::transport::{CassetteTransport, MockTransport};
+
+    use std::env;
+    use std::fs::File;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    fn cassette_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("coinnect_{}.json", name))
+    }
+
+    fn mock_kraken() -> MockTransport {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Depth",
+                          r#"{"error": [], "result": {"XXBTZEUR": {
+                          "asks": [["1120.00000", "1.000", 1491919199]],
+                          "bids": [["1119.00000", "2.000", 1491919199]]}}}"#);
+        transport.respond("/0/private/Balance",
+                          r#"{"error": [], "result": {"XXBT": "1.5000000000",
+                          "ZEUR": "100.0000"}}"#);
+        transport
+    }
+
+    #[test]
+    fn cassette_should_replay_a_recorded_session() {
+        let path = cassette_path("replay");
+        let transport = CassetteTransport::record(path.clone(), mock_kraken());
+        let mut api = KrakenApi::with_transport("api_key", "c2VjcmV0", transport);
+        let recorded = api.orderbook(Pair::BTC_EUR).unwrap();
+        api.balances().unwrap();
+
+        let transport = CassetteTransport::replay(path).unwrap();
+        let mut api = KrakenApi::with_transport("other_key", "b3RoZXI=", transport);
+        let replayed = api.orderbook(Pair::BTC_EUR).unwrap();
+        let balances = api.balances().unwrap();
+
+        assert_eq!(replayed.asks, recorded.asks);
+        assert_eq!(replayed.bids, recorded.bids);
+        assert_eq!(balances.get("BTC").unwrap().total, dec("1.5"));
+    }
+
+    #[test]
+    fn cassette_should_not_contain_credentials() {
+        let path = cassette_path("redaction");
+        let transport = CassetteTransport::record(path.clone(), mock_kraken());
+        let mut api = KrakenApi::with_transport("my_api_key", "c2VjcmV0", transport);
+        api.balances().unwrap();
+
+        let mut content = String::new();
+        File::open(&path).unwrap().read_to_string(&mut content).unwrap();
+
+        assert!(!content.contains("my_api_key"));
+        assert!(content.contains("nonce=REDACTED"));
+        assert!(content.contains("\"API-Sign\""));
+    }
+
+    #[test]
+    fn cassette_should_fail_on_unexpected_requests() {
+        let path = cassette_path("unexpected");
+        let transport = CassetteTransport::record(path.clone(), mock_kraken());
+        let mut api = KrakenApi::with_transport("", "", transport);
+        api.orderbook(Pair::BTC_EUR).unwrap();
+
+        let transport = CassetteTransport::replay(path).unwrap();
+        let mut api = KrakenApi::with_transport("", "", transport);
+
+        assert!(api.orderbook(Pair::ETH_EUR).is_err());
+        assert!(api.orderbook(Pair::BTC_EUR).is_ok());
+        // Each recorded response is only served once
+        assert!(api.orderbook(Pair::BTC_EUR).is_err());
+    }
+
+    #[test]
+    fn replaying_a_missing_cassette_should_fail() {
+        assert!(CassetteTransport::replay(cassette_path("missing")).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+#[cfg(test)]
+mod validator_tests {
+    extern crate coinnect;
+
+    use self::coinnect::config::ClientConfig;
+    use self::coinnect::decimal::Decimal;
+    use self::coinnect::error::ErrorKind;
+    use self::coinnect::exchange::ExchangeApi;
+    use self::coinnect::kraken::api::KrakenApi;
+    use self::coinnect::market::MarketCache;
+    use self::coinnect::pair::Pair;
+    use self::coinnect::transport::MockTransport;
+    use self::coinnect::types::{Market, MarketInfo, OrderRequest, OrderSide, OrderType,
+                                Ticker};
+    use self::coinnect::validator::{OrderValidator, Rejection, Rounding};
+
+    fn dec(number: &str) -> Decimal {
+        number.parse().unwrap()
+    }
+
+    fn btc_eur() -> MarketInfo {
+        MarketInfo {
+            price_decimals: 1,
+            volume_decimals: 8,
+            min_volume: Some(dec("0.002")),
+            min_value: Some(dec("5.0")),
+            leverage: Vec::new(),
+            maker_fee: 0.0016,
+            taker_fee: 0.0026,
+        }
+    }
+
+    fn ticker(last_trade_price: Decimal) -> Ticker {
+        Ticker {
+            timestamp: 0,
+            pair: Pair::BTC_EUR,
+            last_trade_price: last_trade_price,
+            lowest_ask: last_trade_price,
+            highest_bid: last_trade_price,
+            volume: None,
+        }
+    }
+
+    fn rejection(result: Result<OrderRequest, coinnect::error::Error>) -> Rejection {
+        match result.unwrap_err().kind() {
+            &ErrorKind::OrderRejected(ref rejection) => rejection.clone(),
+            kind => panic!("Unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn numbers_should_be_rounded_as_specified() {
+        assert_eq!(Rounding::Nearest.round(dec("1000.15"), 1, OrderSide::Buy), dec("1000.2"));
+        assert_eq!(Rounding::Down.round(dec("1000.19"), 1, OrderSide::Sell), dec("1000.1"));
+        assert_eq!(Rounding::Up.round(dec("1000.11"), 1, OrderSide::Buy), dec("1000.2"));
+        assert_eq!(Rounding::Passive.round(dec("1000.15"), 1, OrderSide::Buy), dec("1000.1"));
+        assert_eq!(Rounding::Passive.round(dec("1000.15"), 1, OrderSide::Sell), dec("1000.2"));
+        assert_eq!(Rounding::Down.round(dec("-0.15"), 1, OrderSide::Buy), dec("-0.2"));
+        assert_eq!(Rounding::Up.round(dec("0.123456789"), 8, OrderSide::Buy), dec("0.12345679"));
+    }
+
+    #[test]
+    fn orders_should_be_rounded_to_the_precision_of_the_market() {
+        let validator = OrderValidator::new();
+        let order = OrderRequest::limit(Pair::BTC_EUR,
+                                        OrderSide::Sell,
+                                        dec("1000.04"),
+                                        dec("0.123456789"));
+
+        let order = validator.validate(order, Some(&btc_eur()), None).unwrap();
+        assert_eq!(order.price, Some(dec("1000.1")));
+        assert_eq!(order.volume, dec("0.12345678"));
+        // Without market rules, orders are sent as they are
+        let order = OrderRequest::limit(Pair::BTC_EUR,
+                                        OrderSide::Sell,
+                                        dec("1000.04"),
+                                        dec("0.123456789"));
+        assert_eq!(validator.validate(order, None, None).unwrap().price, Some(dec("1000.04")));
+    }
+
+    #[test]
+    fn malformed_orders_should_be_rejected() {
+        let validator = OrderValidator::new();
+        let order = OrderRequest {
+            pair: Pair::BTC_EUR,
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            price: None,
+            volume: dec("1.0"),
+        };
+
+        assert_eq!(rejection(validator.validate(order, None, None)),
+                   Rejection::MissingPrice);
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("-1000.0"), dec("1.0"));
+        assert_eq!(rejection(validator.validate(order, None, None)),
+                   Rejection::InvalidPrice(dec("-1000.0")));
+        let order = OrderRequest::market(Pair::BTC_EUR, OrderSide::Buy, Decimal::ZERO);
+        assert_eq!(rejection(validator.validate(order, None, None)),
+                   Rejection::InvalidVolume(Decimal::ZERO));
+        // A price rounded to zero is invalid too
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("0.01"), dec("1.0"));
+        assert_eq!(rejection(validator.validate(order, Some(&btc_eur()), None)),
+                   Rejection::InvalidPrice(dec("0.01")));
+    }
+
+    #[test]
+    fn orders_below_the_minimum_of_the_market_should_be_rejected() {
+        let validator = OrderValidator::new();
+
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("1000.0"), dec("0.001"));
+        assert_eq!(rejection(validator.validate(order, Some(&btc_eur()), None)),
+                   Rejection::VolumeBelowMinimum {
+                       volume: dec("0.001"),
+                       minimum: dec("0.002"),
+                   });
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("1000.0"), dec("0.004"));
+        assert_eq!(rejection(validator.validate(order, Some(&btc_eur()), None)),
+                   Rejection::ValueBelowMinimum {
+                       value: dec("4.0"),
+                       minimum: dec("5.0"),
+                   });
+        // The value of market orders is estimated from the Ticker
+        let order = OrderRequest::market(Pair::BTC_EUR, OrderSide::Buy, dec("0.004"));
+        assert!(validator.validate(order.clone(), Some(&btc_eur()), None).is_ok());
+        assert!(validator.validate(order, Some(&btc_eur()), Some(&ticker(dec("1000.0")))).is_err());
+    }
+
+    #[test]
+    fn the_ticker_should_be_retrieved_to_check_the_value_of_market_orders() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Ticker",
+                          r#"{"error": [], "result": {"XXBTZEUR": {"a": ["1001", "1", "1.000"],
+                          "b": ["999", "1", "1.000"], "c": ["1000", "0.1"],
+                          "v": ["10", "20"]}}}"#);
+        let mut api = KrakenApi::with_transport("", "", transport.clone());
+        let validator = OrderValidator::new();
+        let tickers = || transport.requests().iter().filter(|r| r.url.contains("Ticker")).count();
+
+        let order = OrderRequest::market(Pair::BTC_EUR, OrderSide::Buy, dec("0.004"));
+        assert_eq!(rejection(validator.check(&mut api, Some(&btc_eur()), order)),
+                   Rejection::ValueBelowMinimum {
+                       value: dec("4.0"),
+                       minimum: dec("5.0"),
+                   });
+        assert_eq!(tickers(), 1);
+        // Limit orders are valued at their own price
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("1000.0"), dec("0.01"));
+        assert!(validator.check(&mut api, Some(&btc_eur()), order).is_ok());
+        assert_eq!(tickers(), 1);
+    }
+
+    #[test]
+    fn prices_outside_the_band_should_be_rejected() {
+        let validator = OrderValidator::new().price_band(0.1);
+
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("1100.0"), dec("1.0"));
+        assert!(validator.validate(order, None, Some(&ticker(dec("1000.0")))).is_ok());
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("1200.0"), dec("1.0"));
+        assert_eq!(rejection(validator.validate(order, None, Some(&ticker(dec("1000.0"))))),
+                   Rejection::PriceOutsideBand {
+                       price: dec("1200.0"),
+                       last_trade_price: dec("1000.0"),
+                       band: 0.1,
+                   });
+    }
+
+    #[test]
+    #[should_panic]
+    fn a_band_which_is_not_a_number_should_be_rejected() {
+        OrderValidator::new().price_band(::std::f64::NAN);
+    }
+
+    #[test]
+    #[should_panic]
+    fn a_negative_band_should_be_rejected() {
+        OrderValidator::new().price_band(-0.1);
+    }
+
+    #[test]
+    fn clients_should_validate_orders_before_sending_them() {
+        let transport = MockTransport::new();
+        transport.respond("/0/public/Ticker",
+                          r#"{"error": [], "result": {"XXBTZEUR": {"a": ["1001", "1", "1.000"],
+                          "b": ["999", "1", "1.000"], "c": ["1000", "0.1"],
+                          "v": ["10", "20"]}}}"#);
+        transport.respond("/0/private/AddOrder",
+                          r#"{"error": [], "result": {"txid": ["OAVY7T-MV5VK-KHDF5X"]}}"#);
+        let cache = MarketCache::default();
+        cache.set(vec![Market {
+                           pair: Pair::BTC_EUR,
+                           symbol: "XXBTZEUR".to_string(),
+                           info: btc_eur(),
+                       }]);
+        let config = ClientConfig::new()
+            .market_cache(cache)
+            .order_validator(OrderValidator::new().price_band(0.05));
+        let mut api = KrakenApi::with_config("api_key", "c2VjcmV0", config, transport.clone());
+
+        let order = OrderRequest::limit(Pair::BTC_EUR,
+                                        OrderSide::Buy,
+                                        dec("999.96"),
+                                        dec("0.0123456789"));
+        api.place_order(order).unwrap();
+        let body = transport.last_request().unwrap().body;
+        assert!(body.contains("price=999.9"));
+        assert!(body.contains("volume=0.01234567"));
+
+        let order = OrderRequest::limit(Pair::BTC_EUR, OrderSide::Buy, dec("500.0"), dec("1.0"));
+        assert_eq!(api.place_order(order).unwrap_err().kind(),
+                   &ErrorKind::OrderRejected(Rejection::PriceOutsideBand {
+                       price: dec("500.0"),
+                       last_trade_price: dec("1000.0"),
+                       band: 0.05,
+                   }));
+        // Only the Ticker was requested
+        assert!(transport.last_request().unwrap().url.contains("Ticker"));
+        assert_eq!(transport.requests().len(), 3);
+    }
+}